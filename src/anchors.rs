@@ -0,0 +1,253 @@
+//! Anchors: source-code and document references in node descriptions
+//!
+//! This module provides:
+//! - `extract`: file anchors in a description — plain markdown links `[text](path)` and
+//!   bare paths such as `src/cache.rs`, each optionally followed by `:LINE`, `:START-END`
+//!   or `#symbol` (`[N](path)` node references and URLs are not anchors)
+//! - `check`: whether an anchor's file exists inside the workspace (same rules as
+//!   `MindmapCache::resolve_path`), its line is within the file, and its symbol (a
+//!   heading slug for markdown, a whole word otherwise) still occurs there
+
+use std::{fs, ops::Range, path::Path, sync::LazyLock};
+
+use crate::cache::MindmapCache;
+
+static LINK: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"!?\[([^\]\n]*)\]\(([^)\s]+)\)").unwrap());
+
+static URL: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"[A-Za-z][A-Za-z0-9+.-]*://\S+").unwrap());
+
+/// A path with at least one `/` and a file extension, not glued to other path characters
+static BARE_PATH: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(
+        r"(?:^|[^\w./-])((?:[\w.-]+/)+[\w-][\w.-]*\.[A-Za-z0-9]+)(?::(\d+)(?:-(\d+))?)?(?:#([\w-]+))?",
+    )
+    .unwrap()
+});
+
+/// A line or line range within the anchored file (1-based, inclusive)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lines {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// One file anchor found in a description
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Anchor {
+    /// Byte range of the whole anchor in the description
+    pub range: Range<usize>,
+    pub path: String,
+    pub lines: Option<Lines>,
+    pub symbol: Option<String>,
+}
+
+/// Every file anchor in `description`, in order of appearance
+pub fn extract(description: &str) -> Vec<Anchor> {
+    let mut out = Vec::new();
+    // link and URL bytes are blanked so bare paths are not found inside them again
+    let mut masked = description.to_string();
+    let mut mask = |range: Range<usize>| {
+        masked.replace_range(range.clone(), &" ".repeat(range.len()));
+    };
+
+    for c in LINK.captures_iter(description) {
+        let whole = c.get(0).unwrap().range();
+        let text = &c[1];
+        let target = &c[2];
+        mask(whole.clone());
+        let is_node_ref = !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit());
+        if is_node_ref || target.starts_with('#') || target.contains(':') {
+            // node references, same-document fragments, URLs and mailto: links
+            continue;
+        }
+        let (path, symbol) = match target.split_once('#') {
+            Some((p, s)) => (p, Some(s.to_string())),
+            None => (target, None),
+        };
+        out.push(Anchor {
+            range: whole,
+            path: path.to_string(),
+            lines: None,
+            symbol,
+        });
+    }
+    for m in URL.find_iter(description) {
+        mask(m.range());
+    }
+
+    for c in BARE_PATH.captures_iter(&masked) {
+        let path = c.get(1).unwrap();
+        let end = c.get(0).unwrap().end();
+        let lines = c.get(2).and_then(|s| s.as_str().parse().ok()).map(|start| {
+            let end = c
+                .get(3)
+                .and_then(|e| e.as_str().parse().ok())
+                .unwrap_or(start);
+            Lines { start, end }
+        });
+        out.push(Anchor {
+            range: path.start()..end,
+            path: path.as_str().to_string(),
+            lines,
+            symbol: c.get(4).map(|s| s.as_str().to_string()),
+        });
+    }
+    out.sort_by_key(|a| a.range.start);
+    out
+}
+
+/// What checking an anchor found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnchorStatus {
+    Ok,
+    /// No such file
+    Missing,
+    /// The path exists but is absolute or resolves outside the workspace
+    OutsideWorkspace,
+    /// The anchored line is past the end of the file, which has this many lines
+    PastEnd(usize),
+    /// The symbol no longer occurs in the file
+    MissingSymbol,
+}
+
+/// Check `anchor`, resolving its path relative to `base_file` within `cache`'s workspace.
+/// Line and symbol checks are skipped for directories and files over `max_file_size`.
+pub fn check(
+    cache: &MindmapCache,
+    base_file: &Path,
+    anchor: &Anchor,
+    max_file_size: u64,
+) -> AnchorStatus {
+    let canonical = match cache.resolve_path(base_file, &anchor.path) {
+        Ok(p) => p,
+        Err(_) => {
+            let base_dir = base_file.parent().unwrap_or(Path::new("."));
+            return if base_dir.join(&anchor.path).exists() {
+                AnchorStatus::OutsideWorkspace
+            } else {
+                AnchorStatus::Missing
+            };
+        }
+    };
+    if anchor.lines.is_none() && anchor.symbol.is_none() {
+        return AnchorStatus::Ok;
+    }
+    let Ok(meta) = fs::metadata(&canonical) else {
+        return AnchorStatus::Missing;
+    };
+    if !meta.is_file() || meta.len() > max_file_size {
+        return AnchorStatus::Ok;
+    }
+    let Ok(content) = fs::read_to_string(&canonical) else {
+        // binary files can only be checked for existence
+        return AnchorStatus::Ok;
+    };
+
+    if let Some(lines) = anchor.lines {
+        let total = content.lines().count();
+        if lines.start.max(lines.end) > total {
+            return AnchorStatus::PastEnd(total);
+        }
+    }
+    if let Some(symbol) = &anchor.symbol {
+        let is_markdown = canonical
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("md"));
+        let found = if is_markdown {
+            content
+                .lines()
+                .filter_map(|l| l.trim_start().strip_prefix('#'))
+                .any(|h| heading_slug(h.trim_start_matches('#')) == symbol.to_lowercase())
+        } else {
+            contains_word(&content, symbol)
+        };
+        if !found {
+            return AnchorStatus::MissingSymbol;
+        }
+    }
+    AnchorStatus::Ok
+}
+
+/// GitHub-style heading anchor: lowercase, punctuation dropped, spaces to `-`
+pub fn heading_slug(heading: &str) -> String {
+    heading
+        .trim()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c.to_ascii_lowercase()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn contains_word(haystack: &str, word: &str) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    haystack.match_indices(word).any(|(i, _)| {
+        !haystack[..i].chars().next_back().is_some_and(is_word)
+            && !haystack[i + word.len()..]
+                .chars()
+                .next()
+                .is_some_and(is_word)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn test_extract_anchors() {
+        let desc = "Location: src/cache.rs:120 and `src/lib.rs#cmd_lint`; see [DESIGN](./DESIGN.md#goals), [4](./other.md), [web](https://example.com/a/b.md) or https://example.com/x/y.rs. Not add/put.";
+        let anchors = extract(desc);
+        let paths: Vec<&str> = anchors.iter().map(|a| a.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/cache.rs", "src/lib.rs", "./DESIGN.md"]);
+        assert_eq!(&desc[anchors[0].range.clone()], "src/cache.rs:120");
+        assert_eq!(
+            anchors[0].lines,
+            Some(Lines {
+                start: 120,
+                end: 120
+            })
+        );
+        assert_eq!(anchors[1].symbol.as_deref(), Some("cmd_lint"));
+        assert_eq!(anchors[2].symbol.as_deref(), Some("goals"));
+
+        // link text that looks like a path is not reported twice
+        let anchors = extract("[planning/A.md](./proj/planning/A.md)");
+        assert_eq!(anchors.len(), 1);
+        assert_eq!(anchors[0].path, "./proj/planning/A.md");
+    }
+
+    #[test]
+    fn test_check_anchors() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        temp.child("secret.txt").write_str("x")?;
+        let ws = temp.child("ws");
+        ws.child("src/a.rs")
+            .write_str("fn resolve_path() {}\nfn other() {}\n")?;
+        ws.child("DESIGN.md")
+            .write_str("# Design\n\n## Core Goals\n")?;
+        let base = ws.child("MINDMAP.md");
+        base.write_str("")?;
+        let cache = MindmapCache::new(ws.path().to_path_buf());
+        let status = |desc: &str| check(&cache, base.path(), &extract(desc)[0], 1024);
+
+        assert_eq!(status("src/a.rs:2"), AnchorStatus::Ok);
+        assert_eq!(status("src/a.rs:1-3"), AnchorStatus::PastEnd(2));
+        assert_eq!(status("src/a.rs#resolve_path"), AnchorStatus::Ok);
+        assert_eq!(status("src/a.rs#resolve"), AnchorStatus::MissingSymbol);
+        assert_eq!(status("src/b.rs"), AnchorStatus::Missing);
+        assert_eq!(status("[d](./DESIGN.md#core-goals)"), AnchorStatus::Ok);
+        assert_eq!(status("[d](./DESIGN.md#gone)"), AnchorStatus::MissingSymbol);
+        assert_eq!(
+            status("[up](../secret.txt)"),
+            AnchorStatus::OutsideWorkspace
+        );
+        temp.close()?;
+        Ok(())
+    }
+}
@@ -177,14 +177,12 @@ impl MindmapCache {
         }
     }
 
-    /// Set max file size (for testing)
-    #[cfg(test)]
+    /// Set max file size (`[cache] max_file_size`)
     pub fn set_max_file_size(&mut self, size: u64) {
         self.max_file_size = size;
     }
 
-    /// Set max recursion depth (for testing)
-    #[cfg(test)]
+    /// Set max recursion depth (`[cache] max_depth`)
     pub fn set_max_depth(&mut self, depth: usize) {
         self.max_depth = depth;
     }
@@ -0,0 +1,208 @@
+//! Citations: `MINDMAP[N]` mentions in source code
+//!
+//! This module provides:
+//! - `scan`: every `MINDMAP[N]` citation under a directory (hidden directories,
+//!   `target` and `node_modules` are skipped, as are symlinks, non-UTF-8 files and
+//!   files over the size limit)
+//! - `check`: citations of nodes that are missing or deprecated
+//! - `uncited`: nodes of a type (AE by default) that no code cites
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use crate::{Mindmap, Node, split_title};
+
+static CITATION: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"MINDMAP\[(\d+)\]").unwrap());
+
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules"];
+
+/// One `MINDMAP[N]` in a source file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Citation {
+    /// Path relative to the `base` given to `scan` (the mindmap's directory)
+    pub file: PathBuf,
+    /// 1-based line and column
+    pub line: usize,
+    pub column: usize,
+    pub id: u32,
+    /// The citing line, trimmed
+    pub text: String,
+}
+
+/// What is wrong with a citation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Problem {
+    Missing,
+    /// The node was deprecated, in favour of `successor` when the marker names one
+    Deprecated {
+        successor: Option<u32>,
+    },
+}
+
+/// Result of scanning a tree
+#[derive(Debug, Default)]
+pub struct Scan {
+    pub files_scanned: usize,
+    pub citations: Vec<Citation>,
+}
+
+/// Every citation under `root`, ordered by file and position, with paths relative to
+/// `base`
+pub fn scan(root: &Path, base: &Path, max_file_size: u64) -> Result<Scan> {
+    let mut out = Scan::default();
+    // compare canonical paths so `src` and `./src` under `.` strip alike
+    let root = fs::canonicalize(root)
+        .with_context(|| format!("Failed to read directory {}", root.display()))?;
+    let base = fs::canonicalize(base).unwrap_or_else(|_| base.to_path_buf());
+    let mut dirs = vec![root];
+    let mut files = Vec::new();
+    while let Some(dir) = dirs.pop() {
+        let entries = fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read directory {}", dir.display()))?;
+        for entry in entries {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if file_type.is_dir() {
+                if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref()) {
+                    dirs.push(entry.path());
+                }
+            } else if file_type.is_file() && entry.metadata()?.len() <= max_file_size {
+                files.push(entry.path());
+            }
+        }
+    }
+    files.sort();
+
+    for path in files {
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        out.files_scanned += 1;
+        let file = path.strip_prefix(&base).unwrap_or(&path).to_path_buf();
+        for (i, line) in content.lines().enumerate() {
+            for c in CITATION.captures_iter(line) {
+                let Ok(id) = c[1].parse() else {
+                    continue;
+                };
+                out.citations.push(Citation {
+                    file: file.clone(),
+                    line: i + 1,
+                    column: c.get(0).unwrap().start() + 1,
+                    id,
+                    text: line.trim().to_string(),
+                });
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// `Some(successor)` for a `[DEPRECATED → N]` (or bare `[DEPRECATED]`) title
+pub fn deprecated(node: &Node) -> Option<Option<u32>> {
+    let rest = node.raw_title.strip_prefix("[DEPRECATED")?;
+    let marker = &rest[..rest.find(']')?];
+    Some(marker.trim_start_matches([' ', '→']).trim().parse().ok())
+}
+
+/// Citations of missing or deprecated nodes
+pub fn check<'a>(mm: &Mindmap, citations: &'a [Citation]) -> Vec<(&'a Citation, Problem)> {
+    citations
+        .iter()
+        .filter_map(|c| match mm.get_node(c.id) {
+            None => Some((c, Problem::Missing)),
+            Some(node) => deprecated(node).map(|successor| (c, Problem::Deprecated { successor })),
+        })
+        .collect()
+}
+
+/// Live nodes of type `typ` that no citation mentions
+pub fn uncited<'a>(mm: &'a Mindmap, citations: &[Citation], typ: &str) -> Vec<&'a Node> {
+    let cited: HashSet<u32> = citations.iter().map(|c| c.id).collect();
+    mm.nodes
+        .iter()
+        .filter(|n| {
+            !cited.contains(&n.id)
+                && deprecated(n).is_none()
+                && split_title(&n.raw_title)
+                    .0
+                    .is_some_and(|t| t.eq_ignore_ascii_case(typ))
+        })
+        .collect()
+}
+
+/// `file:line:col` of a citation
+pub fn location(c: &Citation) -> String {
+    format!("{}:{}:{}", c.file.display(), c.line, c.column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn test_scan_and_check_citations() -> Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        temp.child("src/a.rs")
+            .write_str("// MINDMAP[1]\nfn a() {} // MINDMAP[2] and MINDMAP[9]\n")?;
+        temp.child("src/nested/b.py").write_str("# MINDMAP[1]\n")?;
+        temp.child("target/debug/c.rs")
+            .write_str("// MINDMAP[7]\n")?;
+        temp.child(".git/d").write_str("MINDMAP[8]\n")?;
+
+        // paths are relative to the base, whichever directory is scanned
+        let nested = scan(&temp.path().join("src/nested"), temp.path(), 1024)?;
+        assert_eq!(nested.citations[0].file, PathBuf::from("src/nested/b.py"));
+
+        let scan = scan(temp.path(), temp.path(), 1024)?;
+        assert_eq!(scan.files_scanned, 2);
+        let found: Vec<(String, usize, usize, u32)> = scan
+            .citations
+            .iter()
+            .map(|c| (c.file.display().to_string(), c.line, c.column, c.id))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("src/a.rs".to_string(), 1, 4, 1),
+                ("src/a.rs".to_string(), 2, 14, 2),
+                ("src/a.rs".to_string(), 2, 29, 9),
+                ("src/nested/b.py".to_string(), 1, 3, 1),
+            ]
+        );
+
+        let mm = Mindmap::from_string(
+            "[1] **AE: Live** - a\n\n[2] **[DEPRECATED → 1] AE: Old** - b\n\n[3] **AE: Lonely** - c\n\n[4] **WF: Flow** - d\n"
+                .to_string(),
+            "-".into(),
+        )?;
+        let problems: Vec<(u32, Problem)> = check(&mm, &scan.citations)
+            .into_iter()
+            .map(|(c, p)| (c.id, p))
+            .collect();
+        assert_eq!(
+            problems,
+            vec![
+                (2, Problem::Deprecated { successor: Some(1) }),
+                (9, Problem::Missing)
+            ]
+        );
+        let ids: Vec<u32> = uncited(&mm, &scan.citations, "AE")
+            .iter()
+            .map(|n| n.id)
+            .collect();
+        assert_eq!(ids, vec![3]);
+        temp.close()?;
+        Ok(())
+    }
+}
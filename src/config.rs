@@ -0,0 +1,480 @@
+//! Config: project settings from `.mindmap.toml`
+//!
+//! This module provides:
+//! - Discovery of `.mindmap.toml` by walking up from a directory (the current one for
+//!   CLI defaults, the MINDMAP file's one for per-map policies)
+//! - `file`, `editor`: default MINDMAP file (relative to the config) and editor
+//! - `[output]`: default `format` and `pretty` (`MINDMAP_PRETTY` still wins)
+//! - `[cache]`: `max_file_size` / `max_depth` for `--follow` and external references
+//! - `[types]`: allowed type prefixes and `[types.<PREFIX>]` definitions (description,
+//!   aliases, `must_reference` / `must_contain` conventions), enforced by add/patch/put
+//!   and `lint`
+//! - `[orphans]`: type prefixes exempt from `orphans` (default `META`)
+//! - `[stale]`: default `older_than` for `stale`, and per-type maximum ages since the
+//!   last `(verify …)` for the `stale-verification` lint rule
+//! - `[dupes]`: similarity `threshold` for `dupes` and `add --check-dupes` (default 0.6)
+//! - `[lint.rules]`: per-rule severity (`error`, `warn`, `info`) or `off`
+
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{OutputFormat, cache::MindmapCache, stale::Age};
+
+/// File name looked up in each directory while walking up
+pub const CONFIG_FILE: &str = ".mindmap.toml";
+
+/// Configured level of one lint rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LintConfig {
+    /// Rule ID -> level, e.g. `missing-ref = "warn"`, `duplicate-title-prefix = "off"`
+    #[serde(default)]
+    pub rules: BTreeMap<String, RuleLevel>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigFormat {
+    Default,
+    Json,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputConfig {
+    /// Used when `--output` is not given
+    pub format: Option<ConfigFormat>,
+    /// Colored output; defaults to "when stdout is a terminal"
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct CacheConfig {
+    pub max_file_size: u64,
+    pub max_depth: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            max_file_size: 10 * 1024 * 1024,
+            max_depth: 50,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// A cache for `workspace` with these limits
+    pub fn cache(&self, workspace: &Path) -> MindmapCache {
+        let mut cache = MindmapCache::new(workspace.to_path_buf());
+        cache.set_max_file_size(self.max_file_size);
+        cache.set_max_depth(self.max_depth);
+        cache
+    }
+}
+
+/// One declared node type (`[types.DR]`)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct TypeDef {
+    pub description: String,
+    /// Other spellings accepted by `add --type`, `patch --type` and `list --type`
+    pub aliases: Vec<String>,
+    /// The node must reference at least one node of one of these types
+    pub must_reference: Vec<String>,
+    /// The description must contain each phrase (case-insensitive)
+    pub must_contain: Vec<String>,
+}
+
+/// A type prefix offered by completion (shell and LSP)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeCompletion {
+    /// What is typed: a prefix or an alias
+    pub label: String,
+    /// The prefix to insert
+    pub prefix: String,
+    /// The declared description or `alias of X`; empty for undeclared prefixes
+    pub detail: String,
+}
+
+/// `[types]`: an `allowed` list and/or `[types.<PREFIX>]` definitions. With neither,
+/// any type is accepted.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TypesConfig {
+    #[serde(default)]
+    pub allowed: Vec<String>,
+    #[serde(flatten)]
+    pub defs: BTreeMap<String, TypeDef>,
+}
+
+impl TypesConfig {
+    fn is_open(&self) -> bool {
+        self.allowed.is_empty() && self.defs.is_empty()
+    }
+
+    /// Declared prefixes, `allowed` first
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.allowed.iter().map(String::as_str).collect();
+        for name in self.defs.keys() {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    pub fn is_allowed(&self, typ: &str) -> bool {
+        self.is_open() || self.names().contains(&typ)
+    }
+
+    /// The declared prefix `typ` stands for: itself, a differently cased prefix, or an
+    /// alias. None when it names nothing declared.
+    pub fn canonical(&self, typ: &str) -> Option<&str> {
+        let names = self.names();
+        if let Some(name) = names.iter().find(|n| **n == typ) {
+            return Some(name);
+        }
+        if let Some(name) = names.iter().find(|n| n.eq_ignore_ascii_case(typ)) {
+            return Some(name);
+        }
+        self.defs
+            .iter()
+            .find(|(_, d)| d.aliases.iter().any(|a| a.eq_ignore_ascii_case(typ)))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// `typ` as it should be written: the canonical prefix, or `typ` unchanged when no
+    /// registry is configured. Err naming the allowed prefixes otherwise.
+    pub fn resolve(&self, typ: &str) -> Result<String> {
+        if self.is_open() {
+            return Ok(typ.to_string());
+        }
+        self.canonical(typ).map(str::to_string).ok_or_else(|| {
+            anyhow!(
+                "Type '{}' is not allowed by {} (allowed: {})",
+                typ,
+                CONFIG_FILE,
+                self.names().join(", ")
+            )
+        })
+    }
+
+    /// The `used` prefixes plus every declared prefix and alias, sorted by label
+    pub fn completions<'a>(&self, used: impl IntoIterator<Item = &'a str>) -> Vec<TypeCompletion> {
+        // label -> (prefix, detail)
+        let mut types: BTreeMap<String, (String, String)> = used
+            .into_iter()
+            .filter(|t| !t.is_empty())
+            .map(|t| (t.to_string(), (t.to_string(), String::new())))
+            .collect();
+        for name in self.names() {
+            let detail = self
+                .defs
+                .get(name)
+                .map(|d| d.description.clone())
+                .unwrap_or_default();
+            types.insert(name.to_string(), (name.to_string(), detail));
+        }
+        for (name, def) in &self.defs {
+            for alias in &def.aliases {
+                types
+                    .entry(alias.clone())
+                    .or_insert_with(|| (name.clone(), format!("alias of {}", name)));
+            }
+        }
+        types
+            .into_iter()
+            .map(|(label, (prefix, detail))| TypeCompletion {
+                label,
+                prefix,
+                detail,
+            })
+            .collect()
+    }
+
+    /// Violations of the `must_reference` / `must_contain` conventions of `typ`;
+    /// `ref_types` are the types of the nodes the node references
+    pub fn violations(&self, typ: &str, description: &str, ref_types: &[&str]) -> Vec<String> {
+        let Some(def) = self.defs.get(typ) else {
+            return Vec::new();
+        };
+        let mut out = Vec::new();
+        if !def.must_reference.is_empty()
+            && !ref_types
+                .iter()
+                .any(|t| def.must_reference.iter().any(|m| m == t))
+        {
+            out.push(format!(
+                "{} must reference at least one {} node",
+                typ,
+                def.must_reference.join(" or ")
+            ));
+        }
+        let lower = description.to_lowercase();
+        for phrase in &def.must_contain {
+            if !lower.contains(&phrase.to_lowercase()) {
+                out.push(format!("{} description must contain '{}'", typ, phrase));
+            }
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct OrphansConfig {
+    /// Type prefixes never reported as orphans
+    pub exempt: Vec<String>,
+}
+
+impl Default for OrphansConfig {
+    fn default() -> Self {
+        OrphansConfig {
+            exempt: vec!["META".to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct DupesConfig {
+    /// Minimum similarity (0.0-1.0) reported as a likely duplicate
+    pub threshold: f64,
+}
+
+impl Default for DupesConfig {
+    fn default() -> Self {
+        DupesConfig { threshold: 0.6 }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StaleConfig {
+    /// Default for `stale --older-than`
+    pub older_than: Option<Age>,
+    /// Type prefix -> maximum age since the last `(verify YYYY-MM-DD)`, e.g. `DR = "180d"`
+    #[serde(default)]
+    pub types: BTreeMap<String, Age>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Default MINDMAP file, relative to the config file
+    pub file: Option<PathBuf>,
+    /// Editor for `add`/`edit` (before `$EDITOR`)
+    pub editor: Option<String>,
+    #[serde(default)]
+    pub output: OutputConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub types: TypesConfig,
+    #[serde(default)]
+    pub orphans: OrphansConfig,
+    #[serde(default)]
+    pub stale: StaleConfig,
+    #[serde(default)]
+    pub dupes: DupesConfig,
+    #[serde(default)]
+    pub lint: LintConfig,
+    /// Where the config was read from (None for defaults)
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+impl Config {
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut config =
+            Self::parse(&content).with_context(|| format!("Invalid config {}", path.display()))?;
+        config.source = Some(path.to_path_buf());
+        Ok(config)
+    }
+
+    /// Path of the nearest `.mindmap.toml` in `dir` or its ancestors
+    fn locate(dir: &Path) -> Option<PathBuf> {
+        let start = std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf());
+        start
+            .ancestors()
+            .map(|d| d.join(CONFIG_FILE))
+            .find(|path| path.is_file())
+    }
+
+    /// Directory to start discovery from for a MINDMAP file; `None` for stdin
+    fn mindmap_dir(mindmap: &Path) -> Option<&Path> {
+        if mindmap.as_os_str() == "-" {
+            return None;
+        }
+        Some(
+            mindmap
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or_else(|| Path::new(".")),
+        )
+    }
+
+    /// The nearest `.mindmap.toml` in `dir` or its ancestors; defaults when there is none
+    pub fn discover(dir: &Path) -> Result<Self> {
+        match Self::locate(dir) {
+            Some(path) => Self::load(&path),
+            None => Ok(Config::default()),
+        }
+    }
+
+    /// The config governing a MINDMAP file (walking up from its directory); defaults
+    /// for stdin
+    pub fn for_mindmap(mindmap: &Path) -> Result<Self> {
+        match Self::mindmap_dir(mindmap) {
+            Some(dir) => Self::discover(dir),
+            None => Ok(Config::default()),
+        }
+    }
+
+    /// Like `for_mindmap`, but reuses `self` (without re-reading it) when the map is
+    /// governed by the same `.mindmap.toml` this config was loaded from
+    pub fn for_mindmap_from(&self, mindmap: &Path) -> Result<Self> {
+        let Some(dir) = Self::mindmap_dir(mindmap) else {
+            return Ok(Config::default());
+        };
+        match Self::locate(dir) {
+            Some(path) if self.source.as_ref() == Some(&path) => Ok(self.clone()),
+            Some(path) => Self::load(&path),
+            None => Ok(Config::default()),
+        }
+    }
+
+    /// `--file` when given, else `file` from the config, else `./MINDMAP.md`
+    pub fn mindmap_path(&self, flag: Option<PathBuf>) -> PathBuf {
+        if let Some(path) = flag {
+            return path;
+        }
+        match (&self.file, &self.source) {
+            (Some(file), Some(source)) => source
+                .parent()
+                .map(|dir| dir.join(file))
+                .unwrap_or_else(|| file.clone()),
+            (Some(file), None) => file.clone(),
+            _ => PathBuf::from("MINDMAP.md"),
+        }
+    }
+
+    /// `--output` when given, else `[output] format`
+    pub fn output_format(&self, flag: Option<OutputFormat>) -> OutputFormat {
+        flag.unwrap_or(match self.output.format {
+            Some(ConfigFormat::Json) => OutputFormat::Json,
+            _ => OutputFormat::Default,
+        })
+    }
+
+    /// `editor`, else `$EDITOR`, else `vi`
+    pub fn editor(&self) -> String {
+        self.editor
+            .clone()
+            .or_else(|| std::env::var("EDITOR").ok())
+            .unwrap_or_else(|| "vi".to_string())
+    }
+
+    /// Whether a node title is exempt from orphan reports
+    pub fn orphan_exempt(&self, raw_title: &str) -> bool {
+        let (typ, _) = crate::split_title(raw_title);
+        let typ = typ.unwrap_or(raw_title).to_uppercase();
+        self.orphans
+            .exempt
+            .iter()
+            .any(|e| typ.starts_with(&e.to_uppercase()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lint_rules() -> Result<()> {
+        let config = Config::parse("[lint.rules]\nmissing-ref = \"warn\"\nsyntax = \"off\"\n")?;
+        assert_eq!(config.lint.rules["missing-ref"], RuleLevel::Warn);
+        assert_eq!(config.lint.rules["syntax"], RuleLevel::Off);
+        assert!(Config::parse("[lint.rules]\nsyntax = \"loud\"\n").is_err());
+        assert!(Config::parse("[unknown]\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_type_registry() -> Result<()> {
+        let config = Config::parse(
+            "[types.AE]\ndescription = \"Architecture element\"\naliases = [\"arch\"]\n\n[types.DR]\nmust_reference = [\"AE\"]\n\n[types.BUG]\nmust_contain = [\"root cause\"]\n",
+        )?;
+        let types = &config.types;
+        assert_eq!(types.resolve("Arch")?, "AE");
+        assert_eq!(types.resolve("bug")?, "BUG");
+        assert!(types.resolve("WF").is_err());
+        assert!(types.violations("DR", "x", &["AE"]).is_empty());
+        assert_eq!(
+            types.violations("DR", "x", &["BUG"]),
+            vec!["DR must reference at least one AE node".to_string()]
+        );
+        assert!(types.violations("BUG", "Root Cause: typo", &[]).is_empty());
+        assert_eq!(types.violations("BUG", "crash", &[]).len(), 1);
+        assert!(Config::parse("[types.AE]\nbogus = 1\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_discover_walks_up_and_resolves_file() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let nested = temp.path().join("a").join("b");
+        std::fs::create_dir_all(&nested)?;
+        std::fs::write(
+            temp.path().join(CONFIG_FILE),
+            "file = \"docs/MINDMAP.md\"\n[cache]\nmax_depth = 3\n[types]\nallowed = [\"AE\"]\n",
+        )?;
+
+        let config = Config::discover(&nested)?;
+        assert_eq!(
+            config.mindmap_path(None),
+            std::path::absolute(temp.path())?
+                .join("docs")
+                .join("MINDMAP.md")
+        );
+        assert_eq!(
+            config.mindmap_path(Some("X.md".into())),
+            PathBuf::from("X.md")
+        );
+        assert_eq!(config.cache.max_depth, 3);
+        assert_eq!(config.cache.max_file_size, 10 * 1024 * 1024);
+        assert_eq!(config.types.resolve("ae")?, "AE");
+        assert!(config.types.resolve("WF").is_err());
+        assert!(config.orphan_exempt("META: Notes"));
+        assert!(!config.orphan_exempt("AE: Metadata"));
+
+        // a map governed by the same file reuses it; a nearer one is read instead
+        let map = nested.join("MINDMAP.md");
+        assert_eq!(config.for_mindmap_from(&map)?.cache.max_depth, 3);
+        std::fs::write(nested.join(CONFIG_FILE), "editor = \"ed\"\n")?;
+        let nearer = config.for_mindmap_from(&map)?;
+        assert_eq!(nearer.editor.as_deref(), Some("ed"));
+        assert_eq!(nearer.cache.max_depth, 50);
+        Ok(())
+    }
+}
@@ -0,0 +1,307 @@
+//! Diff: semantic comparison of two versions of a mindmap
+//!
+//! This module provides:
+//! - A comparison of parsed mindmaps by node ID, so blank-line moves from
+//!   `lint --fix` or reordered lines are not reported
+//! - Added, removed, retitled, retyped and re-described nodes, plus reference edges
+//!   added or removed
+//! - Human, JSON (via serde) and markdown (for PR comments) renderings
+//! - Resolving each side from a file path, a git revision of the mindmap file, or
+//!   an explicit `rev:path`
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use crate::{Mindmap, export::format_reference, git, split_title};
+
+/// A node as listed in added/removed
+#[derive(Debug, Serialize)]
+pub struct NodeSummary {
+    pub id: u32,
+    pub raw_title: String,
+    pub description: String,
+}
+
+/// One changed field of a node present on both sides
+#[derive(Debug, Serialize)]
+pub struct FieldChange {
+    pub id: u32,
+    /// "type", "title" or "description"
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+/// A reference from node `from` (written as in the description, e.g. `[3]` or `[3](./x.md)`)
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Edge {
+    pub from: u32,
+    pub to: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct MindmapDiff {
+    pub added: Vec<NodeSummary>,
+    pub removed: Vec<NodeSummary>,
+    pub changed: Vec<FieldChange>,
+    pub edges_added: Vec<Edge>,
+    pub edges_removed: Vec<Edge>,
+}
+
+impl MindmapDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.edges_added.is_empty()
+            && self.edges_removed.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        let nodes: BTreeSet<u32> = self.changed.iter().map(|c| c.id).collect();
+        format!(
+            "{} added, {} removed, {} changed; {} reference(s) added, {} removed",
+            self.added.len(),
+            self.removed.len(),
+            nodes.len(),
+            self.edges_added.len(),
+            self.edges_removed.len()
+        )
+    }
+}
+
+fn summary(n: &crate::Node) -> NodeSummary {
+    NodeSummary {
+        id: n.id,
+        raw_title: n.raw_title.clone(),
+        description: n.description.clone(),
+    }
+}
+
+fn edges(mm: &Mindmap) -> BTreeSet<Edge> {
+    mm.nodes
+        .iter()
+        .flat_map(|n| {
+            n.references.iter().map(|r| Edge {
+                from: n.id,
+                to: format_reference(r),
+            })
+        })
+        .collect()
+}
+
+/// Compare `old` and `new` by node ID
+pub fn diff(old: &Mindmap, new: &Mindmap) -> MindmapDiff {
+    let old_nodes: BTreeMap<u32, &crate::Node> = old.nodes.iter().map(|n| (n.id, n)).collect();
+    let new_nodes: BTreeMap<u32, &crate::Node> = new.nodes.iter().map(|n| (n.id, n)).collect();
+
+    let mut d = MindmapDiff::default();
+    for (id, n) in &new_nodes {
+        match old_nodes.get(id) {
+            None => d.added.push(summary(n)),
+            Some(o) => {
+                let (ot, otitle) = split_title(&o.raw_title);
+                let (nt, ntitle) = split_title(&n.raw_title);
+                let fields = [
+                    ("type", ot.unwrap_or_default(), nt.unwrap_or_default()),
+                    ("title", otitle, ntitle),
+                    (
+                        "description",
+                        o.description.as_str(),
+                        n.description.as_str(),
+                    ),
+                ];
+                for (field, a, b) in fields {
+                    if a != b {
+                        d.changed.push(FieldChange {
+                            id: *id,
+                            field,
+                            old: a.to_string(),
+                            new: b.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    d.removed = old_nodes
+        .iter()
+        .filter(|(id, _)| !new_nodes.contains_key(id))
+        .map(|(_, n)| summary(n))
+        .collect();
+
+    let (old_edges, new_edges) = (edges(old), edges(new));
+    d.edges_added = new_edges.difference(&old_edges).cloned().collect();
+    d.edges_removed = old_edges.difference(&new_edges).cloned().collect();
+    d
+}
+
+/// Load one side: an existing file, `rev:path`, or a revision of `file`
+pub fn load_side(spec: &str, file: &Path) -> Result<(String, Mindmap)> {
+    let content = if Path::new(spec).is_file() {
+        std::fs::read_to_string(spec).with_context(|| format!("Failed to read {}", spec))?
+    } else if let Some((rev, path)) = spec.split_once(':') {
+        git::show(file, rev, path)?
+            .ok_or_else(|| anyhow::anyhow!("{} does not exist at {}", path, rev))?
+    } else {
+        let path = git::repo_path(file)?;
+        git::show(file, spec, &path)
+            .with_context(|| format!("'{}' is neither a file nor a git revision", spec))?
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "'{}' is neither a file nor a revision containing {}",
+                    spec,
+                    path
+                )
+            })?
+    };
+    let mm = Mindmap::from_string(content, spec.into())?;
+    Ok((spec.to_string(), mm))
+}
+
+/// One line per change: `+` added, `-` removed, `~` changed
+pub fn format_human(d: &MindmapDiff) -> Vec<String> {
+    let mut out = Vec::new();
+    for n in &d.added {
+        out.push(format!(
+            "+ [{}] **{}** - {}",
+            n.id, n.raw_title, n.description
+        ));
+    }
+    for n in &d.removed {
+        out.push(format!(
+            "- [{}] **{}** - {}",
+            n.id, n.raw_title, n.description
+        ));
+    }
+    for c in &d.changed {
+        out.push(format!(
+            "~ [{}] {}: {:?} -> {:?}",
+            c.id, c.field, c.old, c.new
+        ));
+    }
+    for e in &d.edges_added {
+        out.push(format!("+ ref [{}] -> {}", e.from, e.to));
+    }
+    for e in &d.edges_removed {
+        out.push(format!("- ref [{}] -> {}", e.from, e.to));
+    }
+    out
+}
+
+fn md_cell(s: &str) -> String {
+    if s.is_empty() {
+        return "_(none)_".to_string();
+    }
+    s.replace('|', "\\|")
+}
+
+/// Markdown for a PR comment
+pub fn format_markdown(d: &MindmapDiff, old: &str, new: &str) -> String {
+    let mut out = format!("### Mindmap changes (`{}` → `{}`)\n\n", old, new);
+    if d.is_empty() {
+        out.push_str("No node changes.\n");
+        return out;
+    }
+    out.push_str(&format!("{}\n", d.summary()));
+    let list = |title: &str, nodes: &[NodeSummary], out: &mut String| {
+        if nodes.is_empty() {
+            return;
+        }
+        out.push_str(&format!("\n**{}**\n\n", title));
+        for n in nodes {
+            out.push_str(&format!(
+                "- `[{}]` **{}** — {}\n",
+                n.id, n.raw_title, n.description
+            ));
+        }
+    };
+    list("Added", &d.added, &mut out);
+    list("Removed", &d.removed, &mut out);
+    if !d.changed.is_empty() {
+        out.push_str("\n**Changed**\n\n| Node | Field | Before | After |\n|---|---|---|---|\n");
+        for c in &d.changed {
+            out.push_str(&format!(
+                "| `[{}]` | {} | {} | {} |\n",
+                c.id,
+                c.field,
+                md_cell(&c.old),
+                md_cell(&c.new)
+            ));
+        }
+    }
+    if !d.edges_added.is_empty() || !d.edges_removed.is_empty() {
+        out.push_str("\n**References**\n\n");
+        for e in &d.edges_added {
+            out.push_str(&format!("- added `[{}]` → `{}`\n", e.from, e.to));
+        }
+        for e in &d.edges_removed {
+            out.push_str(&format!("- removed `[{}]` → `{}`\n", e.from, e.to));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(content: &str) -> Result<Mindmap> {
+        Mindmap::from_string(content.to_string(), "-".into())
+    }
+
+    #[test]
+    fn test_diff_by_id_ignores_layout() -> Result<()> {
+        let old = load("[1] **AE: Auth** - login\n[2] **WF: Flow** - uses [1]\n")?;
+        let new = load("[2] **WF: Flow** - uses [1]\n\n\n[1] **AE: Auth** - login\n")?;
+        assert!(diff(&old, &new).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_reports_fields_and_edges() -> Result<()> {
+        let old =
+            load("[1] **AE: Auth** - login\n\n[2] **WF: Flow** - uses [1]\n\n[3] **Old** - x\n")?;
+        let new = load(
+            "[1] **DR: Authn** - login\n\n[2] **WF: Flow** - uses [4]\n\n[4] **AE: New** - y [1]\n",
+        )?;
+        let d = diff(&old, &new);
+        assert_eq!(d.added.len(), 1);
+        assert_eq!(d.removed[0].id, 3);
+        let fields: Vec<(u32, &str)> = d.changed.iter().map(|c| (c.id, c.field)).collect();
+        assert_eq!(fields, vec![(1, "type"), (1, "title"), (2, "description")]);
+        assert_eq!(
+            d.edges_added,
+            vec![
+                Edge {
+                    from: 2,
+                    to: "[4]".into()
+                },
+                Edge {
+                    from: 4,
+                    to: "[1]".into()
+                }
+            ]
+        );
+        assert_eq!(
+            d.edges_removed,
+            vec![Edge {
+                from: 2,
+                to: "[1]".into()
+            }]
+        );
+
+        let human = format_human(&d);
+        assert_eq!(human[0], "+ [4] **AE: New** - y [1]");
+        assert!(human.contains(&"~ [1] type: \"AE\" -> \"DR\"".to_string()));
+
+        let md = format_markdown(&d, "HEAD", "MINDMAP.md");
+        assert!(md.contains("| `[1]` | title | Auth | Authn |"));
+        assert!(md.contains("- removed `[2]` → `[1]`"));
+        Ok(())
+    }
+}
@@ -0,0 +1,220 @@
+//! Dupes: near-duplicate node detection
+//!
+//! This module provides:
+//! - Normalised tokens (lowercase words without stopwords, references, verify tags,
+//!   deprecation markers or plural `s`)
+//!   and character trigrams of titles
+//! - `similarity`: Jaccard similarity of two nodes — the better of token and trigram
+//!   overlap for titles (type prefixes ignored), token overlap for descriptions,
+//!   weighted 60/40 (titles only when either description is empty)
+//! - `find`: pairs of live nodes scoring at least a threshold, best first, and
+//!   `similar_to` for one node (the `add --check-dupes` guard)
+
+use serde::Serialize;
+use std::{collections::HashSet, sync::LazyLock};
+
+use crate::{Mindmap, Node, citations, split_title};
+
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "in", "into", "is", "it", "of",
+    "on", "or", "see", "that", "the", "this", "to", "with",
+];
+
+const TITLE_WEIGHT: f64 = 0.6;
+
+/// References, `(verify YYYY-MM-DD)` tags and `[DEPRECATED → N]` markers
+static MARKUP: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r"\[\d+\](?:\([^)]*\))?|\(verify \d{4}-\d{2}-\d{2}\)|\[DEPRECATED[^\]]*\]")
+        .unwrap()
+});
+
+/// Lowercase words with references, verify tags, deprecation markers, stopwords and a
+/// plural `s` removed
+pub fn tokens(text: &str) -> HashSet<String> {
+    MARKUP
+        .replace_all(text, " ")
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let w = w.to_lowercase();
+            match w.strip_suffix('s') {
+                Some(stem) if stem.len() > 2 && !stem.ends_with('s') => stem.to_string(),
+                _ => w,
+            }
+        })
+        .filter(|w| !STOPWORDS.contains(&w.as_str()))
+        .collect()
+}
+
+/// Character trigrams of the space-joined lowercase words
+fn trigrams(text: &str) -> HashSet<[char; 3]> {
+    let words: Vec<String> = MARKUP
+        .replace_all(text, " ")
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let chars: Vec<char> = format!(" {} ", words.join(" ")).chars().collect();
+    chars.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+}
+
+fn jaccard<T: Eq + std::hash::Hash>(a: &HashSet<T>, b: &HashSet<T>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / a.union(b).count() as f64
+}
+
+/// How alike two nodes are, each part in `0.0..=1.0`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Similarity {
+    pub score: f64,
+    pub title: f64,
+    /// None when either description has no words
+    pub description: Option<f64>,
+}
+
+/// Similarity of two titles and descriptions (`title` without the type prefix)
+pub fn similarity(title_a: &str, desc_a: &str, title_b: &str, desc_b: &str) -> Similarity {
+    let title = jaccard(&tokens(title_a), &tokens(title_b))
+        .max(jaccard(&trigrams(title_a), &trigrams(title_b)));
+    let (da, db) = (tokens(desc_a), tokens(desc_b));
+    let description = (!da.is_empty() && !db.is_empty()).then(|| jaccard(&da, &db));
+    let score = match description {
+        Some(d) => TITLE_WEIGHT * title + (1.0 - TITLE_WEIGHT) * d,
+        None => title,
+    };
+    Similarity {
+        score,
+        title,
+        description,
+    }
+}
+
+fn node_similarity(a: &Node, b: &Node) -> Similarity {
+    similarity(
+        split_title(&a.raw_title).1,
+        &a.description,
+        split_title(&b.raw_title).1,
+        &b.description,
+    )
+}
+
+/// A likely duplicate pair; `keep` is the older (lower ID) node
+#[derive(Debug, Clone, Serialize)]
+pub struct Candidate {
+    pub keep: u32,
+    pub duplicate: u32,
+    #[serde(flatten)]
+    pub similarity: Similarity,
+}
+
+/// Pairs of live (not deprecated) nodes scoring at least `threshold`, best first
+pub fn find(mm: &Mindmap, threshold: f64) -> Vec<Candidate> {
+    let live: Vec<&Node> = mm
+        .nodes
+        .iter()
+        .filter(|n| citations::deprecated(n).is_none())
+        .collect();
+    let mut out = Vec::new();
+    for (i, a) in live.iter().enumerate() {
+        for b in &live[i + 1..] {
+            let similarity = node_similarity(a, b);
+            if similarity.score >= threshold {
+                out.push(Candidate {
+                    keep: a.id.min(b.id),
+                    duplicate: a.id.max(b.id),
+                    similarity,
+                });
+            }
+        }
+    }
+    out.sort_by(|x, y| {
+        y.similarity
+            .score
+            .total_cmp(&x.similarity.score)
+            .then(x.keep.cmp(&y.keep))
+            .then(x.duplicate.cmp(&y.duplicate))
+    });
+    out
+}
+
+/// Live nodes other than `id` scoring at least `threshold` against it, best first
+pub fn similar_to(mm: &Mindmap, id: u32, threshold: f64) -> Vec<(u32, Similarity)> {
+    let Some(node) = mm.get_node(id) else {
+        return Vec::new();
+    };
+    let mut out: Vec<(u32, Similarity)> = mm
+        .nodes
+        .iter()
+        .filter(|n| n.id != id && citations::deprecated(n).is_none())
+        .map(|n| (n.id, node_similarity(node, n)))
+        .filter(|(_, s)| s.score >= threshold)
+        .collect();
+    out.sort_by(|x, y| y.1.score.total_cmp(&x.1.score).then(x.0.cmp(&y.0)));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_similarity() {
+        let exact = similarity(
+            "Cache loading",
+            "Loads files [3]",
+            "Cache loading",
+            "Loads files",
+        );
+        assert_eq!(exact.score, 1.0);
+
+        // word order, plurals, case and stopwords do not matter
+        let s = similarity(
+            "The mindmap cache",
+            "",
+            "Caches for Mindmap",
+            "lazy loading",
+        );
+        assert_eq!(s.title, 1.0);
+        assert_eq!(s.description, None);
+
+        // small spelling differences still overlap by trigrams
+        let s = similarity("MindmapCache", "", "Mindmap Cache", "");
+        assert!(s.title > 0.5, "{:?}", s);
+
+        let s = similarity("Cache", "files on disk", "Parser", "lines into nodes");
+        assert!(s.score < 0.2, "{:?}", s);
+    }
+
+    #[test]
+    fn test_find_pairs() -> anyhow::Result<()> {
+        let mm = Mindmap::from_string(
+            "[1] **AE: Mindmap cache** - Lazy loading of mindmap files\n\n[2] **WF: Release** - Tag and publish\n\n[3] **AE: Mindmap caches** - Lazily loads mindmap files [1]\n\n[4] **[DEPRECATED → 1] AE: Mindmap cache** - Lazy loading of mindmap files\n"
+                .to_string(),
+            "-".into(),
+        )?;
+        let pairs = find(&mm, 0.6);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].keep, pairs[0].duplicate), (1, 3));
+        assert_eq!(pairs[0].similarity.title, 1.0);
+
+        let similar: Vec<u32> = similar_to(&mm, 3, 0.6).iter().map(|(id, _)| *id).collect();
+        assert_eq!(similar, vec![1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_tags_are_ignored() -> anyhow::Result<()> {
+        let mm = Mindmap::from_string(
+            "[1] **AE: Auth** - Handles login tokens (verify 2026-01-02)\n\n[2] **AE: Auth** - Handles login tokens\n"
+                .to_string(),
+            "-".into(),
+        )?;
+        let pairs = find(&mm, 0.6);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].similarity.score, 1.0);
+        assert_eq!(tokens("[DEPRECATED → 3] Cache"), tokens("Cache"));
+        Ok(())
+    }
+}
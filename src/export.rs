@@ -0,0 +1,679 @@
+//! Export: serialise a mindmap into formats consumed by other tools
+//!
+//! This module provides:
+//! - CSV / TSV export (one row per node, spreadsheet friendly)
+//! - SQLite export (`files`, `nodes`, `edges` tables plus FTS5 full-text index),
+//!   optionally spanning every file reachable through external references
+//! - JSON export of the whole document (nodes plus non-node lines), the format
+//!   read back by `import --format json`
+//! - Graph views of the whole map: Graphviz DOT, Mermaid and a standalone HTML page
+
+use anyhow::{Context, Result};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::{Path, PathBuf},
+};
+
+use crate::{ExportFormat, Mindmap, Reference, split_title};
+
+/// Column headers used by CSV/TSV export (and recognised by CSV/TSV import)
+pub const CSV_HEADERS: [&str; 7] = [
+    "id",
+    "type",
+    "title",
+    "description",
+    "outgoing",
+    "incoming",
+    "line",
+];
+
+/// Render a reference the way it is written in a node description
+pub fn format_reference(r: &Reference) -> String {
+    match r {
+        Reference::Internal(id) => format!("[{}]", id),
+        Reference::External(id, path) => format!("[{}]({})", id, path),
+    }
+}
+
+/// Collect incoming internal references for every node: id -> referring node ids
+pub fn incoming_map(mm: &Mindmap) -> HashMap<u32, Vec<u32>> {
+    let mut incoming: HashMap<u32, Vec<u32>> = HashMap::new();
+    for n in &mm.nodes {
+        for r in &n.references {
+            if let Reference::Internal(rid) = r {
+                let entry = incoming.entry(*rid).or_default();
+                if !entry.contains(&n.id) {
+                    entry.push(n.id);
+                }
+            }
+        }
+    }
+    incoming
+}
+
+/// Export all nodes as delimited text (`b','` for CSV, `b'\t'` for TSV).
+///
+/// Columns: id, type, title, description, outgoing refs, incoming refs and the
+/// 1-based line number. Refs are space-separated in `[N]` / `[N](path)` form.
+pub fn cmd_export_csv(mm: &Mindmap, delimiter: u8) -> Result<String> {
+    let incoming = incoming_map(mm);
+
+    let mut wtr = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+    wtr.write_record(CSV_HEADERS)?;
+
+    for n in &mm.nodes {
+        let (typ, title) = split_title(&n.raw_title);
+        let outgoing = n
+            .references
+            .iter()
+            .map(format_reference)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let inbound = incoming
+            .get(&n.id)
+            .map(|ids| {
+                ids.iter()
+                    .map(|id| format!("[{}]", id))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .unwrap_or_default();
+
+        wtr.write_record([
+            n.id.to_string(),
+            typ.unwrap_or("").to_string(),
+            title.to_string(),
+            n.description.clone(),
+            outgoing,
+            inbound,
+            (n.line_index + 1).to_string(),
+        ])?;
+    }
+
+    let bytes = wtr
+        .into_inner()
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// Version of the whole-document JSON format
+pub const JSON_DOCUMENT_VERSION: u32 = 1;
+
+/// Whole-document JSON: every node plus every non-node line, each with its
+/// 0-based `line_index`, so the markdown can be regenerated line for line
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct JsonDocument {
+    pub version: u32,
+    pub nodes: Vec<JsonNode>,
+    /// Non-node lines (headings, prose, blank lines)
+    #[serde(default)]
+    pub lines: Vec<JsonLine>,
+}
+
+/// A node in the JSON document.
+///
+/// On import the title comes from `type` + `title` or from `raw_title` (if both are
+/// given they must agree), `references` is ignored (it is derived from the
+/// description) and a missing `line_index` appends the node at the end.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct JsonNode {
+    pub id: u32,
+    #[serde(rename = "type", default)]
+    pub node_type: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub raw_title: Option<String>,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, skip_deserializing)]
+    pub references: Vec<Reference>,
+    #[serde(default)]
+    pub line_index: Option<usize>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct JsonLine {
+    pub line_index: usize,
+    pub text: String,
+}
+
+/// Export the whole document as pretty-printed JSON (see `JsonDocument`)
+pub fn cmd_export_json(mm: &Mindmap) -> Result<String> {
+    let nodes = mm
+        .nodes
+        .iter()
+        .map(|n| {
+            let (node_type, title) = split_title(&n.raw_title);
+            JsonNode {
+                id: n.id,
+                node_type: node_type.map(|t| t.to_string()),
+                title: Some(title.to_string()),
+                raw_title: Some(n.raw_title.clone()),
+                description: n.description.clone(),
+                references: n.references.clone(),
+                line_index: Some(n.line_index),
+            }
+        })
+        .collect();
+
+    let node_lines: HashSet<usize> = mm.nodes.iter().map(|n| n.line_index).collect();
+    let lines = mm
+        .lines
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !node_lines.contains(i))
+        .map(|(i, text)| JsonLine {
+            line_index: i,
+            text: text.clone(),
+        })
+        .collect();
+
+    let doc = JsonDocument {
+        version: JSON_DOCUMENT_VERSION,
+        nodes,
+        lines,
+    };
+    Ok(serde_json::to_string_pretty(&doc)? + "\n")
+}
+
+/// Export every node and reference as a Graphviz digraph; external targets are
+/// dashed boxes labelled `path#id`
+pub fn cmd_export_dot(mm: &Mindmap) -> String {
+    let quote = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut dot = String::from("digraph {\n  rankdir=LR;\n");
+    for n in &mm.nodes {
+        dot.push_str(&format!(
+            "  {} [label=\"{}: {}\"];\n",
+            n.id,
+            n.id,
+            quote(&n.raw_title)
+        ));
+    }
+    let mut externals = HashSet::new();
+    for n in &mm.nodes {
+        for r in &n.references {
+            match r {
+                Reference::Internal(rid) => dot.push_str(&format!("  {} -> {};\n", n.id, rid)),
+                Reference::External(rid, path) => {
+                    let target = quote(&format!("{}#{}", path, rid));
+                    if externals.insert(target.clone()) {
+                        dot.push_str(&format!("  \"{}\" [shape=box, style=dashed];\n", target));
+                    }
+                    dot.push_str(&format!("  {} -> \"{}\";\n", n.id, target));
+                }
+            }
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Export every node and reference as a Mermaid flowchart
+pub fn cmd_export_mermaid(mm: &Mindmap) -> String {
+    let label = |s: &str| s.replace('"', "#quot;");
+    let mut out = String::from("graph LR\n");
+    for n in &mm.nodes {
+        out.push_str(&format!(
+            "  n{}[\"{}: {}\"]\n",
+            n.id,
+            n.id,
+            label(&n.raw_title)
+        ));
+    }
+    let mut externals = HashMap::new();
+    for n in &mm.nodes {
+        for r in &n.references {
+            match r {
+                Reference::Internal(rid) => out.push_str(&format!("  n{} --> n{}\n", n.id, rid)),
+                Reference::External(rid, path) => {
+                    let key = format!("{}#{}", path, rid);
+                    let next = externals.len();
+                    let name = externals.entry(key.clone()).or_insert_with(|| {
+                        out.push_str(&format!("  x{}[/\"{}\"/]\n", next, label(&key)));
+                        format!("x{}", next)
+                    });
+                    out.push_str(&format!("  n{} -.-> {}\n", n.id, name));
+                }
+            }
+        }
+    }
+    out
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Export a standalone HTML page: one entry per node with its references as links
+/// (internal ones to anchors on the page, external ones to the referenced file)
+pub fn cmd_export_html(mm: &Mindmap) -> String {
+    let incoming = incoming_map(mm);
+    let title = html_escape(&mm.path.display().to_string());
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>body{{font-family:sans-serif;max-width:60em;margin:auto}}\
+         dt{{margin-top:1em;font-weight:bold}}.refs{{color:#666;font-size:90%}}</style>\n\
+         </head>\n<body>\n<h1>{}</h1>\n<dl>\n",
+        title, title
+    );
+    for n in &mm.nodes {
+        // link references inside the description text
+        let mut desc = String::new();
+        let mut last = 0;
+        for (range, r) in crate::ref_spans(&n.description) {
+            desc.push_str(&html_escape(&n.description[last..range.start]));
+            let href = match &r {
+                Reference::Internal(rid) => format!("#n{}", rid),
+                Reference::External(_, path) => html_escape(path),
+            };
+            desc.push_str(&format!(
+                "<a href=\"{}\">{}</a>",
+                href,
+                html_escape(&n.description[range.clone()])
+            ));
+            last = range.end;
+        }
+        desc.push_str(&html_escape(&n.description[last..]));
+
+        let referrers: Vec<String> = incoming
+            .get(&n.id)
+            .map(|ids| {
+                ids.iter()
+                    .map(|i| format!("<a href=\"#n{}\">[{}]</a>", i, i))
+                    .collect()
+            })
+            .unwrap_or_default();
+        out.push_str(&format!(
+            "<dt id=\"n{}\">[{}] {}</dt>\n<dd>{}",
+            n.id,
+            n.id,
+            html_escape(&n.raw_title),
+            desc
+        ));
+        if !referrers.is_empty() {
+            out.push_str(&format!(
+                "<div class=\"refs\">Referred to by: {}</div>",
+                referrers.join(" ")
+            ));
+        }
+        out.push_str("</dd>\n");
+    }
+    out.push_str("</dl>\n</body>\n</html>\n");
+    out
+}
+
+/// Render a text export format (every format except sqlite)
+pub fn render(mm: &Mindmap, format: &ExportFormat) -> Result<String> {
+    match format {
+        ExportFormat::Csv => cmd_export_csv(mm, b','),
+        ExportFormat::Tsv => cmd_export_csv(mm, b'\t'),
+        ExportFormat::Json => cmd_export_json(mm),
+        ExportFormat::Dot => Ok(cmd_export_dot(mm)),
+        ExportFormat::Mermaid => Ok(cmd_export_mermaid(mm)),
+        ExportFormat::Html => Ok(cmd_export_html(mm)),
+        ExportFormat::Sqlite => Err(anyhow::anyhow!(
+            "sqlite is a database export; use cmd_export_sqlite"
+        )),
+    }
+}
+
+/// Write an export to `out`, replacing the file atomically (sqlite included)
+pub fn write_export(mm: &Mindmap, format: &ExportFormat, out: &Path) -> Result<()> {
+    if matches!(format, ExportFormat::Sqlite) {
+        cmd_export_sqlite(mm, out, false)?;
+        return Ok(());
+    }
+    let content = render(mm, format)?;
+    let dir = out
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)
+        .with_context(|| format!("Failed to create temp file in {}", dir.display()))?;
+    std::io::Write::write_all(&mut tmp, content.as_bytes())?;
+    tmp.persist(out)
+        .with_context(|| format!("Failed to write {}", out.display()))?;
+    Ok(())
+}
+
+const SQLITE_SCHEMA: &str = "
+CREATE TABLE files (
+    file_id INTEGER PRIMARY KEY,
+    path TEXT NOT NULL UNIQUE,
+    line_count INTEGER NOT NULL
+);
+CREATE TABLE nodes (
+    rowid INTEGER PRIMARY KEY,
+    file_id INTEGER NOT NULL REFERENCES files(file_id),
+    id INTEGER NOT NULL,
+    type TEXT,
+    title TEXT NOT NULL,
+    raw_title TEXT NOT NULL,
+    description TEXT NOT NULL,
+    line_index INTEGER NOT NULL,
+    UNIQUE (file_id, id)
+);
+CREATE TABLE edges (
+    file_id INTEGER NOT NULL REFERENCES files(file_id),
+    source_id INTEGER NOT NULL,
+    target_id INTEGER NOT NULL,
+    target_file_id INTEGER REFERENCES files(file_id),
+    external_path TEXT
+);
+CREATE INDEX edges_source ON edges(file_id, source_id);
+CREATE INDEX edges_target ON edges(target_file_id, target_id);
+CREATE VIRTUAL TABLE nodes_fts USING fts5(
+    type, title, description,
+    content='nodes', content_rowid='rowid'
+);
+";
+
+/// Row counts written by `cmd_export_sqlite`
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct SqliteExportStats {
+    pub files: usize,
+    pub nodes: usize,
+    pub edges: usize,
+}
+
+/// An edge recorded while writing nodes; its target file id is resolved once
+/// every file has been written
+struct PendingEdge {
+    file_id: i64,
+    source: u32,
+    target: u32,
+    target_file: Option<PathBuf>,
+    external_path: Option<String>,
+}
+
+/// Accumulates rows for one SQLite export transaction
+struct SqliteWriter<'a> {
+    tx: &'a rusqlite::Transaction<'a>,
+    file_ids: HashMap<PathBuf, i64>,
+    edges: Vec<PendingEdge>,
+    stats: SqliteExportStats,
+}
+
+impl SqliteWriter<'_> {
+    /// Insert one file and its nodes; returns the external files it references as
+    /// (canonical path, relative path as written)
+    fn write_file(
+        &mut self,
+        cache: &crate::cache::MindmapCache,
+        canonical: &Path,
+        line_count: usize,
+        nodes: &[crate::Node],
+    ) -> Result<Vec<(PathBuf, String)>> {
+        self.tx.execute(
+            "INSERT INTO files (path, line_count) VALUES (?1, ?2)",
+            rusqlite::params![canonical.to_string_lossy(), line_count as i64],
+        )?;
+        let file_id = self.tx.last_insert_rowid();
+        self.file_ids.insert(canonical.to_path_buf(), file_id);
+        self.stats.files += 1;
+
+        let mut externals = Vec::new();
+        for n in nodes {
+            let (typ, title) = split_title(&n.raw_title);
+            // duplicate ids keep the first occurrence (lint reports the rest)
+            self.stats.nodes += self.tx.execute(
+                "INSERT OR IGNORE INTO nodes \
+                 (file_id, id, type, title, raw_title, description, line_index) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                rusqlite::params![
+                    file_id,
+                    n.id,
+                    typ,
+                    title,
+                    n.raw_title,
+                    n.description,
+                    n.line_index as i64
+                ],
+            )?;
+
+            for r in &n.references {
+                let (target, target_file, external_path) = match r {
+                    Reference::Internal(rid) => (*rid, Some(canonical.to_path_buf()), None),
+                    Reference::External(rid, rel) => {
+                        let target_file = cache.resolve_path(canonical, rel).ok();
+                        if let Some(t) = &target_file {
+                            externals.push((t.clone(), rel.clone()));
+                        }
+                        (*rid, target_file, Some(rel.clone()))
+                    }
+                };
+                self.edges.push(PendingEdge {
+                    file_id,
+                    source: n.id,
+                    target,
+                    target_file,
+                    external_path,
+                });
+            }
+        }
+        Ok(externals)
+    }
+}
+
+/// Export the mindmap into a fresh SQLite database at `out`.
+///
+/// Files are keyed by canonical path. With `follow` (workspace mode), every file
+/// reachable through `[N](./file.md)` references is loaded through `MindmapCache`
+/// (same path safety and size rules as `--follow` navigation) and written into
+/// the same database. The database is built in a temp file next to `out` and then
+/// persisted, so an existing export is only replaced once the new one is complete.
+pub fn cmd_export_sqlite(mm: &Mindmap, out: &Path, follow: bool) -> Result<SqliteExportStats> {
+    let dir = out
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."));
+    let tmp = tempfile::NamedTempFile::new_in(&dir)
+        .with_context(|| format!("Failed to create temp file in {}", dir.display()))?;
+
+    let mut conn = rusqlite::Connection::open(tmp.path())?;
+    let tx = conn.transaction()?;
+    tx.execute_batch(SQLITE_SCHEMA)?;
+
+    let main_path = if mm.path.as_os_str() == "-" {
+        mm.path.clone()
+    } else {
+        std::fs::canonicalize(&mm.path).unwrap_or_else(|_| mm.path.clone())
+    };
+    let workspace = mm.path.parent().unwrap_or_else(|| Path::new("."));
+    let mut cache = crate::config::Config::for_mindmap(&mm.path)?
+        .cache
+        .cache(workspace);
+
+    let mut writer = SqliteWriter {
+        tx: &tx,
+        file_ids: HashMap::new(),
+        edges: Vec::new(),
+        stats: SqliteExportStats::default(),
+    };
+
+    let externals = writer.write_file(&cache, &main_path, mm.lines.len(), &mm.nodes)?;
+
+    if follow {
+        // breadth-first walk over referenced files: (canonical, base file, relative path)
+        let mut seen: HashSet<PathBuf> = HashSet::new();
+        seen.insert(main_path.clone());
+        let mut queue: VecDeque<(PathBuf, PathBuf, String)> = VecDeque::new();
+        for (canonical, rel) in externals {
+            if seen.insert(canonical.clone()) {
+                queue.push_back((canonical, main_path.clone(), rel));
+            }
+        }
+
+        let no_visited = HashSet::new();
+        while let Some((canonical, base, rel)) = queue.pop_front() {
+            let (line_count, nodes) = match cache.load(&base, &rel, &no_visited) {
+                Ok(ext_mm) => (ext_mm.lines.len(), ext_mm.nodes.clone()),
+                Err(e) => {
+                    eprintln!("Warning: skipping {}: {}", rel, e);
+                    continue;
+                }
+            };
+            let externals = writer.write_file(&cache, &canonical, line_count, &nodes)?;
+            for (next, next_rel) in externals {
+                if seen.insert(next.clone()) {
+                    queue.push_back((next, canonical.clone(), next_rel));
+                }
+            }
+        }
+    }
+
+    let SqliteWriter {
+        file_ids,
+        edges,
+        mut stats,
+        ..
+    } = writer;
+    for e in edges {
+        let target_file_id = e.target_file.and_then(|p| file_ids.get(&p).copied());
+        tx.execute(
+            "INSERT INTO edges (file_id, source_id, target_id, target_file_id, external_path) \
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![
+                e.file_id,
+                e.source,
+                e.target,
+                target_file_id,
+                e.external_path
+            ],
+        )?;
+        stats.edges += 1;
+    }
+
+    tx.execute("INSERT INTO nodes_fts(nodes_fts) VALUES ('rebuild')", [])?;
+    tx.commit()?;
+    conn.close().map_err(|(_, e)| e)?;
+
+    tmp.persist(out)
+        .with_context(|| format!("Failed to persist export to {}", out.display()))?;
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn load(content: &str) -> Result<Mindmap> {
+        Mindmap::load_from_reader(std::io::Cursor::new(content), PathBuf::from("-"))
+    }
+
+    #[test]
+    fn test_export_csv_columns() -> Result<()> {
+        let mm = load(
+            "[1] **AE: One** - first, with comma\n\n[2] **WF: Two** - uses [1] and [7](./x.md)\n",
+        )?;
+        let out = cmd_export_csv(&mm, b',')?;
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "id,type,title,description,outgoing,incoming,line");
+        assert_eq!(lines[1], "1,AE,One,\"first, with comma\",,[2],1");
+        assert_eq!(
+            lines[2],
+            "2,WF,Two,uses [1] and [7](./x.md),[1] [7](./x.md),,3"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_json_keeps_all_lines() -> Result<()> {
+        let mm = load("# Title\n\n[1] **AE: One** - see [2]\n\n[2] **Two** - x\n")?;
+        let doc: JsonDocument = serde_json::from_str(&cmd_export_json(&mm)?)?;
+        assert_eq!(doc.version, JSON_DOCUMENT_VERSION);
+        assert_eq!(doc.nodes.len(), 2);
+        assert_eq!(doc.nodes[0].node_type.as_deref(), Some("AE"));
+        assert_eq!(doc.nodes[0].title.as_deref(), Some("One"));
+        assert_eq!(doc.nodes[1].line_index, Some(4));
+        let idx: Vec<usize> = doc.lines.iter().map(|l| l.line_index).collect();
+        assert_eq!(idx, vec![0, 1, 3]);
+        assert_eq!(doc.lines[0].text, "# Title");
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_graph_formats() -> Result<()> {
+        let mm = load("[1] **AE: A \"q\"** - see [2] and [5](./x.md)\n[2] **B** - x < y\n")?;
+
+        let dot = cmd_export_dot(&mm);
+        assert!(dot.contains("  1 [label=\"1: AE: A \\\"q\\\"\"];"));
+        assert!(dot.contains("  1 -> 2;"));
+        assert!(dot.contains("  1 -> \"./x.md#5\";"));
+
+        let mermaid = cmd_export_mermaid(&mm);
+        assert!(mermaid.starts_with("graph LR\n"));
+        assert!(mermaid.contains("  n1[\"1: AE: A #quot;q#quot;\"]"));
+        assert!(mermaid.contains("  n1 --> n2"));
+        assert!(mermaid.contains("  n1 -.-> x0"));
+
+        let html = cmd_export_html(&mm);
+        assert!(
+            html.contains("see <a href=\"#n2\">[2]</a> and <a href=\"./x.md\">[5](./x.md)</a>")
+        );
+        assert!(html.contains("x &lt; y"));
+        assert!(html.contains("Referred to by: <a href=\"#n1\">[1]</a>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_tsv() -> Result<()> {
+        let mm = load("[1] **Untyped** - plain\n")?;
+        let out = cmd_export_csv(&mm, b'\t')?;
+        assert!(
+            out.lines()
+                .nth(1)
+                .unwrap()
+                .starts_with("1\t\tUntyped\tplain")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_sqlite_workspace() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let main = temp.path().join("MINDMAP.md");
+        std::fs::write(
+            &main,
+            "[1] **AE: Auth** - token service [2] [10](./other.md)\n\n[2] **AE: Store** - keeps sessions\n",
+        )?;
+        std::fs::write(
+            temp.path().join("other.md"),
+            "[10] **WF: Login** - login flow [1](./MINDMAP.md)\n",
+        )?;
+        let mm = Mindmap::load(main.clone())?;
+        let out = temp.path().join("map.db");
+
+        let stats = cmd_export_sqlite(&mm, &out, false)?;
+        assert_eq!((stats.files, stats.nodes, stats.edges), (1, 2, 2));
+
+        let stats = cmd_export_sqlite(&mm, &out, true)?;
+        assert_eq!((stats.files, stats.nodes, stats.edges), (2, 3, 3));
+
+        let conn = rusqlite::Connection::open(&out)?;
+        let title: String = conn.query_row(
+            "SELECT n.title FROM nodes_fts f JOIN nodes n ON n.rowid = f.rowid \
+             WHERE nodes_fts MATCH 'sessions'",
+            [],
+            |r| r.get(0),
+        )?;
+        assert_eq!(title, "Store");
+        // the external edge resolves to the other file's row
+        let target: String = conn.query_row(
+            "SELECT f.path FROM edges e JOIN files f ON f.file_id = e.target_file_id \
+             WHERE e.source_id = 1 AND e.target_id = 10",
+            [],
+            |r| r.get(0),
+        )?;
+        assert!(target.ends_with("other.md"));
+        Ok(())
+    }
+}
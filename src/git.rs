@@ -0,0 +1,211 @@
+//! Git: read-only access to the local repository
+//!
+//! This module provides:
+//! - Running `git` in the directory of a MINDMAP file (no network access is needed)
+//! - The commits touching a file, following renames
+//! - The content of a file at a revision (or in the index, with the empty revision)
+//! - Writing new content for a file into the index (leaving the working tree alone)
+
+use anyhow::{Context, Result, anyhow};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// One commit from `file_log`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Commit {
+    pub hash: String,
+    pub author: String,
+    pub email: String,
+    /// Author date, ISO 8601
+    pub date: String,
+    pub subject: String,
+    /// The file's path (relative to the repository root) in this commit
+    pub path: String,
+}
+
+/// Directory to run git in for `file`
+fn dir_of(file: &Path) -> PathBuf {
+    file.parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Run git in `dir` and return stdout; a non-zero exit is an error carrying stderr
+pub fn run(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("Failed to run git (is it installed?)")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Like `run`, feeding `input` on stdin
+pub fn run_with_input(dir: &Path, args: &[&str], input: &str) -> Result<String> {
+    let mut child = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run git (is it installed?)")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Commits that touched `file`, newest first, following renames
+pub fn file_log(file: &Path) -> Result<Vec<Commit>> {
+    let name = file
+        .file_name()
+        .ok_or_else(|| anyhow!("Not a file: {}", file.display()))?
+        .to_string_lossy();
+    let out = run(
+        &dir_of(file),
+        &[
+            "log",
+            "--follow",
+            "--name-only",
+            "--format=%x00%H%x1f%an%x1f%ae%x1f%aI%x1f%s",
+            "--",
+            &name,
+        ],
+    )?;
+
+    let mut commits = Vec::new();
+    for record in out.split('\0').filter(|r| !r.trim().is_empty()) {
+        let mut lines = record.lines();
+        let header: Vec<&str> = lines.next().unwrap_or_default().split('\x1f').collect();
+        let path = lines.find(|l| !l.trim().is_empty()).unwrap_or_default();
+        if let [hash, author, email, date, subject] = header.as_slice() {
+            commits.push(Commit {
+                hash: hash.to_string(),
+                author: author.to_string(),
+                email: email.to_string(),
+                date: date.to_string(),
+                subject: subject.to_string(),
+                path: path.trim().to_string(),
+            });
+        }
+    }
+    Ok(commits)
+}
+
+/// Content of `path` (relative to the repository root) at `rev`, run from the
+/// directory of `file`; `None` when the file does not exist at that revision
+pub fn show(file: &Path, rev: &str, path: &str) -> Result<Option<String>> {
+    let dir = dir_of(file);
+    let object = format!("{}:{}", rev, path);
+    // probe by exit status rather than stderr text, which is localised
+    if run(&dir, &["cat-file", "-e", &object]).is_err() {
+        return Ok(None);
+    }
+    run(&dir, &["show", &object]).map(Some)
+}
+
+/// Path of `file` relative to the repository root (for `rev:path` lookups)
+pub fn repo_path(file: &Path) -> Result<String> {
+    let name = file
+        .file_name()
+        .ok_or_else(|| anyhow!("Not a file: {}", file.display()))?
+        .to_string_lossy();
+    let prefix = run(&dir_of(file), &["rev-parse", "--show-prefix"])?;
+    Ok(format!("{}{}", prefix.trim(), name))
+}
+
+/// Staged (index) content of `file`; `None` when it is not in the index
+pub fn staged(file: &Path) -> Result<Option<String>> {
+    show(file, "", &repo_path(file)?)
+}
+
+/// Replace the staged content of `file` with `content`, keeping its file mode
+pub fn stage_content(file: &Path, content: &str) -> Result<()> {
+    let dir = dir_of(file);
+    let name = file
+        .file_name()
+        .ok_or_else(|| anyhow!("Not a file: {}", file.display()))?
+        .to_string_lossy();
+    let entry = run(&dir, &["ls-files", "-s", "--", &name])?;
+    let mode = entry
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| anyhow!("{} is not in the index", file.display()))?
+        .to_string();
+    let blob = run_with_input(&dir, &["hash-object", "-w", "--stdin"], content)?;
+    run(
+        &dir,
+        &[
+            "update-index",
+            "--cacheinfo",
+            &format!("{},{},{}", mode, blob.trim(), name),
+        ],
+    )?;
+    Ok(())
+}
+
+/// The hooks directory of the repository containing `dir` (honours `core.hooksPath`)
+pub fn hooks_dir(dir: &Path) -> Result<PathBuf> {
+    let out = run(dir, &["rev-parse", "--git-path", "hooks"])?;
+    Ok(dir.join(out.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_show_staged_and_stage_content() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let dir = temp.path();
+        if run(dir, &["init", "-q"]).is_err() {
+            return Ok(()); // git not available
+        }
+        run(dir, &["config", "user.email", "dev@example.com"])?;
+        run(dir, &["config", "user.name", "Dev"])?;
+        let file = dir.join("MINDMAP.md");
+
+        // not in the index yet
+        fs::write(&file, "[1] **AE: One** - first\n")?;
+        assert!(staged(&file)?.is_none());
+
+        run(dir, &["add", "MINDMAP.md"])?;
+        run(dir, &["commit", "-q", "-m", "one"])?;
+        assert_eq!(
+            show(&file, "HEAD", "MINDMAP.md")?.as_deref(),
+            Some("[1] **AE: One** - first\n")
+        );
+        assert!(show(&file, "HEAD", "missing.md")?.is_none());
+        assert!(show(&file, "HEAD^", "MINDMAP.md")?.is_none());
+
+        stage_content(&file, "[1] **AE: One** - staged\n")?;
+        assert_eq!(
+            staged(&file)?.as_deref(),
+            Some("[1] **AE: One** - staged\n")
+        );
+        // the working tree is left alone
+        assert_eq!(fs::read_to_string(&file)?, "[1] **AE: One** - first\n");
+        Ok(())
+    }
+}
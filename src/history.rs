@@ -0,0 +1,473 @@
+//! History: past versions of one node from the local git repository
+//!
+//! This module provides:
+//! - Every version of node N across the commits that touched the file (renames are
+//!   followed). Versions are matched by node ID rather than by line number, so moves
+//!   caused by spacing normalisation or insertions above do not lose the trail
+//! - A field-level diff per version: type, title, description and references
+//! - The uncommitted working-tree version when it differs from HEAD
+//! - `last_modified`: the date each node last changed, for staleness reports
+
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use std::{collections::HashMap, path::Path};
+
+use crate::{Mindmap, Node, export::format_reference, git, split_title};
+
+/// The compared parts of one version of a node
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeVersion {
+    pub r#type: Option<String>,
+    pub title: String,
+    pub description: String,
+    pub references: Vec<String>,
+    /// 1-based line number in that version
+    pub line: usize,
+}
+
+impl NodeVersion {
+    fn of(node: &Node) -> Self {
+        let (t, title) = split_title(&node.raw_title);
+        NodeVersion {
+            r#type: t.map(str::to_string),
+            title: title.to_string(),
+            description: node.description.clone(),
+            references: node.references.iter().map(format_reference).collect(),
+            line: node.line_index + 1,
+        }
+    }
+
+    /// Same content (the line number alone does not make a new version)
+    fn same_content(&self, other: &NodeVersion) -> bool {
+        self.r#type == other.r#type
+            && self.title == other.title
+            && self.description == other.description
+    }
+}
+
+/// A field that changed between two versions
+#[derive(Debug, Serialize)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+/// One entry of the history: the node as of `commit` and what changed
+#[derive(Debug, Serialize)]
+pub struct HistoryEntry {
+    /// None for the uncommitted working-tree version
+    pub commit: Option<git::Commit>,
+    /// "added", "modified" or "deleted"
+    pub change: &'static str,
+    /// The node after this change (None when deleted)
+    pub node: Option<NodeVersion>,
+    pub changes: Vec<FieldChange>,
+    pub refs_added: Vec<String>,
+    pub refs_removed: Vec<String>,
+}
+
+fn version_in(content: &str, id: u32) -> Result<Option<NodeVersion>> {
+    let mm = Mindmap::from_string(content.to_string(), "-".into())?;
+    Ok(mm.get_node(id).map(NodeVersion::of))
+}
+
+fn entry(
+    commit: Option<git::Commit>,
+    old: Option<&NodeVersion>,
+    new: Option<&NodeVersion>,
+) -> HistoryEntry {
+    let blank = NodeVersion {
+        r#type: None,
+        title: String::new(),
+        description: String::new(),
+        references: Vec::new(),
+        line: 0,
+    };
+    let (o, n) = (old.unwrap_or(&blank), new.unwrap_or(&blank));
+    let mut changes = Vec::new();
+    let fields = [
+        (
+            "type",
+            o.r#type.clone().unwrap_or_default(),
+            n.r#type.clone().unwrap_or_default(),
+        ),
+        ("title", o.title.clone(), n.title.clone()),
+        ("description", o.description.clone(), n.description.clone()),
+    ];
+    for (field, old, new) in fields {
+        if old != new {
+            changes.push(FieldChange { field, old, new });
+        }
+    }
+    HistoryEntry {
+        commit,
+        change: match (old, new) {
+            (None, _) => "added",
+            (_, None) => "deleted",
+            _ => "modified",
+        },
+        node: new.cloned(),
+        changes,
+        refs_added: n
+            .references
+            .iter()
+            .filter(|r| !o.references.contains(r))
+            .cloned()
+            .collect(),
+        refs_removed: o
+            .references
+            .iter()
+            .filter(|r| !n.references.contains(r))
+            .cloned()
+            .collect(),
+    }
+}
+
+/// Content of `file` in the parent of `commits[i]` (its first parent); across a
+/// rename the parent holds the file under the path of the next older commit
+fn parent_content(file: &Path, commits: &[git::Commit], i: usize) -> Result<Option<String>> {
+    let commit = &commits[i];
+    let parent = format!("{}^", commit.hash);
+    if let Some(content) = git::show(file, &parent, &commit.path)? {
+        return Ok(Some(content));
+    }
+    match commits.get(i + 1) {
+        Some(older) if older.path != commit.path => git::show(file, &parent, &older.path),
+        _ => Ok(None),
+    }
+}
+
+/// Content of `file` at HEAD, the base the working tree is compared with
+fn head_content(file: &Path) -> Result<Option<String>> {
+    git::show(file, "HEAD", &git::repo_path(file)?)
+}
+
+fn changed(old: Option<&NodeVersion>, new: Option<&NodeVersion>) -> bool {
+    match (old, new) {
+        (Some(o), Some(n)) => !o.same_content(n),
+        (None, None) => false,
+        _ => true,
+    }
+}
+
+/// History of node `id` in `file`, newest first
+///
+/// Each commit is compared with its own parent, so commits merged in from side
+/// branches are not mistaken for reverts of the commits listed next to them.
+pub fn node_history(file: &Path, id: u32) -> Result<Vec<HistoryEntry>> {
+    let commits = git::file_log(file)?;
+
+    let mut entries = Vec::new();
+    if let Ok(content) = std::fs::read_to_string(file) {
+        let old = match head_content(file)? {
+            Some(head) => version_in(&head, id)?,
+            None => None,
+        };
+        let new = version_in(&content, id)?;
+        if changed(old.as_ref(), new.as_ref()) {
+            entries.push(entry(None, old.as_ref(), new.as_ref()));
+        }
+    }
+
+    for i in 0..commits.len() {
+        let new = match git::show(file, &commits[i].hash, &commits[i].path)? {
+            Some(content) => version_in(&content, id)?,
+            None => None,
+        };
+        let old = match parent_content(file, &commits, i)? {
+            Some(content) => version_in(&content, id)?,
+            None => None,
+        };
+        if changed(old.as_ref(), new.as_ref()) {
+            entries.push(entry(Some(commits[i].clone()), old.as_ref(), new.as_ref()));
+        }
+    }
+
+    if entries.is_empty() {
+        return Err(anyhow!(
+            "Node [{}] has no history in {}",
+            id,
+            file.display()
+        ));
+    }
+    Ok(entries)
+}
+
+/// Date each node of `file` last changed content: the newest commit that changed it
+/// relative to its parent, or `today` when the working tree differs from HEAD
+pub fn last_modified(
+    file: &Path,
+    today: chrono::NaiveDate,
+) -> Result<HashMap<u32, chrono::NaiveDate>> {
+    let versions = |content: Option<String>| -> Result<HashMap<u32, NodeVersion>> {
+        let Some(content) = content else {
+            return Ok(HashMap::new());
+        };
+        let mm = Mindmap::from_string(content, "-".into())?;
+        Ok(mm
+            .nodes
+            .iter()
+            .map(|n| (n.id, NodeVersion::of(n)))
+            .collect())
+    };
+    let mut dates: HashMap<u32, chrono::NaiveDate> = HashMap::new();
+    let mut record = |old: &HashMap<u32, NodeVersion>,
+                      new: &HashMap<u32, NodeVersion>,
+                      date: chrono::NaiveDate| {
+        for (id, version) in new {
+            if changed(old.get(id), Some(version)) {
+                let d = dates.entry(*id).or_insert(date);
+                *d = (*d).max(date);
+            }
+        }
+    };
+
+    let commits = git::file_log(file)?;
+    for (i, commit) in commits.iter().enumerate() {
+        let date = chrono::DateTime::parse_from_rfc3339(&commit.date)
+            .map(|d| d.date_naive())
+            .unwrap_or(today);
+        let new = versions(git::show(file, &commit.hash, &commit.path)?)?;
+        let old = versions(parent_content(file, &commits, i)?)?;
+        record(&old, &new, date);
+    }
+    if let Ok(content) = std::fs::read_to_string(file) {
+        let old = versions(head_content(file)?)?;
+        record(&old, &versions(Some(content))?, today);
+    }
+    Ok(dates)
+}
+
+/// Human-readable rendering, one block per version
+pub fn format_history(id: u32, entries: &[HistoryEntry]) -> Vec<String> {
+    let mut out = Vec::new();
+    for e in entries {
+        let header = match &e.commit {
+            Some(c) => format!(
+                "{} {} {} <{}>  {}",
+                &c.hash[..c.hash.len().min(10)],
+                c.date.get(..10).unwrap_or(&c.date),
+                c.author,
+                c.email,
+                c.subject
+            ),
+            None => "(working tree)".to_string(),
+        };
+        out.push(header);
+        match &e.node {
+            Some(n) => {
+                let raw_title = match &n.r#type {
+                    Some(t) => format!("{}: {}", t, n.title),
+                    None => n.title.clone(),
+                };
+                out.push(format!(
+                    "  {} (line {}): [{}] **{}** - {}",
+                    e.change, n.line, id, raw_title, n.description
+                ));
+            }
+            None => out.push(format!("  {}", e.change)),
+        }
+        if e.change == "modified" {
+            for c in &e.changes {
+                out.push(format!("    {}: {:?} -> {:?}", c.field, c.old, c.new));
+            }
+            for r in &e.refs_added {
+                out.push(format!("    + ref {}", r));
+            }
+            for r in &e.refs_removed {
+                out.push(format!("    - ref {}", r));
+            }
+        }
+        out.push(String::new());
+    }
+    out.pop();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn git(dir: &Path, args: &[&str]) -> Result<()> {
+        git::run(dir, args).map(|_| ())
+    }
+
+    #[test]
+    fn test_history_follows_node_by_id() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let dir = temp.path();
+        if git(dir, &["init", "-q"]).is_err() {
+            return Ok(()); // git not available
+        }
+        git(dir, &["config", "user.email", "dev@example.com"])?;
+        git(dir, &["config", "user.name", "Dev"])?;
+        let file = dir.join("MINDMAP.md");
+        let commit = |content: &str, msg: &str| -> Result<()> {
+            fs::write(&file, content)?;
+            git(dir, &["add", "MINDMAP.md"])?;
+            git(dir, &["commit", "-q", "-m", msg])
+        };
+
+        commit("[1] **AE: Auth** - login\n", "add auth")?;
+        commit(
+            "[2] **WF: Other** - x\n\n[1] **AE: Auth** - login\n",
+            "insert above",
+        )?;
+        commit(
+            "[2] **WF: Other** - x\n\n[1] **AE: Authn** - login via [2]\n",
+            "rename",
+        )?;
+        fs::write(&file, "[2] **WF: Other** - x\n")?;
+
+        let h = node_history(&file, 1)?;
+        assert_eq!(h.len(), 3);
+        assert!(h[0].commit.is_none());
+        assert_eq!(h[0].change, "deleted");
+
+        assert_eq!(h[1].commit.as_ref().unwrap().subject, "rename");
+        assert_eq!(h[1].node.as_ref().unwrap().line, 3);
+        assert_eq!(h[1].changes[0].field, "title");
+        assert_eq!(h[1].changes[0].new, "Authn");
+        assert_eq!(h[1].refs_added, vec!["[2]".to_string()]);
+
+        assert_eq!(h[2].change, "added");
+        assert_eq!(h[2].commit.as_ref().unwrap().author, "Dev");
+
+        let text = format_history(1, &h);
+        assert!(text.contains(&"    title: \"Auth\" -> \"Authn\"".to_string()));
+        assert!(node_history(&file, 9).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_last_modified_per_node() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let dir = temp.path();
+        if git(dir, &["init", "-q"]).is_err() {
+            return Ok(()); // git not available
+        }
+        git(dir, &["config", "user.email", "dev@example.com"])?;
+        git(dir, &["config", "user.name", "Dev"])?;
+        let file = dir.join("MINDMAP.md");
+        let commit = |content: &str, date: &str| -> Result<()> {
+            fs::write(&file, content)?;
+            git(dir, &["add", "MINDMAP.md"])?;
+            git(
+                dir,
+                &["commit", "-q", "-m", "c", &format!("--date={}", date)],
+            )
+        };
+        let day = |s: &str| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+
+        commit(
+            "[1] **AE: A** - a\n\n[2] **AE: B** - b\n",
+            "2020-01-01T12:00:00Z",
+        )?;
+        commit(
+            "[2] **AE: B** - b\n\n[1] **AE: A** - changed\n",
+            "2021-06-01T12:00:00Z",
+        )?;
+        fs::write(
+            &file,
+            "[2] **AE: B** - b\n\n[1] **AE: A** - changed\n\n[3] **AE: C** - new\n",
+        )?;
+
+        let dates = last_modified(&file, day("2026-10-18"))?;
+        assert_eq!(dates[&1], day("2021-06-01"));
+        assert_eq!(dates[&2], day("2020-01-01"));
+        assert_eq!(dates[&3], day("2026-10-18"));
+        Ok(())
+    }
+
+    /// Repository with a side branch merged back: node 1 changes on the side branch,
+    /// node 2 on main after the branch point (committed later than the side change)
+    fn merged_repo(dir: &Path) -> Result<Option<std::path::PathBuf>> {
+        if git(dir, &["init", "-q"]).is_err() {
+            return Ok(None); // git not available
+        }
+        git(dir, &["config", "user.email", "dev@example.com"])?;
+        git(dir, &["config", "user.name", "Dev"])?;
+        let file = dir.join("MINDMAP.md");
+        let doc = |one: &str, two: &str| {
+            format!(
+                "[1] **AE: A** - {}\n\n[3] **AE: C** - c\n\n[4] **AE: D** - d\n\n\
+                 [5] **AE: E** - e\n\n[2] **AE: B** - {}\n",
+                one, two
+            )
+        };
+        let commit = |content: &str, msg: &str, date: &str| -> Result<()> {
+            fs::write(&file, content)?;
+            git(dir, &["add", "MINDMAP.md"])?;
+            let status = std::process::Command::new("git")
+                .args(["commit", "-q", "-m", msg])
+                .env("GIT_AUTHOR_DATE", date)
+                .env("GIT_COMMITTER_DATE", date)
+                .current_dir(dir)
+                .status()?;
+            assert!(status.success());
+            Ok(())
+        };
+
+        commit(&doc("a", "b"), "base", "2020-01-01T12:00:00Z")?;
+        git(dir, &["checkout", "-q", "-b", "side"])?;
+        commit(&doc("a side", "b"), "side", "2021-01-01T12:00:00Z")?;
+        git(dir, &["checkout", "-q", "-"])?;
+        commit(&doc("a", "b main"), "main", "2022-01-01T12:00:00Z")?;
+        git(dir, &["merge", "-q", "--no-edit", "side"])?;
+        assert_eq!(fs::read_to_string(&file)?, doc("a side", "b main"));
+        Ok(Some(file))
+    }
+
+    #[test]
+    fn test_history_across_merge() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let Some(file) = merged_repo(temp.path())? else {
+            return Ok(());
+        };
+
+        let h = node_history(&file, 1)?;
+        // the main commit did not touch node 1, and nothing is uncommitted
+        assert!(h.iter().all(|e| e.commit.is_some()));
+        assert!(
+            h.iter()
+                .all(|e| e.commit.as_ref().unwrap().subject != "main")
+        );
+        assert!(
+            h.iter()
+                .flat_map(|e| &e.changes)
+                .all(|c| !(c.old == "a side" && c.new == "a"))
+        );
+        let side = h
+            .iter()
+            .find(|e| e.commit.as_ref().unwrap().subject == "side")
+            .unwrap();
+        assert_eq!(side.changes[0].old, "a");
+        assert_eq!(side.changes[0].new, "a side");
+        assert_eq!(h.last().unwrap().change, "added");
+
+        let h = node_history(&file, 2)?;
+        assert!(
+            h.iter()
+                .all(|e| e.commit.as_ref().unwrap().subject != "side")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_last_modified_across_merge() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let Some(file) = merged_repo(temp.path())? else {
+            return Ok(());
+        };
+        let day = |s: &str| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+
+        let dates = last_modified(&file, day("2026-10-18"))?;
+        assert_eq!(dates[&2], day("2022-01-01"));
+        assert_eq!(dates[&3], day("2020-01-01"));
+        // the side change reaches main through the merge, never the working tree
+        assert_ne!(dates[&1], day("2026-10-18"));
+        Ok(())
+    }
+}
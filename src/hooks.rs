@@ -0,0 +1,216 @@
+//! Hooks: git pre-commit integration
+//!
+//! This module provides:
+//! - `hooks install` / `hooks uninstall` for a pre-commit hook that lints every staged
+//!   MINDMAP.md, plus the configured mindmap file when it has another name
+//! - `lint --staged`: lint the staged blob (`git show :path`) rather than the working
+//!   tree, so partially staged files are checked as they will be committed
+//! - With `--fix`, `apply_fixes` on the staged content and re-stage it (the working
+//!   tree is updated too when it matched the index)
+
+use anyhow::{Result, anyhow};
+use std::{fs, path::Path};
+
+use crate::{Mindmap, cmd_lint, config::Config, git, lint::Finding};
+
+/// First lines of every hook written by `install`; used to recognise our own hook
+const HOOK_MARKER: &str = "# mindmap-cli pre-commit hook";
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// `pathspecs` are repository-relative paths linted besides every MINDMAP.md
+fn hook_script(exe: &str, pathspecs: &[String], fix: bool) -> String {
+    let extra: String = pathspecs
+        .iter()
+        .map(|p| format!(" {}", shell_quote(&format!(":(literal){}", p))))
+        .collect();
+    format!(
+        "#!/bin/sh\n\
+         {} (installed by `mindmap-cli hooks install`)\n\
+         # Lints the staged content of MINDMAP files; remove this file to disable.\n\
+         MINDMAP_CLI=${{MINDMAP_CLI:-{}}}\n\
+         git diff --cached --name-only --diff-filter=ACMR -- 'MINDMAP.md' '*/MINDMAP.md'{} |\n\
+         while IFS= read -r f; do\n\
+         \x20   \"$MINDMAP_CLI\" --file \"$f\" lint --staged{} || exit 1\n\
+         done\n",
+        HOOK_MARKER,
+        shell_quote(exe),
+        extra,
+        if fix { " --fix" } else { "" }
+    )
+}
+
+/// Write the pre-commit hook for the repository containing `file` (the configured
+/// mindmap, linted even when not named MINDMAP.md). An existing hook that was not
+/// written by us is only replaced with `force`.
+pub fn install(file: &Path, fix: bool, force: bool) -> Result<std::path::PathBuf> {
+    let dir = file
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let hooks = git::hooks_dir(dir)?;
+    let hook = hooks.join("pre-commit");
+    if let Ok(existing) = fs::read_to_string(&hook)
+        && !existing.contains(HOOK_MARKER)
+        && !force
+    {
+        return Err(anyhow!(
+            "{} already exists and was not installed by mindmap-cli; use --force to replace it",
+            hook.display()
+        ));
+    }
+
+    let exe = std::env::current_exe()
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| "mindmap-cli".to_string());
+    let pathspecs = if file.file_name() == Some("MINDMAP.md".as_ref()) {
+        Vec::new()
+    } else {
+        vec![git::repo_path(file)?]
+    };
+    fs::create_dir_all(&hooks)?;
+    fs::write(&hook, hook_script(&exe, &pathspecs, fix))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(hook)
+}
+
+/// Remove the pre-commit hook if it is ours
+pub fn uninstall(dir: &Path) -> Result<std::path::PathBuf> {
+    let hook = git::hooks_dir(dir)?.join("pre-commit");
+    match fs::read_to_string(&hook) {
+        Ok(existing) if existing.contains(HOOK_MARKER) => {
+            fs::remove_file(&hook)?;
+            Ok(hook)
+        }
+        Ok(_) => Err(anyhow!(
+            "{} was not installed by mindmap-cli; leaving it in place",
+            hook.display()
+        )),
+        Err(_) => Err(anyhow!("No pre-commit hook at {}", hook.display())),
+    }
+}
+
+/// Outcome of `lint_staged`
+#[derive(Debug, serde::Serialize)]
+pub struct StagedLint {
+    pub findings: Vec<Finding>,
+    /// Fixes were applied and re-staged
+    pub fixed: bool,
+    /// The working tree was left alone because it differs from the index
+    pub working_tree_untouched: bool,
+}
+
+/// Lint the staged content of `file` with its `config`; with `fix`, apply fixes to
+/// it and re-stage
+pub fn lint_staged(file: &Path, fix: bool, config: &Config) -> Result<StagedLint> {
+    let content =
+        git::staged(file)?.ok_or_else(|| anyhow!("{} is not in the git index", file.display()))?;
+    let mut mm = Mindmap::load_from_reader(content.as_bytes(), file.to_path_buf())?;
+
+    let mut fixed = false;
+    let mut working_tree_untouched = false;
+    if fix {
+        let report = mm.apply_fixes()?;
+        if report.any_changes() {
+            git::stage_content(file, &(mm.lines.join("\n") + "\n"))?;
+            fixed = true;
+            // partial staging: the working tree has other edits, do not overwrite them
+            if fs::read_to_string(file).ok().as_deref() == Some(content.as_str()) {
+                mm.save()?;
+            } else {
+                working_tree_untouched = true;
+            }
+        }
+    }
+
+    Ok(StagedLint {
+        findings: cmd_lint(&mm, config)?,
+        fixed,
+        working_tree_untouched,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo(temp: &tempfile::TempDir) -> Result<bool> {
+        let dir = temp.path();
+        if git::run(dir, &["init", "-q"]).is_err() {
+            return Ok(false); // git not available
+        }
+        git::run(dir, &["config", "user.email", "dev@example.com"])?;
+        git::run(dir, &["config", "user.name", "Dev"])?;
+        Ok(true)
+    }
+
+    #[test]
+    fn test_install_and_uninstall() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        if !repo(&temp)? {
+            return Ok(());
+        }
+        let file = temp.path().join("MINDMAP.md");
+        let hook = install(&file, true, false)?;
+        let script = fs::read_to_string(&hook)?;
+        assert!(script.starts_with("#!/bin/sh\n# mindmap-cli pre-commit hook"));
+        assert!(script.contains("-- 'MINDMAP.md' '*/MINDMAP.md' |"));
+        assert!(script.contains("lint --staged --fix || exit 1"));
+        // reinstalling over our own hook is fine
+        install(&file, false, false)?;
+
+        // a configured file with another name is linted too
+        fs::create_dir(temp.path().join("docs"))?;
+        install(&temp.path().join("docs").join("ARCH.md"), false, false)?;
+        assert!(
+            fs::read_to_string(&hook)?
+                .contains("-- 'MINDMAP.md' '*/MINDMAP.md' ':(literal)docs/ARCH.md' |")
+        );
+
+        fs::write(&hook, "#!/bin/sh\necho custom\n")?;
+        assert!(install(&file, false, false).is_err());
+        assert!(uninstall(temp.path()).is_err());
+        install(&file, false, true)?;
+        uninstall(temp.path())?;
+        assert!(!hook.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lint_staged_reads_the_index() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        if !repo(&temp)? {
+            return Ok(());
+        }
+        let file = temp.path().join("MINDMAP.md");
+        fs::write(&file, "[1] **AE: AE: One** - see [2]\n")?;
+        git::run(temp.path(), &["add", "MINDMAP.md"])?;
+        // the working tree is fine, the staged blob is not
+        fs::write(&file, "[1] **AE: One** - ok\n")?;
+
+        let res = lint_staged(&file, false, &Config::default())?;
+        let missing: Vec<String> = res
+            .findings
+            .iter()
+            .filter(|f| f.rule == "missing-ref")
+            .map(|f| f.to_string())
+            .collect();
+        assert_eq!(
+            missing,
+            vec!["Missing ref: node 1 references missing node 2".to_string()]
+        );
+
+        let res = lint_staged(&file, true, &Config::default())?;
+        assert!(res.fixed);
+        assert!(res.working_tree_untouched);
+        assert_eq!(git::staged(&file)?.unwrap(), "[1] **AE: One** - see [2]\n");
+        assert_eq!(fs::read_to_string(&file)?, "[1] **AE: One** - ok\n");
+        Ok(())
+    }
+}
@@ -0,0 +1,416 @@
+//! HTTP: local REST API over the mindmap
+//!
+//! This module provides:
+//! - `GET /nodes`, `GET /nodes/{id}`, `POST /nodes`, `PATCH /nodes/{id}`,
+//!   `DELETE /nodes/{id}` and `POST /batch` with JSON bodies
+//! - Writes through the same `cmd_*` functions and atomic `Mindmap::save` as the CLI
+//!   (via the MCP `Session`, which keeps the map loaded)
+//! - ETags derived from the blake3 file hash: `If-Match` guards writes (412 on
+//!   mismatch), `If-None-Match` answers reads with 304
+//! - Loopback addresses only, unless `--allow-remote` is given
+
+use anyhow::{Result, anyhow};
+use serde_json::{Value, json};
+
+use crate::{
+    Mindmap,
+    config::Config,
+    mcp::{Session, ToolError, call_tool},
+};
+
+/// A routed response; the ETag reflects the file after the request
+#[derive(Debug)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Option<Value>,
+    pub etag: Option<String>,
+    pub location: Option<String>,
+}
+
+/// Decode a URL query component (`%XX` escapes and `+` for space)
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => match s
+                .get(i + 1..i + 3)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+            {
+                Some(b) => {
+                    out.push(b);
+                    i += 3;
+                    continue;
+                }
+                None => out.push(b'%'),
+            },
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Tool arguments that are flags; other query values stay strings
+const BOOL_ARGS: [&str; 6] = [
+    "regex",
+    "case_sensitive",
+    "exact_match",
+    "force",
+    "strict",
+    "fix",
+];
+
+/// Query string as tool arguments; for the flags in `BOOL_ARGS`, "true"/"1" and
+/// "false"/"0" (or a bare key) become booleans
+fn query_args(query: &str) -> Value {
+    let mut args = serde_json::Map::new();
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (k, v) = pair.split_once('=').unwrap_or((pair, "true"));
+        let (k, v) = (percent_decode(k), percent_decode(v));
+        let value = match v.as_str() {
+            "true" | "1" if BOOL_ARGS.contains(&k.as_str()) => json!(true),
+            "false" | "0" if BOOL_ARGS.contains(&k.as_str()) => json!(false),
+            _ => json!(v),
+        };
+        args.insert(k, value);
+    }
+    Value::Object(args)
+}
+
+/// Refuse to listen on `addr` unless it resolves only to loopback addresses or
+/// `allow_remote` is set: the API has no authentication
+fn check_local(addr: &str, allow_remote: bool) -> Result<()> {
+    use std::net::ToSocketAddrs;
+    let resolved: Vec<_> = addr
+        .to_socket_addrs()
+        .map_err(|e| anyhow!("Invalid address {}: {}", addr, e))?
+        .collect();
+    if allow_remote || resolved.iter().all(|a| a.ip().is_loopback()) {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "{} is not a loopback address; the API is unauthenticated. \
+         Use e.g. 127.0.0.1:8080, or pass --allow-remote to expose it",
+        addr
+    ))
+}
+
+fn error_status(e: &anyhow::Error) -> u16 {
+    match e.downcast_ref::<ToolError>() {
+        Some(ToolError::NotFound(_)) => 404,
+        Some(ToolError::Conflict(_)) => 409,
+        None => 400,
+    }
+}
+
+fn parse_body(body: &str) -> Result<Value> {
+    if body.trim().is_empty() {
+        return Ok(json!({}));
+    }
+    serde_json::from_str(body).map_err(|e| anyhow!("Invalid JSON body: {}", e))
+}
+
+fn parse_id(s: &str) -> Result<u32> {
+    s.parse().map_err(|_| anyhow!("Invalid node id '{}'", s))
+}
+
+fn tool(session: &mut Session, name: &str, args: &Value) -> Result<Value> {
+    call_tool(session, name, args)?.ok_or_else(|| anyhow!("Unknown tool: {}", name))
+}
+
+/// Route one request. `if_match` / `if_none_match` are the raw header values.
+pub fn handle(
+    session: &mut Session,
+    method: &str,
+    url: &str,
+    if_match: Option<&str>,
+    if_none_match: Option<&str>,
+    body: &str,
+) -> HttpResponse {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let writes = !matches!(method, "GET" | "HEAD");
+
+    let mut location = None;
+    let result: Result<(u16, Option<Value>), (u16, String)> = (|| {
+        let fail = |e: anyhow::Error| (error_status(&e), e.to_string());
+        session.refresh().map_err(fail)?;
+        let etag = format!("\"{}\"", session.hash());
+
+        if writes
+            && let Some(tag) = if_match
+            && tag.trim() != "*"
+            && tag.trim() != etag
+        {
+            return Err((412, format!("ETag mismatch: the file is now at {}", etag)));
+        }
+        if !writes && if_none_match.is_some_and(|tag| tag.trim() == etag || tag.trim() == "*") {
+            return Ok((304, None));
+        }
+
+        match (method, segments.as_slice()) {
+            ("GET" | "HEAD", ["nodes"]) => {
+                let out = tool(session, "list", &query_args(query)).map_err(fail)?;
+                Ok((200, Some(out)))
+            }
+            ("GET" | "HEAD", ["nodes", id]) => {
+                let args = json!({"id": parse_id(id).map_err(fail)?});
+                let node = tool(session, "show", &args).map_err(fail)?;
+                let refs = tool(session, "refs", &args).map_err(fail)?;
+                let links = tool(session, "links", &args).map_err(fail)?;
+                Ok((
+                    200,
+                    Some(json!({"node": node, "refs": refs["nodes"], "links": links["links"]})),
+                ))
+            }
+            ("POST", ["nodes"]) => {
+                let args = parse_body(body).map_err(fail)?;
+                let out = tool(session, "add", &args).map_err(fail)?;
+                location = out["node"]["id"]
+                    .as_u64()
+                    .map(|id| format!("/nodes/{}", id));
+                Ok((201, Some(out)))
+            }
+            ("PATCH", ["nodes", id]) => {
+                let mut args = parse_body(body).map_err(fail)?;
+                let id = parse_id(id).map_err(fail)?;
+                args.as_object_mut()
+                    .ok_or((400, "Body must be a JSON object".to_string()))?
+                    .insert("id".to_string(), json!(id));
+                let out = tool(session, "patch", &args).map_err(fail)?;
+                Ok((200, Some(out)))
+            }
+            ("DELETE", ["nodes", id]) => {
+                let mut args = query_args(query);
+                if let Some(obj) = args.as_object_mut() {
+                    obj.insert("id".to_string(), json!(parse_id(id).map_err(fail)?));
+                }
+                let out = tool(session, "delete", &args).map_err(fail)?;
+                Ok((200, Some(out)))
+            }
+            ("POST", ["batch"]) => {
+                // either {"ops": [...], "fix": bool} or a bare array of ops
+                let args = match parse_body(body).map_err(fail)? {
+                    Value::Array(ops) => json!({"ops": ops}),
+                    other => other,
+                };
+                let out = tool(session, "batch", &args).map_err(fail)?;
+                Ok((200, Some(out)))
+            }
+            (_, ["nodes"] | ["nodes", _] | ["batch"]) => {
+                Err((405, format!("Method {} not allowed on {}", method, path)))
+            }
+            _ => Err((404, format!("No route for {}", path))),
+        }
+    })();
+
+    let (status, body) = match result {
+        Ok((status, body)) => (status, body),
+        Err((status, message)) => (status, Some(json!({"error": message}))),
+    };
+    HttpResponse {
+        status,
+        body: if method == "HEAD" { None } else { body },
+        etag: (!session.hash().is_empty()).then(|| format!("\"{}\"", session.hash())),
+        location,
+    }
+}
+
+fn header(request: &tiny_http::Request, name: &'static str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str().to_string())
+}
+
+/// Serve the REST API on `addr` (e.g. 127.0.0.1:8080) until the process is stopped
+pub fn run(mm: Mindmap, addr: &str, allow_remote: bool, config: Config) -> Result<()> {
+    check_local(addr, allow_remote)?;
+    let server = tiny_http::Server::http(addr)
+        .map_err(|e| anyhow!("Failed to listen on {}: {}", addr, e))?;
+    eprintln!("Serving {} on http://{}", mm.path.display(), addr);
+    let mut session = Session::new(mm, config)?;
+
+    for mut request in server.incoming_requests() {
+        let if_match = header(&request, "If-Match");
+        let if_none_match = header(&request, "If-None-Match");
+
+        let mut body = String::new();
+        let response = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) => handle(
+                &mut session,
+                request.method().as_str(),
+                request.url(),
+                if_match.as_deref(),
+                if_none_match.as_deref(),
+                &body,
+            ),
+            Err(e) => HttpResponse {
+                status: 400,
+                body: Some(json!({"error": format!("Failed to read request body: {}", e)})),
+                etag: None,
+                location: None,
+            },
+        };
+
+        let text = response
+            .body
+            .as_ref()
+            .map(|b| serde_json::to_string_pretty(b).unwrap_or_default())
+            .unwrap_or_default();
+        let mut reply = tiny_http::Response::from_string(text).with_status_code(response.status);
+        let mut headers = vec![("Content-Type", "application/json".to_string())];
+        if let Some(etag) = response.etag {
+            headers.push(("ETag", etag));
+        }
+        if let Some(location) = response.location {
+            headers.push(("Location", location));
+        }
+        for (name, value) in headers {
+            if let Ok(h) = tiny_http::Header::from_bytes(name.as_bytes(), value.as_bytes()) {
+                reply.add_header(h);
+            }
+        }
+        if let Err(e) = request.respond(reply) {
+            eprintln!("Warning: failed to send response: {}", e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(temp: &tempfile::TempDir, content: &str) -> Result<Session> {
+        let path = temp.path().join("MINDMAP.md");
+        std::fs::write(&path, content)?;
+        let config = Config::for_mindmap(&path)?;
+        Session::new(Mindmap::load(path)?, config)
+    }
+
+    #[test]
+    fn test_percent_decode_and_query() {
+        assert_eq!(percent_decode("a%20b+c"), "a b c");
+        let args = query_args("type=AE&grep=auth%20svc&regex=1");
+        assert_eq!(
+            args,
+            json!({"type": "AE", "grep": "auth svc", "regex": true})
+        );
+        assert_eq!(
+            query_args("grep=1&force=false"),
+            json!({"grep": "1", "force": false})
+        );
+    }
+
+    #[test]
+    fn test_check_local() {
+        assert!(check_local("127.0.0.1:8080", false).is_ok());
+        assert!(check_local("[::1]:8080", false).is_ok());
+        assert!(check_local("0.0.0.0:8080", false).is_err());
+        assert!(check_local("0.0.0.0:8080", true).is_ok());
+    }
+
+    #[test]
+    fn test_read_routes_and_etag() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let mut s = session(
+            &temp,
+            "[1] **AE: Auth** - login\n\n[2] **WF: Flow** - uses [1]\n",
+        )?;
+
+        let list = handle(&mut s, "GET", "/nodes?type=WF", None, None, "");
+        assert_eq!(list.status, 200);
+        assert_eq!(list.body.unwrap()["count"], 1);
+
+        // a numeric grep stays a string filter
+        let digits = handle(&mut s, "GET", "/nodes?grep=1", None, None, "");
+        assert_eq!(digits.status, 200);
+        assert_eq!(digits.body.unwrap()["count"], 1);
+
+        let one = handle(&mut s, "GET", "/nodes/1", None, None, "");
+        let body = one.body.unwrap();
+        assert_eq!(body["node"]["title"], "Auth");
+        assert_eq!(body["refs"][0]["id"], 2);
+
+        let etag = one.etag.unwrap();
+        let cached = handle(&mut s, "GET", "/nodes/1", None, Some(&etag), "");
+        assert_eq!(cached.status, 304);
+
+        assert_eq!(
+            handle(&mut s, "GET", "/nodes/9", None, None, "").status,
+            404
+        );
+        assert_eq!(
+            handle(&mut s, "PUT", "/nodes/1", None, None, "").status,
+            405
+        );
+        assert_eq!(handle(&mut s, "GET", "/other", None, None, "").status, 404);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_routes_with_if_match() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let mut s = session(&temp, "[1] **AE: Auth** - login\n")?;
+        let etag = handle(&mut s, "GET", "/nodes", None, None, "")
+            .etag
+            .unwrap();
+
+        let created = handle(
+            &mut s,
+            "POST",
+            "/nodes",
+            Some(&etag),
+            None,
+            r#"{"type": "WF", "title": "Flow", "desc": "uses [1]"}"#,
+        );
+        assert_eq!(created.status, 201);
+        assert_eq!(created.location.as_deref(), Some("/nodes/2"));
+        assert_ne!(created.etag.as_deref(), Some(etag.as_str()));
+
+        // the old ETag is stale now
+        let stale = handle(
+            &mut s,
+            "PATCH",
+            "/nodes/1",
+            Some(&etag),
+            None,
+            r#"{"desc": "x"}"#,
+        );
+        assert_eq!(stale.status, 412);
+
+        let referenced = handle(&mut s, "DELETE", "/nodes/1", None, None, "");
+        assert_eq!(referenced.status, 400);
+        let missing = handle(&mut s, "PATCH", "/nodes/9", None, None, r#"{"desc": "x"}"#);
+        assert_eq!(missing.status, 404);
+        // a failing op inside a batch is a bad request, whatever its message says
+        let bad_batch = handle(
+            &mut s,
+            "POST",
+            "/batch",
+            None,
+            None,
+            r#"[{"op": "delete", "id": 9}]"#,
+        );
+        assert_eq!(bad_batch.status, 400);
+
+        let batch = handle(
+            &mut s,
+            "POST",
+            "/batch",
+            None,
+            None,
+            r#"[{"op": "patch", "id": 1, "desc": "logins"}, {"op": "delete", "id": 2}]"#,
+        );
+        assert_eq!(batch.status, 200);
+        let content = std::fs::read_to_string(temp.path().join("MINDMAP.md"))?;
+        assert!(content.contains("[1] **AE: Auth** - logins"));
+        assert!(!content.contains("[2]"));
+        Ok(())
+    }
+}
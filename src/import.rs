@@ -0,0 +1,930 @@
+//! Import: create or update nodes from external formats
+//!
+//! This module provides:
+//! - CSV / TSV import (rows matched to nodes by ID)
+//! - Outline import (markdown headings and bullet lists become new nodes)
+//! - MINDMAP merge (nodes of another MINDMAP file, colliding IDs remapped)
+//! - JSON import (the whole document regenerated from `export --format json`)
+//!
+//! Importers only stage changes on the given `Mindmap` (JSON import replaces its
+//! content wholesale); persisting (with dry-run preview and the blake3
+//! concurrency guard) is left to the caller, exactly like `batch`.
+
+use anyhow::Result;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    BatchResult, Mindmap, Reference,
+    cache::MindmapCache,
+    cmd_add, cmd_add_with_id, cmd_patch, cmd_put,
+    config::TypesConfig,
+    export::{JSON_DOCUMENT_VERSION, JsonDocument, JsonNode},
+    parse_node_line, rewrite_refs,
+};
+
+/// How an imported node's ID was mapped into the target mindmap
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct IdMapping {
+    pub old: u32,
+    pub new: u32,
+    /// True when the node was merged into an existing node with the same title
+    pub merged: bool,
+}
+
+/// CSV cell value that clears a node's type or description on update
+const CLEAR: &str = "-";
+
+/// Flatten multi-line cell content into a single line (nodes are one line each)
+fn one_line(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Import nodes from delimited text (`b','` for CSV, `b'\t'` for TSV).
+///
+/// The first row must be a header; `id`, `type`, `title` and `description`
+/// (or `desc`) columns are recognised, any other column (e.g. the derived
+/// `outgoing`, `incoming` and `line` columns of an export) is ignored.
+/// Rows with an empty id are added under the next free id; rows whose id exists
+/// update that node (`cmd_put`); rows with an id not yet in use are added under
+/// exactly that id, so references to it keep pointing at the row's node.
+///
+/// When updating, an empty (or absent) `type`, `title` or `description` cell keeps
+/// the node's current value; a cell containing just `-` clears the type or the
+/// description.
+pub fn cmd_import_csv(
+    mm: &mut Mindmap,
+    content: &str,
+    delimiter: u8,
+    types: &TypesConfig,
+) -> Result<BatchResult> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+
+    let headers: Vec<String> = rdr
+        .headers()?
+        .iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+    let col = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+    let id_col = col(&["id"]);
+    let type_col = col(&["type"]);
+    let title_col = col(&["title"])
+        .ok_or_else(|| anyhow::anyhow!("Import: missing 'title' column in header"))?;
+    let desc_col = col(&["description", "desc"]);
+
+    let mut result = BatchResult {
+        total_ops: 0,
+        applied: 0,
+        added_ids: Vec::new(),
+        patched_ids: Vec::new(),
+        deleted_ids: Vec::new(),
+        warnings: Vec::new(),
+        id_map: Vec::new(),
+    };
+
+    for record in rdr.records() {
+        let record = record?;
+        let row = record.position().map(|p| p.line()).unwrap_or(0);
+        let cell = |c: Option<usize>| c.and_then(|c| record.get(c)).map(one_line);
+
+        // skip fully blank rows (common at the end of spreadsheet exports)
+        if record.iter().all(|f| f.trim().is_empty()) {
+            continue;
+        }
+        result.total_ops += 1;
+
+        let id = match cell(id_col).filter(|s| !s.is_empty()) {
+            Some(s) => Some(
+                s.parse::<u32>()
+                    .map_err(|_| anyhow::anyhow!("Row {}: invalid id '{}'", row, s))?,
+            ),
+            None => None,
+        };
+        let typ = cell(type_col);
+        let title = cell(Some(title_col)).unwrap_or_default();
+        let desc = cell(desc_col);
+
+        match id.and_then(|id| mm.get_node(id).map(|n| (id, n))) {
+            Some((id, node)) => {
+                // update: empty or absent cells keep the node's current value, `-` clears
+                let (cur_type, cur_title) = crate::split_title(&node.raw_title);
+                let typ = match typ.as_deref() {
+                    Some(CLEAR) => "",
+                    Some(t) if !t.is_empty() => t,
+                    _ => cur_type.unwrap_or(""),
+                };
+                let title = if title.is_empty() { cur_title } else { &title };
+                let desc = match desc.as_deref() {
+                    Some(CLEAR) => "",
+                    Some(d) if !d.is_empty() => d,
+                    _ => &node.description,
+                };
+                let raw_title = if typ.is_empty() {
+                    title.to_string()
+                } else {
+                    format!("{}: {}", typ, title)
+                };
+                let line = format!("[{}] **{}** - {}", id, raw_title, desc);
+                if line == mm.lines[node.line_index] {
+                    continue;
+                }
+                cmd_put(mm, id, &line, false, types)
+                    .map_err(|e| anyhow::anyhow!("Row {}: put failed: {}", row, e))?;
+                result.patched_ids.push(id);
+            }
+            None => {
+                let typ = typ.unwrap_or_default();
+                if typ.is_empty() || title.is_empty() {
+                    return Err(anyhow::anyhow!(
+                        "Row {}: adding a node requires non-empty type and title",
+                        row
+                    ));
+                }
+                let desc = desc.filter(|d| d != CLEAR).unwrap_or_default();
+                let new_id = id.unwrap_or_else(|| mm.next_id());
+                parse_node_line(
+                    &format!("[{}] **{}: {}** - {}", new_id, typ, title, desc),
+                    0,
+                )
+                .map_err(|_| anyhow::anyhow!("Row {}: does not form a valid node line", row))?;
+                cmd_add_with_id(mm, new_id, &typ, &title, &desc, types)
+                    .map_err(|e| anyhow::anyhow!("Row {}: add failed: {}", row, e))?;
+                result.added_ids.push(new_id);
+            }
+        }
+        result.applied += 1;
+    }
+
+    Ok(result)
+}
+
+/// One heading or bullet of an outline document
+#[derive(Debug)]
+struct OutlineItem {
+    title: String,
+    /// Continuation / paragraph text following the item
+    text: Vec<String>,
+    parent: Option<usize>,
+}
+
+/// Nesting key of an outline line: headings nest by level (1-6), bullets always
+/// nest below the current heading, deeper by indentation.
+fn outline_key(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_start();
+    let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+        return Some((hashes, trimmed[hashes..].trim()));
+    }
+
+    let indent: usize = line
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum();
+    let marker_len =
+        if trimmed.starts_with("- ") || trimmed.starts_with("* ") || trimmed.starts_with("+ ") {
+            1
+        } else {
+            let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+            let rest = &trimmed[digits..];
+            if digits > 0 && (rest.starts_with(". ") || rest.starts_with(") ")) {
+                digits + 1
+            } else {
+                return None;
+            }
+        };
+    let text = trimmed[marker_len..].trim();
+    // task list checkboxes are not part of the title
+    let text = ["[ ] ", "[x] ", "[X] "]
+        .iter()
+        .find_map(|b| text.strip_prefix(b))
+        .unwrap_or(text);
+    Some((10 + indent, text.trim()))
+}
+
+/// Parse markdown headings and (nested) bullet lists into a flat list of items
+/// with parent links. Text lines following an item are attached to it; fenced
+/// code blocks are skipped.
+fn parse_outline(content: &str) -> Vec<OutlineItem> {
+    let mut items: Vec<OutlineItem> = Vec::new();
+    let mut stack: Vec<(usize, usize)> = Vec::new(); // (nesting key, item index)
+    let mut in_fence = false;
+
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence || line.trim().is_empty() {
+            continue;
+        }
+
+        match outline_key(line) {
+            Some((key, text)) if !text.is_empty() => {
+                while stack.last().is_some_and(|(k, _)| *k >= key) {
+                    stack.pop();
+                }
+                items.push(OutlineItem {
+                    title: text.replace("**", ""),
+                    text: Vec::new(),
+                    parent: stack.last().map(|(_, idx)| *idx),
+                });
+                stack.push((key, items.len() - 1));
+            }
+            _ => {
+                if let Some(last) = items.last_mut() {
+                    last.text.push(line.trim().to_string());
+                }
+            }
+        }
+    }
+    items
+}
+
+/// Import a markdown outline: every heading or bullet becomes a one-line node
+/// of the given type, with its following text flattened into the description.
+/// Parents reference their children with `[N]`; IDs come from `Mindmap::next_id`
+/// and nodes are appended in document order.
+pub fn cmd_import_outline(
+    mm: &mut Mindmap,
+    content: &str,
+    type_prefix: &str,
+    types: &TypesConfig,
+) -> Result<BatchResult> {
+    let items = parse_outline(content);
+    let first_id = mm.next_id();
+
+    let mut result = BatchResult {
+        total_ops: items.len(),
+        applied: 0,
+        added_ids: Vec::new(),
+        patched_ids: Vec::new(),
+        deleted_ids: Vec::new(),
+        warnings: Vec::new(),
+        id_map: Vec::new(),
+    };
+
+    for (i, item) in items.iter().enumerate() {
+        let id = first_id + i as u32;
+        let children: Vec<String> = items
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.parent == Some(i))
+            .map(|(ci, _)| format!("[{}]", first_id + ci as u32))
+            .collect();
+
+        let mut desc = one_line(&item.text.join(" "));
+        if !children.is_empty() {
+            if !desc.is_empty() {
+                desc.push(' ');
+            }
+            desc.push_str(&children.join(" "));
+        }
+        let title = one_line(&item.title);
+
+        parse_node_line(
+            &format!("[{}] **{}: {}** - {}", id, type_prefix, title, desc),
+            0,
+        )
+        .map_err(|_| anyhow::anyhow!("Outline item '{}' does not form a valid node line", title))?;
+        let added = cmd_add(mm, type_prefix, &title, &desc, types)?;
+        if added != id {
+            return Err(anyhow::anyhow!(
+                "Outline import: expected id [{}] but got [{}]",
+                id,
+                added
+            ));
+        }
+        result.added_ids.push(added);
+        result.applied += 1;
+    }
+
+    if items.is_empty() {
+        result
+            .warnings
+            .push("No headings or bullets found in outline".to_string());
+    }
+    Ok(result)
+}
+
+/// Resolve the title of a JSON node from `type` + `title` and/or `raw_title`
+fn json_raw_title(n: &JsonNode) -> Result<String> {
+    let from_parts = n.title.as_ref().map(|title| match &n.node_type {
+        Some(t) if !t.is_empty() => format!("{}: {}", t, title),
+        _ => title.clone(),
+    });
+    match (from_parts, &n.raw_title) {
+        (Some(a), Some(b)) if a != *b => Err(anyhow::anyhow!(
+            "Node [{}]: title '{}' conflicts with raw_title '{}'",
+            n.id,
+            a,
+            b
+        )),
+        (Some(a), _) => Ok(a),
+        (None, Some(b)) => Ok(b.clone()),
+        (None, None) => Err(anyhow::anyhow!("Node [{}]: missing title", n.id)),
+    }
+}
+
+/// Regenerate the whole document from `export --format json` output.
+///
+/// Every node line is rebuilt as `[id] **raw_title** - description` and checked to
+/// parse back to the same node; lines are placed by `line_index` (nodes without
+/// one are appended). The result reports nodes added, changed and removed
+/// relative to the current content.
+pub fn cmd_import_json(mm: &mut Mindmap, content: &str) -> Result<BatchResult> {
+    let doc: JsonDocument = serde_json::from_str(content)
+        .map_err(|e| anyhow::anyhow!("Invalid JSON document: {}", e))?;
+    if doc.version != JSON_DOCUMENT_VERSION {
+        return Err(anyhow::anyhow!(
+            "Unsupported JSON document version {} (expected {})",
+            doc.version,
+            JSON_DOCUMENT_VERSION
+        ));
+    }
+
+    let mut placed: HashMap<usize, String> = HashMap::new();
+    let mut appended: Vec<String> = Vec::new();
+    let mut seen: HashSet<u32> = HashSet::new();
+    let mut new_lines: HashMap<u32, String> = HashMap::new();
+
+    for n in &doc.nodes {
+        if !seen.insert(n.id) {
+            return Err(anyhow::anyhow!("Duplicate node id [{}]", n.id));
+        }
+        let raw_title = json_raw_title(n)?;
+        if raw_title.contains('\n') || n.description.contains('\n') {
+            return Err(anyhow::anyhow!(
+                "Node [{}]: title and description must be single-line",
+                n.id
+            ));
+        }
+        let line = format!("[{}] **{}** - {}", n.id, raw_title, n.description);
+        match parse_node_line(&line, 0) {
+            Ok(p) if p.id == n.id && p.raw_title == raw_title && p.description == n.description => {
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Node [{}]: does not produce a valid node line: {}",
+                    n.id,
+                    line
+                ));
+            }
+        }
+        new_lines.insert(n.id, line.clone());
+        match n.line_index {
+            Some(i) => {
+                if placed.insert(i, line).is_some() {
+                    return Err(anyhow::anyhow!("Duplicate line_index {}", i));
+                }
+            }
+            None => appended.push(line),
+        }
+    }
+    for l in &doc.lines {
+        if l.text.contains('\n') {
+            return Err(anyhow::anyhow!(
+                "Line {}: text must be single-line",
+                l.line_index
+            ));
+        }
+        if parse_node_line(&l.text, l.line_index).is_ok() {
+            return Err(anyhow::anyhow!(
+                "Line {}: text is a node line; list it under \"nodes\" instead",
+                l.line_index
+            ));
+        }
+        if placed.insert(l.line_index, l.text.clone()).is_some() {
+            return Err(anyhow::anyhow!("Duplicate line_index {}", l.line_index));
+        }
+    }
+
+    // gaps (e.g. a removed node) simply collapse; spacing is normalized on save
+    let mut ordered: Vec<(usize, String)> = placed.into_iter().collect();
+    ordered.sort_by_key(|(i, _)| *i);
+    let mut lines: Vec<String> = ordered.into_iter().map(|(_, l)| l).collect();
+    for line in appended {
+        if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+            lines.push(String::new());
+        }
+        lines.push(line);
+    }
+
+    let mut result = BatchResult {
+        total_ops: doc.nodes.len(),
+        applied: 0,
+        added_ids: Vec::new(),
+        patched_ids: Vec::new(),
+        deleted_ids: Vec::new(),
+        warnings: Vec::new(),
+        id_map: Vec::new(),
+    };
+    for n in &doc.nodes {
+        match mm.get_node(n.id) {
+            None => result.added_ids.push(n.id),
+            Some(old) if mm.lines[old.line_index] != new_lines[&n.id] => {
+                result.patched_ids.push(n.id)
+            }
+            Some(_) => {}
+        }
+    }
+    result.deleted_ids = mm
+        .nodes
+        .iter()
+        .map(|n| n.id)
+        .filter(|id| !seen.contains(id))
+        .collect();
+    result.applied = result.added_ids.len() + result.patched_ids.len() + result.deleted_ids.len();
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+    *mm = Mindmap::from_string(content, mm.path.clone())?;
+    Ok(result)
+}
+
+/// Append a node line with an explicit ID (merge keeps non-colliding IDs)
+fn append_node(mm: &mut Mindmap, id: u32, raw_title: &str, desc: &str) -> Result<()> {
+    let line = format!("[{}] **{}** - {}", id, raw_title, desc);
+    let node = parse_node_line(&line, mm.lines.len())?;
+    mm.lines.push(line);
+    mm.by_id.insert(id, mm.nodes.len());
+    mm.nodes.push(node);
+    Ok(())
+}
+
+/// Relative link from a directory to a file, written the way MINDMAP external
+/// references are (`./x.md`, `../team/MINDMAP.md`)
+fn relative_link(from_dir: &Path, to: &Path) -> String {
+    let from: Vec<_> = from_dir.components().collect();
+    let to_c: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to_c).take_while(|(a, b)| a == b).count();
+
+    let mut rel = PathBuf::new();
+    for _ in common..from.len() {
+        rel.push("..");
+    }
+    for c in &to_c[common..] {
+        rel.push(c.as_os_str());
+    }
+    let rel = rel.to_string_lossy().replace('\\', "/");
+    if rel.starts_with("..") {
+        rel
+    } else {
+        format!("./{}", rel)
+    }
+}
+
+fn canonical_dir(path: &Path) -> PathBuf {
+    let canonical = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    canonical
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Merge nodes of another MINDMAP file into `mm`.
+///
+/// Imported IDs that collide with existing ones are remapped to fresh IDs and the
+/// imported descriptions are rewritten accordingly. References to nodes of the
+/// other file that are not imported (see `ids`) become `[N](path)` external links,
+/// and external links inside imported nodes are re-based onto the target file's
+/// directory (resolved with `cache`, relative to the other file; links that do not
+/// resolve inside the workspace are kept as written and reported). Imported nodes
+/// whose title matches an existing node are reported; with `dedupe` they are not
+/// added, and their description is appended to the existing node's instead.
+pub fn cmd_import_mindmap(
+    mm: &mut Mindmap,
+    other: &Mindmap,
+    cache: &MindmapCache,
+    ids: &[u32],
+    dedupe: bool,
+    types: &TypesConfig,
+) -> Result<BatchResult> {
+    // duplicate ids inside the other file: by_id points at the last occurrence
+    let mut selected: Vec<&crate::Node> = other
+        .nodes
+        .iter()
+        .enumerate()
+        .filter(|(i, n)| other.by_id.get(&n.id) == Some(i))
+        .map(|(_, n)| n)
+        .collect();
+    if !ids.is_empty() {
+        for id in ids {
+            if other.get_node(*id).is_none() {
+                return Err(anyhow::anyhow!(
+                    "Node [{}] not found in {}",
+                    id,
+                    other.path.display()
+                ));
+            }
+        }
+        selected.retain(|n| ids.contains(&n.id));
+    }
+
+    let mut result = BatchResult {
+        total_ops: selected.len(),
+        applied: 0,
+        added_ids: Vec::new(),
+        patched_ids: Vec::new(),
+        deleted_ids: Vec::new(),
+        warnings: Vec::new(),
+        id_map: Vec::new(),
+    };
+
+    let existing_titles: HashMap<String, u32> = mm
+        .nodes
+        .iter()
+        .map(|n| (n.raw_title.trim().to_lowercase(), n.id))
+        .collect();
+
+    // 1) decide per node: merge into an existing node, keep its id, or remap
+    let mut mapping: HashMap<u32, u32> = HashMap::new();
+    let mut merged: HashSet<u32> = HashSet::new();
+    let mut colliding: Vec<u32> = Vec::new();
+    for n in &selected {
+        if let Some(&existing) = existing_titles.get(&n.raw_title.trim().to_lowercase()) {
+            if dedupe {
+                mapping.insert(n.id, existing);
+                merged.insert(n.id);
+                continue;
+            }
+            result.warnings.push(format!(
+                "Possible duplicate: imported [{}] '{}' matches existing [{}]; use --dedupe to merge",
+                n.id, n.raw_title, existing
+            ));
+        }
+        if mm.by_id.contains_key(&n.id) {
+            colliding.push(n.id);
+        } else {
+            mapping.insert(n.id, n.id);
+        }
+    }
+    let first_free = mm
+        .by_id
+        .keys()
+        .chain(mapping.values())
+        .max()
+        .copied()
+        .unwrap_or(0)
+        + 1;
+    for (id, new) in colliding.into_iter().zip(first_free..) {
+        mapping.insert(id, new);
+    }
+
+    // 2) rewrite references and append the imported nodes in file order
+    let other_canonical = std::fs::canonicalize(&other.path).unwrap_or_else(|_| other.path.clone());
+    let target_canonical = std::fs::canonicalize(&mm.path).unwrap_or_else(|_| mm.path.clone());
+    let target_dir = canonical_dir(&mm.path);
+    let link_to_other = relative_link(&target_dir, &other_canonical);
+
+    for n in &selected {
+        let new_id = mapping[&n.id];
+        let desc = rewrite_refs(&n.description, |r| match r {
+            Reference::Internal(rid) => match mapping.get(rid) {
+                Some(new) if new == rid => None,
+                Some(new) => Some(format!("[{}]", new)),
+                None if other.by_id.contains_key(rid) => {
+                    Some(format!("[{}]({})", rid, link_to_other))
+                }
+                None => {
+                    result.warnings.push(format!(
+                        "Imported [{}] references [{}], which does not exist in {}; replaced with a missing-ref comment",
+                        n.id,
+                        rid,
+                        other.path.display()
+                    ));
+                    Some(format!("<!-- missing ref {} -->", rid))
+                }
+            },
+            Reference::External(rid, rel) => {
+                let abs = match cache.resolve_path(&other_canonical, rel) {
+                    Ok(abs) => abs,
+                    Err(e) => {
+                        result.warnings.push(format!(
+                            "Imported [{}] links [{}]({}), which cannot be re-based: {}",
+                            n.id, rid, rel, e
+                        ));
+                        return None;
+                    }
+                };
+                if abs == target_canonical {
+                    Some(format!("[{}]", rid))
+                } else {
+                    Some(format!("[{}]({})", rid, relative_link(&target_dir, &abs)))
+                }
+            }
+        });
+
+        if merged.contains(&n.id) {
+            let existing = &mm.get_node(new_id).unwrap().description;
+            if desc.trim().is_empty() || existing.contains(desc.trim()) {
+                result.warnings.push(format!(
+                    "Merged imported [{}] into existing [{}] '{}' (description already present)",
+                    n.id, new_id, n.raw_title
+                ));
+                continue;
+            }
+            let appended = if existing.trim().is_empty() {
+                desc.trim().to_string()
+            } else {
+                format!("{} {}", existing.trim_end(), desc.trim())
+            };
+            cmd_patch(mm, new_id, None, None, Some(&appended), false, types)?;
+            result.warnings.push(format!(
+                "Merged imported [{}] into existing [{}] '{}' (description appended)",
+                n.id, new_id, n.raw_title
+            ));
+            result.patched_ids.push(new_id);
+            result.applied += 1;
+            continue;
+        }
+
+        append_node(mm, new_id, &n.raw_title, &desc)?;
+        result.added_ids.push(new_id);
+        result.applied += 1;
+    }
+
+    for n in &selected {
+        result.id_map.push(IdMapping {
+            old: n.id,
+            new: mapping[&n.id],
+            merged: merged.contains(&n.id),
+        });
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn load(content: &str) -> Result<Mindmap> {
+        Mindmap::load_from_reader(std::io::Cursor::new(content), PathBuf::from("-"))
+    }
+
+    #[test]
+    fn test_import_csv_add_and_update() -> Result<()> {
+        let mut mm = load("[1] **AE: One** - first\n")?;
+        let csv = "id,type,title,description\n1,AE,One renamed,first\n,WF,Flow,\"uses\n[1]\"\n";
+        let res = cmd_import_csv(&mut mm, csv, b',', &TypesConfig::default())?;
+        assert_eq!(res.patched_ids, vec![1]);
+        assert_eq!(res.added_ids, vec![2]);
+        assert_eq!(mm.get_node(1).unwrap().raw_title, "AE: One renamed");
+        assert_eq!(mm.get_node(2).unwrap().description, "uses [1]");
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_csv_empty_cells_keep_values() -> Result<()> {
+        let mut mm = load("[1] **AE: One** - first\n\n[2] **WF: Two** - second\n")?;
+        let csv = "id,type,title,description\n1,,,\n2,-,Two renamed,-\n";
+        let res = cmd_import_csv(&mut mm, csv, b',', &TypesConfig::default())?;
+        assert_eq!(res.patched_ids, vec![2]);
+        assert_eq!(mm.lines[0], "[1] **AE: One** - first");
+        assert_eq!(mm.lines[2], "[2] **Two renamed** - ");
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_csv_roundtrip_is_noop() -> Result<()> {
+        let mut mm = load("[1] **AE: One** - first\n\n[2] **Untyped** - refs [1]\n")?;
+        let exported = crate::export::cmd_export_csv(&mm, b'\t')?;
+        let res = cmd_import_csv(&mut mm, &exported, b'\t', &TypesConfig::default())?;
+        assert_eq!(res.total_ops, 2);
+        assert_eq!(res.applied, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_csv_unknown_id_and_errors() -> Result<()> {
+        let mut mm = load("[1] **AE: One** - first\n")?;
+        // an unused id is kept, so references to it stay correct
+        let res = cmd_import_csv(
+            &mut mm,
+            "id,type,title,desc\n9,AE,Nine,n [1]\n",
+            b',',
+            &TypesConfig::default(),
+        )?;
+        assert_eq!(res.added_ids, vec![9]);
+        assert!(res.warnings.is_empty());
+        assert_eq!(mm.lines.last().unwrap(), "[9] **AE: Nine** - n [1]");
+        assert_eq!(mm.next_id(), 10);
+
+        assert!(
+            cmd_import_csv(
+                &mut mm,
+                "id,title\n,NoType\n",
+                b',',
+                &TypesConfig::default()
+            )
+            .is_err()
+        );
+        assert!(
+            cmd_import_csv(&mut mm, "id,title\nx,Bad\n", b',', &TypesConfig::default()).is_err()
+        );
+        assert!(
+            cmd_import_csv(&mut mm, "id,name\n1,Bad\n", b',', &TypesConfig::default()).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_outline_nesting() {
+        let items = parse_outline(
+            "# Guide\nIntro text\nmore intro\n\n## Setup\n- install **tool**\n  - run it\n- [x] configure\n```\n- not a bullet\n```\n## Usage\n1. start\n",
+        );
+        let titles: Vec<&str> = items.iter().map(|i| i.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "Guide",
+                "Setup",
+                "install tool",
+                "run it",
+                "configure",
+                "Usage",
+                "start"
+            ]
+        );
+        let parents: Vec<Option<usize>> = items.iter().map(|i| i.parent).collect();
+        assert_eq!(
+            parents,
+            vec![None, Some(0), Some(1), Some(2), Some(1), Some(0), Some(5)]
+        );
+        assert_eq!(items[0].text, vec!["Intro text", "more intro"]);
+    }
+
+    #[test]
+    fn test_import_outline_refs() -> Result<()> {
+        let mut mm = load("[1] **AE: One** - first\n")?;
+        let res = cmd_import_outline(
+            &mut mm,
+            "# Doc\nSome text\n- child a\n- child b\n",
+            "DOC",
+            &TypesConfig::default(),
+        )?;
+        assert_eq!(res.added_ids, vec![2, 3, 4]);
+        let root = mm.get_node(2).unwrap();
+        assert_eq!(root.raw_title, "DOC: Doc");
+        assert_eq!(root.description, "Some text [3] [4]");
+        assert_eq!(mm.get_node(4).unwrap().description, "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_relative_link() {
+        assert_eq!(
+            relative_link(Path::new("/w/a"), Path::new("/w/a/x.md")),
+            "./x.md"
+        );
+        assert_eq!(
+            relative_link(Path::new("/w/a"), Path::new("/w/b/MINDMAP.md")),
+            "../b/MINDMAP.md"
+        );
+    }
+
+    #[test]
+    fn test_import_mindmap_remaps_ids() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let target = temp.path().join("MINDMAP.md");
+        std::fs::write(
+            &target,
+            "[1] **AE: Auth** - auth\n\n[2] **AE: Store** - store\n",
+        )?;
+        std::fs::create_dir(temp.path().join("team"))?;
+        let other_path = temp.path().join("team").join("MINDMAP.md");
+        std::fs::write(
+            &other_path,
+            "[1] **WF: Deploy** - uses [2] and [5]\n\n[2] **AE: Store** - dup title\n\n[5] **DR: Keep** - see [1](../MINDMAP.md)\n",
+        )?;
+        let mut mm = Mindmap::load(target.clone())?;
+        let other = Mindmap::load(other_path.clone())?;
+
+        let cache = MindmapCache::new(temp.path().to_path_buf());
+        let res = cmd_import_mindmap(&mut mm, &other, &cache, &[], false, &TypesConfig::default())?;
+        // 5 keeps its id; 1 and 2 collide and get fresh ids after 5
+        assert_eq!(res.added_ids, vec![6, 7, 5]);
+        assert_eq!(mm.get_node(6).unwrap().description, "uses [7] and [5]");
+        // external link back into the target file becomes internal
+        assert_eq!(mm.get_node(5).unwrap().description, "see [1]");
+        assert!(
+            res.warnings
+                .iter()
+                .any(|w| w.contains("Possible duplicate"))
+        );
+
+        // subset import with dedupe: [2] merges into existing [2], [5] stays external
+        let mut mm = Mindmap::load(target)?;
+        let res = cmd_import_mindmap(
+            &mut mm,
+            &other,
+            &cache,
+            &[1, 2],
+            true,
+            &TypesConfig::default(),
+        )?;
+        assert_eq!(res.added_ids, vec![3]);
+        assert_eq!(res.patched_ids, vec![2]);
+        assert_eq!(mm.get_node(2).unwrap().description, "store dup title");
+        assert_eq!(
+            mm.get_node(3).unwrap().description,
+            "uses [2] and [5](./team/MINDMAP.md)"
+        );
+        assert!(
+            res.id_map
+                .iter()
+                .any(|m| m.old == 2 && m.new == 2 && m.merged)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_mindmap_unresolvable_link() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let target = temp.path().join("MINDMAP.md");
+        std::fs::write(&target, "[1] **AE: Auth** - auth\n")?;
+        std::fs::create_dir(temp.path().join("team"))?;
+        let other_path = temp.path().join("team").join("MINDMAP.md");
+        std::fs::write(
+            &other_path,
+            "[1] **WF: Deploy** - see [4](../gone.md) and [9]\n",
+        )?;
+        let mut mm = Mindmap::load(target)?;
+        let other = Mindmap::load(other_path)?;
+
+        let cache = MindmapCache::new(temp.path().to_path_buf());
+        let res = cmd_import_mindmap(&mut mm, &other, &cache, &[], false, &TypesConfig::default())?;
+        // a dangling internal ref would otherwise point at an unrelated local node
+        assert_eq!(
+            mm.get_node(2).unwrap().description,
+            "see [4](../gone.md) and <!-- missing ref 9 -->"
+        );
+        assert!(
+            res.warnings
+                .iter()
+                .any(|w| w.contains("cannot be re-based"))
+        );
+        assert!(res.warnings.iter().any(|w| w.contains("references [9]")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_json_roundtrip_is_noop() -> Result<()> {
+        let src = "# Title\n\n[1] **AE: One** - see [2]\n\n[2] **Two** - x\n";
+        let mut mm = load(src)?;
+        let json = crate::export::cmd_export_json(&mm)?;
+        let res = cmd_import_json(&mut mm, &json)?;
+        assert_eq!(res.applied, 0);
+        assert_eq!(mm.lines.join("\n") + "\n", src);
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_json_structural_edit() -> Result<()> {
+        let mut mm = load("# Title\n\n[1] **AE: One** - a\n\n[2] **Two** - b\n")?;
+        let json = r##"{"version": 1,
+            "nodes": [
+                {"id": 1, "type": "AE", "title": "One", "description": "a [3]", "line_index": 2},
+                {"id": 3, "raw_title": "WF: Three", "description": "new"}
+            ],
+            "lines": [{"line_index": 0, "text": "# Title"}, {"line_index": 1, "text": ""}]}"##;
+        let res = cmd_import_json(&mut mm, json)?;
+        assert_eq!(res.patched_ids, vec![1]);
+        assert_eq!(res.added_ids, vec![3]);
+        assert_eq!(res.deleted_ids, vec![2]);
+        assert_eq!(
+            mm.lines,
+            vec![
+                "# Title",
+                "",
+                "[1] **AE: One** - a [3]",
+                "",
+                "[3] **WF: Three** - new"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_json_validation() -> Result<()> {
+        let mut mm = load("[1] **One** - a\n")?;
+        let dup = r#"{"version": 1, "nodes": [{"id": 1, "title": "A"}, {"id": 1, "title": "B"}]}"#;
+        assert!(cmd_import_json(&mut mm, dup).is_err());
+        let conflict = r#"{"version": 1, "nodes": [{"id": 1, "type": "AE", "title": "A", "raw_title": "AE: B"}]}"#;
+        assert!(cmd_import_json(&mut mm, conflict).is_err());
+        let bad_title = r#"{"version": 1, "nodes": [{"id": 1, "title": "A ** B"}]}"#;
+        assert!(cmd_import_json(&mut mm, bad_title).is_err());
+        let node_as_text =
+            r#"{"version": 1, "nodes": [], "lines": [{"line_index": 0, "text": "[2] **X** - y"}]}"#;
+        assert!(cmd_import_json(&mut mm, node_as_text).is_err());
+        // nothing staged on failure
+        assert_eq!(mm.lines, vec!["[1] **One** - a"]);
+        Ok(())
+    }
+}
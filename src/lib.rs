@@ -1,10 +1,35 @@
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
-use std::{collections::HashMap, fs, io::Read, path::PathBuf};
-
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+pub mod anchors;
 pub mod cache;
+pub mod citations;
+pub mod config;
 pub mod context;
+pub mod diff;
+pub mod dupes;
+pub mod export;
+pub mod git;
+pub mod history;
+pub mod hooks;
+pub mod http;
+pub mod import;
+pub mod lint;
+pub mod lsp;
+pub mod mcp;
+pub mod merge;
+pub mod search;
+pub mod shell;
+pub mod stale;
+pub mod tui;
 mod ui;
+pub mod watch;
 
 #[derive(clap::ValueEnum, Clone)]
 pub enum OutputFormat {
@@ -12,6 +37,73 @@ pub enum OutputFormat {
     Json,
 }
 
+/// Machine formats for `lint --format`
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum LintFormat {
+    /// SARIF 2.1.0 log (code scanning)
+    Sarif,
+    /// GitHub Actions annotations (`::error file=…,line=…::…`)
+    Github,
+}
+
+/// What `add --check-dupes` does when a near-duplicate already exists
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum DupeCheck {
+    /// Refuse to add the node
+    Refuse,
+    /// Add the node and print the near-duplicates
+    Warn,
+}
+
+#[derive(clap::ValueEnum, Clone)]
+pub enum ExportFormat {
+    Csv,
+    Tsv,
+    Sqlite,
+    /// Whole document (nodes and non-node lines); read back by `import --format json`
+    Json,
+    /// Graphviz digraph of all nodes and references
+    Dot,
+    /// Mermaid flowchart of all nodes and references
+    Mermaid,
+    /// Standalone HTML page with linked references
+    Html,
+}
+
+#[derive(clap::ValueEnum, Clone)]
+pub enum ImportFormat {
+    Csv,
+    Tsv,
+    /// Markdown headings / bullet lists; nesting becomes [N] references
+    Outline,
+    /// Another MINDMAP file; colliding IDs are remapped
+    Mindmap,
+    /// Whole document as written by `export --format json`; replaces the content
+    Json,
+}
+
+/// Source of an `import`: a format for the INPUT argument, or (shorthand) the path
+/// of another MINDMAP file to merge, as in `import --from other/MINDMAP.md`
+#[derive(Clone)]
+pub enum ImportSource {
+    Format(ImportFormat),
+    Mindmap(PathBuf),
+}
+
+fn parse_import_source(s: &str) -> std::result::Result<ImportSource, String> {
+    use clap::ValueEnum;
+    if let Ok(format) = ImportFormat::from_str(s, true) {
+        return Ok(ImportSource::Format(format));
+    }
+    if s.ends_with(".md") {
+        return Ok(ImportSource::Mindmap(PathBuf::from(s)));
+    }
+    Err(format!(
+        "unknown import format '{}' (expected csv, tsv, outline, mindmap, json or a path to a .md MINDMAP file)",
+        s
+    ))
+}
+
 #[derive(Parser)]
 #[command(name = "mindmap-cli")]
 #[command(about = "CLI tool for working with MINDMAP files")]
@@ -35,19 +127,20 @@ EXAMPLES:
   EOF
 
 Notes:
-  - Default file: ./MINDMAP.md (override with --file)
+  - Default file: ./MINDMAP.md (override with --file, or `file` in .mindmap.toml)
   - Use `--file -` to read a mindmap from stdin for read-only commands (list/show/refs/links/search/lint/orphans). Mutating commands will error when source is `-`.
-  - Use the EDITOR env var to control the editor used by 'edit'
+  - Use the EDITOR env var (or `editor` in .mindmap.toml) to control the editor used by 'edit'
+  - Project settings are read from the nearest .mindmap.toml in the current directory or above
 "#
 )]
 pub struct Cli {
-    /// Path to MINDMAP file (defaults to ./MINDMAP.md)
+    /// Path to MINDMAP file (defaults to `file` in .mindmap.toml, else ./MINDMAP.md)
     #[arg(global = true, short, long)]
     pub file: Option<PathBuf>,
 
-    /// Output format: default (human) or json
-    #[arg(global = true, long, value_enum, default_value_t = OutputFormat::Default)]
-    pub output: OutputFormat,
+    /// Output format: default (human) or json (default: `[output] format` in .mindmap.toml)
+    #[arg(global = true, long, value_enum)]
+    pub output: Option<OutputFormat>,
 
     #[command(subcommand)]
     pub command: Commands,
@@ -66,6 +159,10 @@ pub enum Commands {
         /// Print the found node's description only
         #[arg(long)]
         desc: bool,
+        /// Also list `MINDMAP[N]` citations of the node in source files under the
+        /// mindmap's directory
+        #[arg(long)]
+        code: bool,
     },
 
     /// List nodes (optionally filtered by --type or --grep with search flags)
@@ -111,20 +208,23 @@ pub enum Commands {
     /// Search nodes by substring (case-insensitive by default, use flags for advanced search)
     #[command(alias = "query")]
     Search {
-        /// Search query (searches title and description)
+        /// Search query: words, "quoted phrases", title:word and desc:word qualifiers
         query: String,
         /// Match case exactly (default: case-insensitive)
         #[arg(long)]
         case_sensitive: bool,
-        /// Match entire words/phrases exactly (default: substring match)
+        /// Match whole words only (default: partial words and small typos also match)
         #[arg(long)]
         exact_match: bool,
-        /// Use regex pattern instead of plain text
+        /// Use regex pattern instead of plain text (results in file order, unranked)
         #[arg(long)]
         regex_mode: bool,
         /// Follow external references across files
         #[arg(long)]
         follow: bool,
+        /// Show at most this many results
+        #[arg(long)]
+        limit: Option<usize>,
     },
 
     /// Add a new node
@@ -138,6 +238,10 @@ pub enum Commands {
         /// When using editor flow, perform strict reference validation
         #[arg(long)]
         strict: bool,
+        /// Check for near-duplicates (similarity at least `[dupes] threshold`) and refuse
+        /// to add (the default) or only warn
+        #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "refuse")]
+        check_dupes: Option<DupeCheck>,
     },
 
     /// Deprecate a node, redirecting to another
@@ -173,7 +277,8 @@ pub enum Commands {
         strict: bool,
     },
 
-    /// Mark a node as needing verification (append verify tag)
+    /// Mark a node as verified today (refresh its last `(verify YYYY-MM-DD)` tag, or
+    /// append one)
     Verify { id: u32 },
 
     /// Delete a node by ID; use --force to remove even if referenced
@@ -183,11 +288,25 @@ pub enum Commands {
         force: bool,
     },
 
-    /// Lint the mindmap for basic issues (use --fix to auto-fix spacing and type prefixes)
+    /// Lint the mindmap for issues (use --fix to auto-fix what can be repaired)
     Lint {
-        /// Auto-fix spacing and duplicated type prefixes
+        /// Auto-fix spacing, duplicated type prefixes, malformed node lines, trailing
+        /// whitespace, `[DEPRECATED → N]` spellings and duplicate IDs
         #[arg(long)]
         fix: bool,
+        /// With --fix, also remove or comment out references to missing nodes
+        #[arg(long, value_enum, requires = "fix")]
+        missing_refs: Option<MissingRefFix>,
+        /// With --fix, show the fixes without writing the file
+        #[arg(long, requires = "fix")]
+        dry_run: bool,
+        /// Lint the staged (git index) content instead of the working tree; fails on
+        /// errors. With --fix, fixes are re-staged
+        #[arg(long)]
+        staged: bool,
+        /// Print findings as SARIF or GitHub Actions annotations on stdout instead
+        #[arg(long, value_enum, conflicts_with = "staged")]
+        format: Option<LintFormat>,
     },
 
     /// Show orphan nodes (no in & no out, excluding META)
@@ -242,6 +361,172 @@ pub enum Commands {
         #[arg(long)]
         fix: bool,
     },
+
+    /// Export all nodes (csv/tsv rows, or a SQLite database with nodes/edges/files + FTS)
+    Export {
+        /// Export format
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// Write to this file instead of stdout (required for sqlite)
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Workspace mode (sqlite): also export files reached through external references
+        #[arg(long, alias = "workspace")]
+        follow: bool,
+    },
+
+    /// Import nodes atomically (csv/tsv rows create or update nodes by ID; outline
+    /// headings/bullets become new nodes; a MINDMAP file is merged; json replaces the document)
+    Import {
+        /// Input file or '-' for stdin (default: stdin)
+        input: Option<PathBuf>,
+        /// Input format: csv, tsv, outline, mindmap, or the path of a MINDMAP file to merge
+        #[arg(long, alias = "from", value_parser = parse_import_source, default_value = "csv")]
+        format: ImportSource,
+        /// Type prefix for nodes created from an outline (e.g., DOC)
+        #[arg(long)]
+        r#type: Option<String>,
+        /// MINDMAP merge: import only these node IDs (comma-separated; default: all)
+        #[arg(long, value_delimiter = ',')]
+        ids: Vec<u32>,
+        /// MINDMAP merge: append the description of imported nodes to existing nodes with the same title instead of adding them
+        #[arg(long)]
+        dedupe: bool,
+        /// Do not write changes; just show what would happen
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Serve the mindmap: MCP tools for AI agents (--stdio) or a local REST API (--http)
+    Serve {
+        /// Speak MCP on stdin/stdout (one JSON-RPC message per line)
+        #[arg(long, conflicts_with = "http")]
+        stdio: bool,
+        /// Serve REST endpoints (/nodes, /nodes/{id}, /batch) on this address, e.g. 127.0.0.1:8080
+        #[arg(long, value_name = "ADDR")]
+        http: Option<String>,
+        /// Allow --http to listen on a non-loopback address (the API has no authentication)
+        #[arg(long, requires = "http")]
+        allow_remote: bool,
+    },
+
+    /// Browse the mindmap interactively: search, follow references (also into external
+    /// files), back/forward history and inline edit
+    Tui,
+
+    /// Watch the file (with --follow, also referenced files): re-lint on every change,
+    /// printing new and resolved issues, and regenerate --export targets
+    Watch {
+        /// Also watch files reached through external references
+        #[arg(long, alias = "workspace")]
+        follow: bool,
+        /// Regenerate an export on change, as FORMAT:PATH (e.g. dot:map.dot, html:map.html); repeatable
+        #[arg(long = "export", value_name = "FORMAT:PATH", value_parser = watch::parse_export_target)]
+        exports: Vec<watch::ExportTarget>,
+        /// Polling interval in milliseconds
+        #[arg(long, default_value_t = 500)]
+        interval: u64,
+    },
+
+    /// List nodes whose last verification or git modification is older than a
+    /// threshold, oldest first
+    Stale {
+        /// Threshold such as 90d, 12w, 6m or 1y (default: `[stale] older_than`, else 90d)
+        #[arg(long)]
+        older_than: Option<stale::Age>,
+        /// Only nodes of this type prefix (repeatable)
+        #[arg(long = "type")]
+        types: Vec<String>,
+        /// Ignore git history and use only (verify YYYY-MM-DD) tags
+        #[arg(long)]
+        no_git: bool,
+    },
+
+    /// Find nodes with identical or highly similar titles and descriptions (merge
+    /// candidates), best first
+    Dupes {
+        /// Minimum similarity from 0.0 to 1.0 (default: `[dupes] threshold`, else 0.6)
+        #[arg(long)]
+        threshold: Option<f64>,
+        /// Show at most this many pairs
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+
+    /// Find `MINDMAP[N]` citations in a source tree and report those of missing or
+    /// deprecated nodes (paths are relative to the mindmap's directory)
+    ScanCode {
+        /// Directory to scan (hidden directories, target and node_modules are skipped)
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+        /// Also list AE nodes that no code cites
+        #[arg(long)]
+        uncited: bool,
+    },
+
+    /// Show every past version of a node from the local git history (matched by ID, so
+    /// line moves are followed), with a field-level diff of title, description and refs
+    History { id: u32 },
+
+    /// Compare two versions by node ID: added, removed, retitled, retyped and
+    /// re-described nodes and reference changes. Each side is a file, a git revision of
+    /// the mindmap file (e.g. HEAD~1, main) or rev:path; NEW defaults to the working tree
+    Diff {
+        old: String,
+        new: Option<String>,
+        /// Render as markdown (for PR comments)
+        #[arg(long, conflicts_with = "stat")]
+        markdown: bool,
+        /// Print only the summary line
+        #[arg(long)]
+        stat: bool,
+    },
+
+    /// Manage the git pre-commit hook that lints staged MINDMAP files
+    Hooks {
+        #[command(subcommand)]
+        action: HooksAction,
+    },
+
+    /// Git merge driver: node-level three-way merge of %O (base), %A (ours, rewritten
+    /// in place) and %B (theirs); exits non-zero when conflict markers are left
+    MergeDriver {
+        /// Common ancestor (%O)
+        base: PathBuf,
+        /// Our version (%A); receives the merge result
+        ours: PathBuf,
+        /// Their version (%B)
+        theirs: PathBuf,
+        /// Conflict marker size (%L)
+        #[arg(default_value_t = 7)]
+        marker_size: usize,
+        /// Path of the merged file in the repository (%P), used in messages
+        path: Option<String>,
+    },
+
+    /// Interactive shell over the loaded mindmap: CLI syntax for reads, mutations staged
+    /// until `commit` (or `rollback`), history and tab completion of IDs and types
+    Shell,
+
+    /// Run a Language Server (LSP) on stdin/stdout for editors (definition, references,
+    /// hover, completion, rename, lint diagnostics)
+    Lsp,
+}
+
+#[derive(Subcommand)]
+pub enum HooksAction {
+    /// Install a pre-commit hook running `lint --staged` on every staged MINDMAP.md and
+    /// on the configured mindmap file
+    Install {
+        /// Apply `lint --fix` to the staged content and re-stage it
+        #[arg(long)]
+        fix: bool,
+        /// Replace an existing pre-commit hook not written by mindmap-cli
+        #[arg(long)]
+        force: bool,
+    },
+    /// Remove the pre-commit hook written by `hooks install`
+    Uninstall,
 }
 
 #[derive(Debug, Clone)]
@@ -259,7 +544,7 @@ pub enum Reference {
     External(u32, String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Mindmap {
     pub path: PathBuf,
     pub lines: Vec<String>,
@@ -386,16 +671,55 @@ impl Mindmap {
         Ok(())
     }
 
-    /// Apply automatic fixes: normalize spacing (ensuring exactly one blank between nodes)
-    /// and remove duplicated leading type prefixes in node titles (e.g., "AE: AE: Foo" -> "AE: Foo").
+    /// Apply automatic fixes with the default options (references to missing nodes are
+    /// left alone)
     pub fn apply_fixes(&mut self) -> Result<FixReport> {
+        self.apply_fixes_with(&FixOptions::default())
+    }
+
+    /// Apply automatic fixes:
+    /// - repair trailing whitespace on node lines, malformed node lines (`[12]**T** - x`,
+    ///   `*T*`, `—`) and `[DEPRECATED->N]` spellings
+    /// - give later duplicates of an ID fresh IDs
+    /// - with `opts.missing_refs`, remove or comment out references to missing nodes
+    /// - normalize spacing (ensuring exactly one blank between nodes)
+    /// - remove duplicated leading type prefixes in node titles (e.g., "AE: AE: Foo" -> "AE: Foo").
+    pub fn apply_fixes_with(&mut self, opts: &FixOptions) -> Result<FixReport> {
         let mut report = FixReport::default();
 
-        // 1) normalize spacing (ensure exactly one blank line between nodes, collapse multiples)
         if self.lines.is_empty() {
             return Ok(report);
         }
 
+        // 1) line repairs, 2) duplicate IDs, 3) missing references
+        let mut lines = self.lines.clone();
+        for (i, line) in lines.iter_mut().enumerate() {
+            for (kind, description, fixer) in LINE_FIXERS {
+                if let Some(new) = fixer(line) {
+                    report.line_fixes.push(LineFix {
+                        line: i + 1,
+                        kind,
+                        description: (*description).to_string(),
+                        old: std::mem::replace(line, new.clone()),
+                        new,
+                    });
+                }
+            }
+        }
+        fix_duplicate_ids(&mut lines, &mut report);
+        if let Some(mode) = opts.missing_refs {
+            fix_missing_refs(&mut lines, mode, &mut report);
+        }
+        if !report.line_fixes.is_empty() {
+            let content = lines.join("\n") + "\n";
+            let fixed = Mindmap::from_string(content, self.path.clone())?;
+            self.lines = fixed.lines;
+            self.nodes = fixed.nodes;
+            self.by_id = fixed.by_id;
+        }
+
+        // 4) normalize spacing (ensure exactly one blank line between nodes, collapse multiples)
+
         let orig = self.lines.clone();
         let mut new_lines: Vec<String> = Vec::new();
         let mut i = 0usize;
@@ -438,7 +762,7 @@ impl Mindmap {
             self.by_id = normalized_mm.by_id;
         }
 
-        // 2) fix duplicated type prefixes in node titles (e.g., "AE: AE: X" -> "AE: X")
+        // 5) fix duplicated type prefixes in node titles (e.g., "AE: AE: X" -> "AE: X")
         let mut changed = false;
         let mut new_lines = self.lines.clone();
         for node in &self.nodes {
@@ -482,6 +806,211 @@ impl Mindmap {
     }
 }
 
+/// Returns the fixed line, or None when the line needs no fix
+type LineFixer = fn(&str) -> Option<String>;
+
+/// Line-level fixers run by `apply_fixes`, in order, with their kind and description
+const LINE_FIXERS: &[(&str, &str, LineFixer)] = &[
+    (
+        "trailing-whitespace",
+        "removed trailing whitespace",
+        fix_trailing_whitespace,
+    ),
+    ("malformed-node", "repaired node syntax", repair_node_line),
+    (
+        "deprecated-marker",
+        "normalized the deprecation marker",
+        fix_deprecated_marker,
+    ),
+];
+
+/// Node lines only: elsewhere two trailing spaces are a markdown hard break
+fn fix_trailing_whitespace(line: &str) -> Option<String> {
+    let trimmed = line.trim_end();
+    if trimmed.len() == line.len() {
+        return None;
+    }
+    match parse_node_line(line, 0) {
+        // `[N] **T** - ` (empty description) needs its last space
+        Ok(_) if parse_node_line(trimmed, 0).is_err() => None,
+        Ok(_) => Some(trimmed.to_string()),
+        Err(_) => repair_node_line(line).map(|_| trimmed.to_string()),
+    }
+}
+
+/// A node line for a line that starts with `[N]` but does not parse: a missing space
+/// after `]`, single-star (or unbalanced) title markers, or `—`/`–`/`--` as separator
+pub(crate) fn repair_node_line(line: &str) -> Option<String> {
+    static MALFORMED: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+        regex::Regex::new(r"^(\s*)\[(\d+)\]\s*\*{1,2}([^*]+?)\*{1,2}\s*(?:--|-|—|–)\s*(.*)$")
+            .unwrap()
+    });
+    if !line.trim_start().starts_with('[') || parse_node_line(line, 0).is_ok() {
+        return None;
+    }
+    let c = MALFORMED.captures(line)?;
+    let repaired = format!("{}[{}] **{}** - {}", &c[1], &c[2], c[3].trim(), &c[4]);
+    parse_node_line(&repaired, 0).is_ok().then_some(repaired)
+}
+
+/// `[DEPRECATED->N]`, `[deprecated: N]`, `[DEPRECATED→N]`, … as `[DEPRECATED → N] `
+fn fix_deprecated_marker(line: &str) -> Option<String> {
+    static MARKER: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+        regex::Regex::new(r"(?i)^\[\s*deprecated\s*(?:→|->|=>|:|to)?\s*(\d+)\s*\]\s*").unwrap()
+    });
+    let node = parse_node_line(line, 0).ok()?;
+    let m = MARKER.captures(&node.raw_title)?;
+    let title = format!(
+        "[DEPRECATED → {}] {}",
+        &m[1],
+        &node.raw_title[m.get(0)?.end()..]
+    );
+    if title == node.raw_title {
+        return None;
+    }
+    let start = line.find("**")? + 2;
+    Some(format!(
+        "{}{}{}",
+        &line[..start],
+        title,
+        &line[start + node.raw_title.len()..]
+    ))
+}
+
+/// Later occurrences of an ID get fresh IDs above the current maximum. References
+/// to the ID keep pointing at the first occurrence; the nodes holding them are
+/// listed in the fix description so they can be checked by hand.
+fn fix_duplicate_ids(lines: &mut [String], report: &mut FixReport) {
+    let nodes: Vec<Option<Node>> = lines.iter().map(|l| parse_node_line(l, 0).ok()).collect();
+    let ids: Vec<Option<u32>> = nodes.iter().map(|n| n.as_ref().map(|n| n.id)).collect();
+    let mut next = ids.iter().flatten().max().map_or(1, |m| m + 1);
+    let mut seen = std::collections::HashSet::new();
+    for (i, id) in ids.iter().enumerate() {
+        let Some(id) = *id else { continue };
+        if seen.insert(id) {
+            continue;
+        }
+        // the node's own `[id]` counts (parsing drops self-references); other nodes
+        // with the same ID mean themselves
+        let referrers: Vec<String> = nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(j, n)| Some((j, n.as_ref()?)))
+            .filter(|(j, n)| n.id != id || *j == i)
+            .filter(|(_, n)| {
+                extract_refs_from_str(&n.description, None).contains(&Reference::Internal(id))
+            })
+            .map(|(j, n)| format!("[{}]", if j == i { next } else { n.id }))
+            .collect();
+        let mut description = format!("reassigned [{}] to [{}]", id, next);
+        if !referrers.is_empty() {
+            description.push_str(&format!(
+                "; references to [{}] in {} now point at the first [{}] and may mean [{}]",
+                id,
+                referrers.join(" "),
+                id,
+                next
+            ));
+        }
+        let line = &lines[i];
+        let open = line.find('[').unwrap_or(0);
+        let close = line.find(']').unwrap_or(open);
+        let new = format!("{}[{}]{}", &line[..open], next, &line[close + 1..]);
+        report.line_fixes.push(LineFix {
+            line: i + 1,
+            kind: "duplicate-id",
+            description,
+            old: std::mem::replace(&mut lines[i], new.clone()),
+            new,
+        });
+        next += 1;
+    }
+}
+
+fn fix_missing_refs(lines: &mut [String], mode: MissingRefFix, report: &mut FixReport) {
+    let ids: std::collections::HashSet<u32> = lines
+        .iter()
+        .filter_map(|l| parse_node_line(l, 0).ok().map(|n| n.id))
+        .collect();
+    for (i, line) in lines.iter_mut().enumerate() {
+        let Ok(node) = parse_node_line(line, i) else {
+            continue;
+        };
+        let mut missing = Vec::new();
+        let desc = match mode {
+            MissingRefFix::Remove => {
+                let spans: Vec<std::ops::Range<usize>> = ref_spans(&node.description)
+                    .into_iter()
+                    .filter_map(|(range, r)| match r {
+                        Reference::Internal(id) if !ids.contains(&id) => {
+                            missing.push(id);
+                            Some(range)
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                remove_spans(&node.description, &spans)
+            }
+            MissingRefFix::Comment => rewrite_refs(&node.description, |r| match r {
+                Reference::Internal(id) if !ids.contains(id) => {
+                    missing.push(*id);
+                    Some(format!("<!-- missing ref {} -->", id))
+                }
+                _ => None,
+            }),
+        };
+        if missing.is_empty() {
+            continue;
+        }
+        let new = format!("{}{}", &line[..line.len() - node.description.len()], desc);
+        report.line_fixes.push(LineFix {
+            line: i + 1,
+            kind: "missing-ref",
+            description: format!(
+                "{} {}",
+                match mode {
+                    MissingRefFix::Remove => "removed",
+                    MissingRefFix::Comment => "commented out",
+                },
+                missing
+                    .iter()
+                    .map(|id| format!("[{}]", id))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            old: std::mem::replace(line, new.clone()),
+            new,
+        });
+    }
+}
+
+/// Cut `spans` out of `s`, collapsing the spaces around each cut to at most one
+/// (none at either end, or before punctuation); other whitespace is left alone
+fn remove_spans(s: &str, spans: &[std::ops::Range<usize>]) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut last = 0usize;
+    let mut pending_space = false;
+    let push = |out: &mut String, text: &str, pending_space: bool| {
+        let glued = text.starts_with([',', '.', ';', ':', '!', '?', ')']);
+        if pending_space && !out.is_empty() && !text.is_empty() && !glued {
+            out.push(' ');
+        }
+        out.push_str(text);
+    };
+    for range in spans {
+        let kept = &s[last..range.start];
+        let trimmed = kept.trim_end_matches([' ', '\t']);
+        push(&mut out, trimmed, pending_space);
+        let rest = &s[range.end..];
+        let skip = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+        pending_space =
+            (pending_space && trimmed.is_empty()) || trimmed.len() < kept.len() || skip > 0;
+        last = range.end + skip;
+    }
+    push(&mut out, &s[last..], pending_space);
+    out
+}
+
 // Helper: lightweight manual parser for the strict node format
 // Format: ^\[(\d+)\] \*\*(.+?)\*\* - (.*)$
 pub fn parse_node_line(line: &str, line_index: usize) -> Result<Node> {
@@ -544,6 +1073,15 @@ pub fn parse_node_line(line: &str, line_index: usize) -> Result<Node> {
     })
 }
 
+/// Split a raw title such as "AE: AuthService" into its type prefix and title.
+/// Titles without a ':' have no type prefix.
+pub fn split_title(raw_title: &str) -> (Option<&str>, &str) {
+    match raw_title.find(':') {
+        Some(pos) => (Some(raw_title[..pos].trim()), raw_title[pos + 1..].trim()),
+        None => (None, raw_title),
+    }
+}
+
 // Extract references of the form [123] or [234](./file.md) from a description string.
 // If skip_self is Some(id) then occurrences equal to that id are ignored.
 fn extract_refs_from_str(s: &str, skip_self: Option<u32>) -> Vec<Reference> {
@@ -589,6 +1127,58 @@ fn extract_refs_from_str(s: &str, skip_self: Option<u32>) -> Vec<Reference> {
     refs
 }
 
+/// Byte ranges of the `[N]` / `[N](path)` references in a description string, using
+/// the same scanning rules as `extract_refs_from_str` (self references included).
+pub fn ref_spans(s: &str) -> Vec<(std::ops::Range<usize>, Reference)> {
+    let mut spans = Vec::new();
+    let mut i = 0usize;
+    while i < s.len() {
+        let Some(rel) = s[i..].find('[') else {
+            break;
+        };
+        let start = i + rel;
+        let Some(rel_end) = s[start..].find(']') else {
+            break;
+        };
+        let end = start + rel_end;
+        let idslice = &s[start + 1..end];
+        if !idslice.is_empty()
+            && idslice.chars().all(|c| c.is_ascii_digit())
+            && let Ok(rid) = idslice.parse::<u32>()
+        {
+            let after = &s[end..];
+            let (reference, token_end) = match after.find(')') {
+                Some(paren_end) if after.starts_with("](") => (
+                    Reference::External(rid, s[end + 2..end + paren_end].to_string()),
+                    end + paren_end + 1,
+                ),
+                _ => (Reference::Internal(rid), end + 1),
+            };
+            spans.push((start..token_end, reference));
+            i = token_end;
+            continue;
+        }
+        i = end + 1;
+    }
+    spans
+}
+
+/// Rewrite `[N]` / `[N](path)` references in a description string. `f` returns the
+/// replacement text for a reference, or None to keep it as written.
+pub fn rewrite_refs(s: &str, mut f: impl FnMut(&Reference) -> Option<String>) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut last = 0usize;
+    for (range, reference) in ref_spans(s) {
+        if let Some(replacement) = f(&reference) {
+            out.push_str(&s[last..range.start]);
+            out.push_str(&replacement);
+            last = range.end;
+        }
+    }
+    out.push_str(&s[last..]);
+    out
+}
+
 // Command helpers
 
 pub fn cmd_show(mm: &Mindmap, id: u32) -> String {
@@ -638,9 +1228,11 @@ pub fn cmd_list(
     };
 
     for n in &mm.nodes {
-        // Type filter
+        // Type filter: case-insensitive; callers resolve `[types]` aliases first
         if let Some(tf) = type_filter
-            && !n.raw_title.starts_with(&format!("{}:", tf))
+            && !split_title(&n.raw_title)
+                .0
+                .is_some_and(|t| t.eq_ignore_ascii_case(tf))
         {
             continue;
         }
@@ -704,20 +1296,19 @@ pub fn cmd_list(
     res
 }
 
-pub fn cmd_refs(mm: &Mindmap, id: u32) -> Vec<String> {
-    let mut out = Vec::new();
-    for n in &mm.nodes {
-        if n.references
+/// Nodes whose description references `id` (internal references only)
+pub fn referring_nodes(mm: &Mindmap, id: u32) -> impl Iterator<Item = &Node> {
+    mm.nodes.iter().filter(move |n| {
+        n.references
             .iter()
             .any(|r| matches!(r, Reference::Internal(iid) if *iid == id))
-        {
-            out.push(format!(
-                "[{}] **{}** - {}",
-                n.id, n.raw_title, n.description
-            ));
-        }
-    }
-    out
+    })
+}
+
+pub fn cmd_refs(mm: &Mindmap, id: u32) -> Vec<String> {
+    referring_nodes(mm, id)
+        .map(|n| format!("[{}] **{}** - {}", n.id, n.raw_title, n.description))
+        .collect()
 }
 
 pub fn cmd_links(mm: &Mindmap, id: u32) -> Option<Vec<Reference>> {
@@ -727,9 +1318,65 @@ pub fn cmd_links(mm: &Mindmap, id: u32) -> Option<Vec<Reference>> {
 // NOTE: cmd_search was consolidated into cmd_list to eliminate code duplication.
 // See `Commands::Search` handler below which delegates to `cmd_list(mm, None, Some(query))`.
 
-pub fn cmd_add(mm: &mut Mindmap, type_prefix: &str, title: &str, desc: &str) -> Result<u32> {
+/// Apply `[types]` (resolved by the caller) to a node about to be written: the type
+/// prefix must be declared (case and aliases are normalized) and the type's
+/// conventions must hold. Returns the raw title to write.
+fn enforce_types(
+    mm: &Mindmap,
+    id: u32,
+    raw_title: &str,
+    desc: &str,
+    types: &config::TypesConfig,
+) -> Result<String> {
+    let (Some(typ), title) = split_title(raw_title) else {
+        return Ok(raw_title.to_string());
+    };
+    let typ = types.resolve(typ)?;
+
+    let refs = extract_refs_from_str(desc, Some(id));
+    let ref_types: Vec<&str> = refs
+        .iter()
+        .filter_map(|r| match r {
+            Reference::Internal(iid) => mm.get_node(*iid),
+            Reference::External(..) => None,
+        })
+        .filter_map(|n| split_title(&n.raw_title).0)
+        .collect();
+    let problems = types.violations(&typ, desc, &ref_types);
+    if !problems.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Node [{}] breaks type conventions: {}",
+            id,
+            problems.join("; ")
+        ));
+    }
+    Ok(format!("{}: {}", typ, title))
+}
+
+pub fn cmd_add(
+    mm: &mut Mindmap,
+    type_prefix: &str,
+    title: &str,
+    desc: &str,
+    types: &config::TypesConfig,
+) -> Result<u32> {
     let id = mm.next_id();
-    let full_title = format!("{}: {}", type_prefix, title);
+    cmd_add_with_id(mm, id, type_prefix, title, desc, types)
+}
+
+/// Add a node under a caller-chosen ID, which must not be in use
+pub fn cmd_add_with_id(
+    mm: &mut Mindmap,
+    id: u32,
+    type_prefix: &str,
+    title: &str,
+    desc: &str,
+    types: &config::TypesConfig,
+) -> Result<u32> {
+    if mm.by_id.contains_key(&id) {
+        return Err(anyhow::anyhow!("Node [{}] already exists", id));
+    }
+    let full_title = enforce_types(mm, id, &format!("{}: {}", type_prefix, title), desc, types)?;
     let line = format!("[{}] **{}** - {}", id, full_title, desc);
 
     mm.lines.push(line.clone());
@@ -750,6 +1397,38 @@ pub fn cmd_add(mm: &mut Mindmap, type_prefix: &str, title: &str, desc: &str) ->
     Ok(id)
 }
 
+/// `add --check-dupes`: fail (refuse) or warn when the just-added node `id` is a
+/// near-duplicate of an existing one; the caller saves only if this returns Ok
+fn check_added_dupes(mm: &Mindmap, id: u32, mode: DupeCheck, threshold: f64) -> Result<()> {
+    let similar = dupes::similar_to(mm, id, threshold);
+    if similar.is_empty() {
+        return Ok(());
+    }
+    let list: Vec<String> = similar
+        .iter()
+        .filter_map(|(other, s)| {
+            mm.get_node(*other)
+                .map(|n| format!("  {:.2}  [{}] **{}**", s.score, n.id, n.raw_title))
+        })
+        .collect();
+    match mode {
+        DupeCheck::Refuse => Err(anyhow::anyhow!(
+            "Refusing to add: {} near-duplicate{} already exist{}:\n{}\nUse `patch` on the existing node, or --check-dupes=warn to add anyway",
+            similar.len(),
+            if similar.len() == 1 { "" } else { "s" },
+            if similar.len() == 1 { "s" } else { "" },
+            list.join("\n")
+        )),
+        DupeCheck::Warn => {
+            eprintln!("Warning: node [{}] looks like a near-duplicate of:", id);
+            for line in list {
+                eprintln!("{}", line);
+            }
+            Ok(())
+        }
+    }
+}
+
 pub fn cmd_add_editor(mm: &mut Mindmap, editor: &str, strict: bool) -> Result<u32> {
     // require interactive terminal for editor
     if !atty::is(atty::Stream::Stdin) {
@@ -864,18 +1543,17 @@ pub fn cmd_verify(mm: &mut Mindmap, id: u32) -> Result<()> {
         .ok_or_else(|| anyhow::anyhow!(format!("Node [{}] not found", id)))?;
     let node = &mut mm.nodes[idx];
 
-    let tag = format!("(verify {})", chrono::Local::now().format("%Y-%m-%d"));
-    if !node.description.contains("(verify ") {
-        if node.description.is_empty() {
-            node.description = tag.clone();
-        } else {
-            node.description = format!("{} {}", node.description, tag);
-        }
-        mm.lines[node.line_index] = format!(
-            "[{}] **{}** - {}",
-            node.id, node.raw_title, node.description
-        );
+    // refresh the last (verify YYYY-MM-DD) tag, or append one
+    let tag = format!("(verify {})", stale::today().format("%Y-%m-%d"));
+    match stale::verify_tag(&node.description) {
+        Some((range, _)) => node.description.replace_range(range, &tag),
+        None if node.description.is_empty() => node.description = tag,
+        None => node.description = format!("{} {}", node.description, tag),
     }
+    mm.lines[node.line_index] = format!(
+        "[{}] **{}** - {}",
+        node.id, node.raw_title, node.description
+    );
     Ok(())
 }
 
@@ -931,17 +1609,30 @@ pub fn cmd_edit(mm: &mut Mindmap, id: u32, editor: &str) -> Result<()> {
     Ok(())
 }
 
-pub fn cmd_put(mm: &mut Mindmap, id: u32, line: &str, strict: bool) -> Result<()> {
+pub fn cmd_put(
+    mm: &mut Mindmap,
+    id: u32,
+    line: &str,
+    strict: bool,
+    types: &config::TypesConfig,
+) -> Result<()> {
     // full-line replace: parse provided line and enforce same id
     let idx = *mm
         .by_id
         .get(&id)
         .ok_or_else(|| anyhow::anyhow!(format!("Node [{}] not found", id)))?;
 
-    let parsed = parse_node_line(line, mm.nodes[idx].line_index)?;
+    let mut parsed = parse_node_line(line, mm.nodes[idx].line_index)?;
     if parsed.id != id {
         return Err(anyhow::anyhow!("PUT line id does not match target id"));
     }
+    let raw_title = enforce_types(mm, id, &parsed.raw_title, &parsed.description, types)?;
+    let line = if raw_title != parsed.raw_title {
+        parsed.raw_title = raw_title;
+        format!("[{}] **{}** - {}", id, parsed.raw_title, parsed.description)
+    } else {
+        line.to_string()
+    };
 
     // strict check for references
     if strict {
@@ -958,7 +1649,7 @@ pub fn cmd_put(mm: &mut Mindmap, id: u32, line: &str, strict: bool) -> Result<()
     }
 
     // apply
-    mm.lines[mm.nodes[idx].line_index] = line.to_string();
+    mm.lines[mm.nodes[idx].line_index] = line;
     let node_mut = &mut mm.nodes[idx];
     node_mut.raw_title = parsed.raw_title;
     node_mut.description = parsed.description;
@@ -974,6 +1665,7 @@ pub fn cmd_patch(
     title: Option<&str>,
     desc: Option<&str>,
     strict: bool,
+    types: &config::TypesConfig,
 ) -> Result<()> {
     let idx = *mm
         .by_id
@@ -982,12 +1674,7 @@ pub fn cmd_patch(
     let node = &mm.nodes[idx];
 
     // split existing raw_title into optional type and title
-    let mut existing_type: Option<&str> = None;
-    let mut existing_title = node.raw_title.as_str();
-    if let Some(pos) = node.raw_title.find(':') {
-        existing_type = Some(node.raw_title[..pos].trim());
-        existing_title = node.raw_title[pos + 1..].trim();
-    }
+    let (existing_type, existing_title) = split_title(&node.raw_title);
 
     let new_type = typ.unwrap_or(existing_type.unwrap_or(""));
     let new_title = title.unwrap_or(existing_title);
@@ -997,7 +1684,13 @@ pub fn cmd_patch(
     let new_raw_title = if new_type.is_empty() {
         new_title.to_string()
     } else {
-        format!("{}: {}", new_type, new_title)
+        enforce_types(
+            mm,
+            id,
+            &format!("{}: {}", new_type, new_title),
+            new_desc,
+            types,
+        )?
     };
 
     let new_line = format!("[{}] **{}** - {}", id, new_raw_title, new_desc);
@@ -1075,130 +1768,21 @@ pub fn cmd_delete(mm: &mut Mindmap, id: u32, force: bool) -> Result<()> {
     Ok(())
 }
 
-/// Validate external file references
-/// Returns list of validation issues found
-fn validate_external_references(mm: &Mindmap, workspace: &std::path::Path) -> Vec<String> {
-    let mut issues = Vec::new();
-    let mut cache = crate::cache::MindmapCache::new(workspace.to_path_buf());
-
-    for node in &mm.nodes {
-        for reference in &node.references {
-            if let Reference::External(ref_id, ref_path) = reference {
-                // 1. Check if file exists
-                let canonical_path = match cache.resolve_path(&mm.path, ref_path) {
-                    Ok(p) => p,
-                    Err(_) => {
-                        issues.push(format!(
-                            "Missing file: node [{}] references missing file {}",
-                            node.id, ref_path
-                        ));
-                        continue;
-                    }
-                };
-
-                // 2. Try to load the file
-                let ext_mm = match cache.load(&mm.path, ref_path, &std::collections::HashSet::new())
-                {
-                    Ok(m) => m,
-                    Err(e) => {
-                        issues.push(format!(
-                            "Unreadable file: node [{}] cannot read {}: {}",
-                            node.id, ref_path, e
-                        ));
-                        continue;
-                    }
-                };
-
-                // 3. Check if the referenced node exists in external file
-                if !ext_mm.by_id.contains_key(ref_id) {
-                    issues.push(format!(
-                        "Invalid node: node [{}] references non-existent [{}] in {}",
-                        node.id,
-                        ref_id,
-                        canonical_path.display()
-                    ));
-                }
-            }
-        }
-    }
-
-    issues
-}
-
-pub fn cmd_lint(mm: &Mindmap) -> Result<Vec<String>> {
-    let mut warnings = Vec::new();
-
-    // 1) Syntax: lines starting with '[' but not matching node format
-    for (i, line) in mm.lines.iter().enumerate() {
-        let trimmed = line.trim_start();
-        if trimmed.starts_with('[') && parse_node_line(trimmed, i).is_err() {
-            warnings.push(format!(
-                "Syntax: line {} starts with '[' but does not match node format",
-                i + 1
-            ));
-        }
-    }
-
-    // 2) Duplicate IDs: scan lines for node ids
-    let mut id_map: HashMap<u32, Vec<usize>> = HashMap::new();
-    for (i, line) in mm.lines.iter().enumerate() {
-        if let Ok(node) = parse_node_line(line, i) {
-            id_map.entry(node.id).or_default().push(i + 1);
-        }
-    }
-    for (id, locations) in &id_map {
-        if locations.len() > 1 {
-            warnings.push(format!(
-                "Duplicate ID: node {} appears on lines {:?}",
-                id, locations
-            ));
-        }
-    }
-
-    // 3) Missing references
-    for n in &mm.nodes {
-        for r in &n.references {
-            match r {
-                Reference::Internal(iid) => {
-                    if !mm.by_id.contains_key(iid) {
-                        warnings.push(format!(
-                            "Missing ref: node {} references missing node {}",
-                            n.id, iid
-                        ));
-                    }
-                }
-                Reference::External(eid, file) => {
-                    // Basic check (file exists)
-                    if !std::path::Path::new(file).exists() {
-                        warnings.push(format!(
-                            "Missing file: node {} references {} in missing file {}",
-                            n.id, eid, file
-                        ));
-                    }
-                }
-            }
-        }
-    }
-
-    // 4) External file validation (detailed checks)
-    let workspace = mm
-        .path
-        .parent()
-        .unwrap_or_else(|| std::path::Path::new("."));
-    let external_issues = validate_external_references(mm, workspace);
-    warnings.extend(external_issues);
-
-    if warnings.is_empty() {
-        Ok(vec!["Lint OK".to_string()])
-    } else {
-        Ok(warnings)
-    }
+/// Run the lint rules with the `[lint.rules]` levels from the map's `.mindmap.toml`
+/// (resolved by the caller). An empty result means the map is clean.
+pub fn cmd_lint(mm: &Mindmap, config: &config::Config) -> Result<Vec<lint::Finding>> {
+    lint::validate_config(&config.lint)?;
+    Ok(lint::lint(mm, config))
 }
 
-pub fn cmd_orphans(mm: &Mindmap, with_descriptions: bool) -> Result<Vec<String>> {
+pub fn cmd_orphans(
+    mm: &Mindmap,
+    with_descriptions: bool,
+    config: &config::Config,
+) -> Result<Vec<String>> {
     let mut warnings = Vec::new();
 
-    // Orphans: nodes with no in and no out, excluding META:*
+    // Orphans: nodes with no in and no out, excluding META:* (or `[orphans] exempt`)
     let mut incoming: HashMap<u32, usize> = HashMap::new();
     for n in &mm.nodes {
         incoming.entry(n.id).or_insert(0);
@@ -1217,8 +1801,7 @@ pub fn cmd_orphans(mm: &Mindmap, with_descriptions: bool) -> Result<Vec<String>>
     for n in &mm.nodes {
         let inc = incoming.get(&n.id).copied().unwrap_or(0);
         let out = n.references.len();
-        let title_up = n.raw_title.to_uppercase();
-        if inc == 0 && out == 0 && !title_up.starts_with("META") {
+        if inc == 0 && out == 0 && !config.orphan_exempt(&n.raw_title) {
             orphan_nodes.push(n.clone());
         }
     }
@@ -1299,7 +1882,11 @@ pub fn cmd_graph(mm: &Mindmap, id: u32) -> Result<String> {
     Ok(dot)
 }
 
-pub fn cmd_types(mm: &Mindmap, type_of: Option<&str>) -> Result<Vec<String>> {
+pub fn cmd_types(
+    mm: &Mindmap,
+    type_of: Option<&str>,
+    types: &config::TypesConfig,
+) -> Result<Vec<String>> {
     // Collect all types with their counts
     let mut type_counts: std::collections::HashMap<String, usize> =
         std::collections::HashMap::new();
@@ -1315,11 +1902,25 @@ pub fn cmd_types(mm: &Mindmap, type_of: Option<&str>) -> Result<Vec<String>> {
     }
 
     let mut results = Vec::new();
+    let describe = |t: &str| {
+        types
+            .defs
+            .get(t)
+            .filter(|d| !d.description.is_empty())
+            .map(|d| format!(" - {}", d.description))
+            .unwrap_or_default()
+    };
 
     if let Some(specific_type) = type_of {
-        // Show details for specific type
+        // Show details for specific type (aliases resolve to the declared prefix)
+        let specific_type = types.canonical(specific_type).unwrap_or(specific_type);
         if let Some(count) = type_counts.get(specific_type) {
-            results.push(format!("Type '{}': {} nodes", specific_type, count));
+            results.push(format!(
+                "Type '{}': {} nodes{}",
+                specific_type,
+                count,
+                describe(specific_type)
+            ));
             if let Some(examples) = type_examples.get(specific_type) {
                 results.push(format!(
                     "  Examples: {}",
@@ -1340,7 +1941,12 @@ pub fn cmd_types(mm: &Mindmap, type_of: Option<&str>) -> Result<Vec<String>> {
         let mut sorted_types: Vec<_> = type_counts.iter().collect();
         sorted_types.sort_by(|a, b| b.1.cmp(a.1)); // Sort by count descending
         for (node_type, count) in sorted_types {
-            results.push(format!("  {:<10} ({:>3} nodes)", node_type, count));
+            results.push(format!(
+                "  {:<10} ({:>3} nodes){}",
+                node_type,
+                count,
+                describe(node_type)
+            ));
         }
     }
 
@@ -1377,7 +1983,147 @@ fn blake3_hash(content: &[u8]) -> String {
     blake3::hash(content).to_hex().to_string()
 }
 
-#[derive(Debug, Clone)]
+/// Read command input from a file, or from stdin when the path is '-' or omitted
+fn read_input(input: Option<&std::path::Path>) -> Result<String> {
+    let mut buf = String::new();
+    match input {
+        Some(p) if p.as_os_str() != "-" => {
+            buf = std::fs::read_to_string(p)
+                .with_context(|| format!("Failed to read input {}", p.display()))?;
+        }
+        _ => {
+            std::io::stdin().read_to_string(&mut buf)?;
+        }
+    }
+    Ok(buf)
+}
+
+/// Re-read the target file and fail if it no longer matches `base_hash`
+/// (concurrency guard for staged, multi-op commits); `command` names the writer
+fn ensure_unchanged(path: &std::path::Path, base_hash: &str, command: &str) -> Result<()> {
+    let current_content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to re-read file before commit {}", path.display()))?;
+    let current_hash = blake3_hash(current_content.as_bytes());
+
+    if current_hash != base_hash {
+        return Err(anyhow::anyhow!(
+            "Cannot commit {}: target file changed since {} began (hash mismatch).\n\
+             Base hash: {}\n\
+             Current hash: {}\n\
+             The file was likely modified by another process. \
+             Re-run {} on the current file.",
+            command,
+            command,
+            base_hash,
+            current_hash,
+            command
+        ));
+    }
+    Ok(())
+}
+
+/// Lint warnings (non-blocking) for a staged mindmap, added to its `BatchResult`
+/// before `finish_staged`
+fn staged_lint_warnings(mm: &Mindmap, config: &config::Config) -> Result<Vec<String>> {
+    cmd_lint(mm, config)
+        .map(|findings| findings.iter().map(|f| f.describe()).collect())
+        .map_err(|e| anyhow::anyhow!("Lint check failed: {}", e))
+}
+
+/// Finish a staged multi-op command (batch, import): with lint warnings already in
+/// `result`, either print a dry-run preview or commit atomically behind the blake3
+/// hash guard.
+/// With `changed_only`, the human dry-run preview lists only added/patched node lines
+/// instead of the whole document.
+fn finish_staged(
+    mm: &mut Mindmap,
+    result: BatchResult,
+    command: &str,
+    dry_run: bool,
+    changed_only: bool,
+    base_hash: &str,
+    output: &OutputFormat,
+) -> Result<()> {
+    if dry_run {
+        // Print what would be written
+        if matches!(output, OutputFormat::Json) {
+            let obj = serde_json::json!({
+                "command": command,
+                "dry_run": true,
+                "result": result,
+                "content": mm.lines.join("\n") + "\n"
+            });
+            println!("{}", serde_json::to_string_pretty(&obj)?);
+        } else {
+            eprintln!("--- DRY RUN: No changes written ---");
+            eprintln!(
+                "Would apply {} operations: {} added, {} patched, {} deleted",
+                result.applied,
+                result.added_ids.len(),
+                result.patched_ids.len(),
+                result.deleted_ids.len()
+            );
+            if !result.warnings.is_empty() {
+                eprintln!("Warnings:");
+                for w in &result.warnings {
+                    eprintln!("  {}", w);
+                }
+            }
+            if changed_only {
+                for id in result.added_ids.iter().chain(&result.patched_ids) {
+                    if let Some(node) = mm.get_node(*id) {
+                        println!("{}", mm.lines[node.line_index]);
+                    }
+                }
+            } else {
+                println!("{}", mm.lines.join("\n"));
+            }
+        }
+        return Ok(());
+    }
+
+    // Check file hash again before writing (concurrency guard)
+    ensure_unchanged(&mm.path, base_hash, command)?;
+
+    // Persist changes atomically
+    mm.save()?;
+
+    if matches!(output, OutputFormat::Json) {
+        let obj = serde_json::json!({
+            "command": command,
+            "dry_run": false,
+            "result": result
+        });
+        println!("{}", serde_json::to_string_pretty(&obj)?);
+    } else {
+        let mut label = command.to_string();
+        if let Some(first) = label.get_mut(..1) {
+            first.make_ascii_uppercase();
+        }
+        eprintln!(
+            "{} applied successfully: {} ops applied",
+            label, result.applied
+        );
+        if !result.added_ids.is_empty() {
+            eprintln!("  Added nodes: {:?}", result.added_ids);
+        }
+        if !result.patched_ids.is_empty() {
+            eprintln!("  Patched nodes: {:?}", result.patched_ids);
+        }
+        if !result.deleted_ids.is_empty() {
+            eprintln!("  Deleted nodes: {:?}", result.deleted_ids);
+        }
+        if !result.warnings.is_empty() {
+            eprintln!("Warnings:");
+            for w in &result.warnings {
+                eprintln!("  {}", w);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
 enum BatchOp {
     Add {
         type_prefix: String,
@@ -1415,6 +2161,138 @@ pub struct BatchResult {
     pub patched_ids: Vec<u32>,
     pub deleted_ids: Vec<u32>,
     pub warnings: Vec<String>,
+    /// Old → new ID mapping (MINDMAP merge import only)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub id_map: Vec<import::IdMapping>,
+}
+
+/// Replay batch ops on `mm` in order (then apply auto-fixes if `fix`). Stops at the
+/// first failing op; callers stage on a clone so a failure leaves nothing half-applied.
+fn apply_batch_ops(
+    mm: &mut Mindmap,
+    ops: &[BatchOp],
+    fix: bool,
+    types: &config::TypesConfig,
+) -> Result<BatchResult> {
+    // Replay ops
+    let mut result = BatchResult {
+        total_ops: ops.len(),
+        applied: 0,
+        added_ids: Vec::new(),
+        patched_ids: Vec::new(),
+        deleted_ids: Vec::new(),
+        warnings: Vec::new(),
+        id_map: Vec::new(),
+    };
+
+    for (i, op) in ops.iter().enumerate() {
+        match op {
+            BatchOp::Add {
+                type_prefix,
+                title,
+                desc,
+            } => match cmd_add(mm, type_prefix, title, desc, types) {
+                Ok(id) => {
+                    result.added_ids.push(id);
+                    result.applied += 1;
+                }
+                Err(e) => {
+                    return Err(anyhow::anyhow!("Op {}: add failed: {}", i, e));
+                }
+            },
+            BatchOp::Patch {
+                id,
+                type_prefix,
+                title,
+                desc,
+            } => {
+                match cmd_patch(
+                    mm,
+                    *id,
+                    type_prefix.as_deref(),
+                    title.as_deref(),
+                    desc.as_deref(),
+                    false,
+                    types,
+                ) {
+                    Ok(_) => {
+                        result.patched_ids.push(*id);
+                        result.applied += 1;
+                    }
+                    Err(e) => {
+                        return Err(anyhow::anyhow!("Op {}: patch failed: {}", i, e));
+                    }
+                }
+            }
+            BatchOp::Put { id, line } => match cmd_put(mm, *id, line, false, types) {
+                Ok(_) => {
+                    result.patched_ids.push(*id);
+                    result.applied += 1;
+                }
+                Err(e) => {
+                    return Err(anyhow::anyhow!("Op {}: put failed: {}", i, e));
+                }
+            },
+            BatchOp::Delete { id, force } => match cmd_delete(mm, *id, *force) {
+                Ok(_) => {
+                    result.deleted_ids.push(*id);
+                    result.applied += 1;
+                }
+                Err(e) => {
+                    return Err(anyhow::anyhow!("Op {}: delete failed: {}", i, e));
+                }
+            },
+            BatchOp::Deprecate { id, to } => match cmd_deprecate(mm, *id, *to) {
+                Ok(_) => {
+                    result.patched_ids.push(*id);
+                    result.applied += 1;
+                }
+                Err(e) => {
+                    return Err(anyhow::anyhow!("Op {}: deprecate failed: {}", i, e));
+                }
+            },
+            BatchOp::Verify { id } => match cmd_verify(mm, *id) {
+                Ok(_) => {
+                    result.patched_ids.push(*id);
+                    result.applied += 1;
+                }
+                Err(e) => {
+                    return Err(anyhow::anyhow!("Op {}: verify failed: {}", i, e));
+                }
+            },
+        }
+    }
+
+    // Apply auto-fixes if requested
+    if fix {
+        match mm.apply_fixes() {
+            Ok(report) => {
+                if !report.spacing.is_empty() {
+                    result.warnings.push(format!(
+                        "Auto-fixed: inserted {} spacing lines",
+                        report.spacing.len()
+                    ));
+                }
+                for tf in &report.title_fixes {
+                    result.warnings.push(format!(
+                        "Auto-fixed title for node {}: '{}' -> '{}'",
+                        tf.id, tf.old, tf.new
+                    ));
+                }
+                for lf in &report.line_fixes {
+                    result.warnings.push(format!(
+                        "Auto-fixed line {} ({}: {}): '{}' -> '{}'",
+                        lf.line, lf.kind, lf.description, lf.old, lf.new
+                    ));
+                }
+            }
+            Err(e) => {
+                return Err(anyhow::anyhow!("Failed to apply fixes: {}", e));
+            }
+        }
+    }
+
+    Ok(result)
 }
 
 /// Parse a batch operation from a JSON value
@@ -1779,8 +2657,199 @@ fn get_outgoing_recursive(
     Ok(outbound)
 }
 
+fn cmd_lint_staged(
+    file: &Path,
+    fix: bool,
+    output: &OutputFormat,
+    config: &config::Config,
+) -> Result<()> {
+    let res = hooks::lint_staged(file, fix, config)?;
+    if matches!(output, OutputFormat::Json) {
+        let obj = serde_json::json!({"command": "lint", "staged": true, "result": res});
+        println!("{}", serde_json::to_string_pretty(&obj)?);
+    } else {
+        if res.fixed {
+            eprintln!("Applied fixes to staged {}", file.display());
+        }
+        if res.working_tree_untouched {
+            eprintln!(
+                "Note: working tree differs from the index; fixes were applied to the index only"
+            );
+        }
+        for f in &res.findings {
+            eprintln!("  - {}", f.describe());
+        }
+    }
+    lint_errors(&res.findings, &format!("staged {}", file.display()))
+}
+
+/// Err when any finding has error severity, so the exit code reflects it
+fn lint_errors(findings: &[lint::Finding], what: &str) -> Result<()> {
+    let errors = lint::count(findings, lint::Severity::Error);
+    if errors > 0 {
+        return Err(anyhow::anyhow!(
+            "Lint found {} error{} in {}",
+            errors,
+            if errors == 1 { "" } else { "s" },
+            what
+        ));
+    }
+    Ok(())
+}
+
+/// Human lint report on stderr
+fn print_findings(findings: &[lint::Finding]) {
+    if findings.is_empty() {
+        eprintln!("✓ Lint OK (0 warnings)");
+        return;
+    }
+    eprintln!(
+        "Lint found {} issue{} ({} error, {} warn, {} info):",
+        findings.len(),
+        if findings.len() == 1 { "" } else { "s" },
+        lint::count(findings, lint::Severity::Error),
+        lint::count(findings, lint::Severity::Warn),
+        lint::count(findings, lint::Severity::Info)
+    );
+    for f in findings {
+        eprintln!("  - {}", f.describe());
+    }
+}
+
+fn cmd_diff(
+    old: &str,
+    new: &str,
+    file: &Path,
+    markdown: bool,
+    stat: bool,
+    output: &OutputFormat,
+) -> Result<()> {
+    let (old_label, old_mm) = diff::load_side(old, file)?;
+    let (new_label, new_mm) = diff::load_side(new, file)?;
+    let d = diff::diff(&old_mm, &new_mm);
+
+    if matches!(output, OutputFormat::Json) {
+        let obj =
+            serde_json::json!({"command": "diff", "old": old_label, "new": new_label, "diff": d});
+        println!("{}", serde_json::to_string_pretty(&obj)?);
+    } else if markdown {
+        print!("{}", diff::format_markdown(&d, &old_label, &new_label));
+    } else if d.is_empty() {
+        eprintln!("No node changes between {} and {}", old_label, new_label);
+    } else {
+        if !stat {
+            for line in diff::format_human(&d) {
+                println!("{}", line);
+            }
+        }
+        eprintln!("{}", d.summary());
+    }
+    Ok(())
+}
+
+/// `merge-driver %O %A %B %L %P`: write the merge into %A; conflicts are an error so
+/// git sees a non-zero exit and keeps the file conflicted
+fn cmd_merge_driver(
+    base: &Path,
+    ours: &Path,
+    theirs: &Path,
+    marker_size: usize,
+    display: Option<&str>,
+) -> Result<()> {
+    let read =
+        |p: &Path| fs::read_to_string(p).with_context(|| format!("Failed to read {}", p.display()));
+    let outcome = merge::merge(&read(base)?, &read(ours)?, &read(theirs)?, marker_size)?;
+
+    let tmp_dir = ours
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let mut tmp = tempfile::NamedTempFile::new_in(tmp_dir)?;
+    tmp.write_all(outcome.content.as_bytes())?;
+    tmp.persist(ours)
+        .with_context(|| format!("Failed to write {}", ours.display()))?;
+
+    let name = display
+        .map(str::to_string)
+        .unwrap_or_else(|| ours.display().to_string());
+    for (old, new) in &outcome.renumbered {
+        eprintln!("{}: renumbered their node [{}] to [{}]", name, old, new);
+    }
+    if outcome.conflicts.is_empty() && !outcome.text_conflict {
+        return Ok(());
+    }
+    let mut what: Vec<String> = outcome
+        .conflicts
+        .iter()
+        .map(|id| format!("[{}]", id))
+        .collect();
+    if outcome.text_conflict {
+        what.push("non-node text".to_string());
+    }
+    Err(anyhow::anyhow!(
+        "{}: merge conflict in {}",
+        name,
+        what.join(", ")
+    ))
+}
+
 pub fn run(cli: Cli) -> Result<()> {
-    let path = cli.file.unwrap_or_else(|| PathBuf::from("MINDMAP.md"));
+    // project defaults from the nearest .mindmap.toml; flags win over it
+    let config = config::Config::discover(Path::new("."))?;
+    let output = config.output_format(cli.output.clone());
+
+    // Commands that do not operate on a single --file are dispatched before loading it
+    match &cli.command {
+        Commands::Lsp => return lsp::run_stdio(),
+        Commands::MergeDriver {
+            base,
+            ours,
+            theirs,
+            marker_size,
+            path,
+        } => return cmd_merge_driver(base, ours, theirs, *marker_size, path.as_deref()),
+        Commands::Diff {
+            old,
+            new,
+            markdown,
+            stat,
+        } => {
+            let file = config.mindmap_path(cli.file.clone());
+            let new = new
+                .clone()
+                .unwrap_or_else(|| file.to_string_lossy().into_owned());
+            return cmd_diff(old, &new, &file, *markdown, *stat, &output);
+        }
+        Commands::Lint {
+            fix, staged: true, ..
+        } => {
+            let file = config.mindmap_path(cli.file.clone());
+            return cmd_lint_staged(&file, *fix, &output, &config.for_mindmap_from(&file)?);
+        }
+        Commands::Hooks { action } => {
+            let file = config.mindmap_path(cli.file.clone());
+            let dir = file
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or_else(|| Path::new("."));
+            match action {
+                HooksAction::Install { fix, force } => {
+                    let hook = hooks::install(&file, *fix, *force)?;
+                    eprintln!("Installed pre-commit hook at {}", hook.display());
+                }
+                HooksAction::Uninstall => {
+                    let hook = hooks::uninstall(dir)?;
+                    eprintln!("Removed pre-commit hook {}", hook.display());
+                }
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let path = config.mindmap_path(cli.file);
+    // per-map policies (types, lint, orphans, stale, dupes), resolved once
+    let map_config = config.for_mindmap_from(&path)?;
 
     // If user passed '-' use stdin as source
     let mut mm = if path.as_os_str() == "-" {
@@ -1795,10 +2864,10 @@ pub fn run(cli: Cli) -> Result<()> {
     let pretty_enabled = match env_override.as_deref() {
         Some("0") => false,
         Some("1") => true,
-        _ => interactive,
-    } && matches!(cli.output, OutputFormat::Default);
+        _ => config.output.pretty.unwrap_or(interactive),
+    } && matches!(output, OutputFormat::Default);
 
-    let printer: Option<Box<dyn ui::Printer>> = if matches!(cli.output, OutputFormat::Default) {
+    let printer: Option<Box<dyn ui::Printer>> = if matches!(output, OutputFormat::Default) {
         if pretty_enabled {
             Some(Box::new(crate::ui::PrettyPrinter::new()?))
         } else {
@@ -1817,17 +2886,41 @@ pub fn run(cli: Cli) -> Result<()> {
     };
 
     match cli.command {
-        Commands::Show { id, follow, desc } => match mm.get_node(id) {
+        Commands::Show {
+            id,
+            follow,
+            desc,
+            code,
+        } => match mm.get_node(id) {
             Some(node) => {
+                let cited_by = if code {
+                    let root = path.parent().unwrap_or_else(|| Path::new("."));
+                    let root = if root.as_os_str().is_empty() {
+                        Path::new(".")
+                    } else {
+                        root
+                    };
+                    let scan = citations::scan(root, root, map_config.cache.max_file_size)?;
+                    Some(
+                        scan.citations
+                            .into_iter()
+                            .filter(|c| c.id == id)
+                            .collect::<Vec<_>>(),
+                    )
+                } else {
+                    None
+                };
                 if follow {
                     // Recursive mode: follow external references
                     let workspace = path.parent().unwrap_or_else(|| std::path::Path::new("."));
-                    let mut cache = crate::cache::MindmapCache::new(workspace.to_path_buf());
-                    let mut ctx = crate::context::NavigationContext::new();
+                    let mut cache = map_config.cache.cache(workspace);
+                    let mut ctx = crate::context::NavigationContext::with_max_depth(
+                        map_config.cache.max_depth,
+                    );
                     let mut visited = std::collections::HashSet::new();
                     visited.insert(path.clone());
 
-                    if matches!(cli.output, OutputFormat::Json) {
+                    if matches!(output, OutputFormat::Json) {
                         // JSON output with recursive refs
                         let inbound =
                             get_incoming_recursive(&mut cache, &mm, &path, id, &visited, &mut ctx)
@@ -1858,7 +2951,7 @@ pub fn run(cli: Cli) -> Result<()> {
                             })
                             .collect();
 
-                        let obj = serde_json::json!({
+                        let mut obj = serde_json::json!({
                             "command": "show",
                             "follow": true,
                             "node": {
@@ -1871,6 +2964,9 @@ pub fn run(cli: Cli) -> Result<()> {
                             "incoming": inbound_refs,
                             "outgoing": outbound_refs,
                         });
+                        if let Some(cited_by) = &cited_by {
+                            obj["code"] = serde_json::json!(cited_by);
+                        }
                         println!("{}", serde_json::to_string_pretty(&obj)?);
                     } else {
                         // Human-readable output with recursive refs
@@ -1927,8 +3023,8 @@ pub fn run(cli: Cli) -> Result<()> {
                     }
                 } else {
                     // Single-file mode: original behavior
-                    if matches!(cli.output, OutputFormat::Json) {
-                        let obj = serde_json::json!({
+                    if matches!(output, OutputFormat::Json) {
+                        let mut obj = serde_json::json!({
                             "command": "show",
                             "follow": false,
                             "node": {
@@ -1940,6 +3036,9 @@ pub fn run(cli: Cli) -> Result<()> {
                                 "line_index": node.line_index,
                             }
                         });
+                        if let Some(cited_by) = &cited_by {
+                            obj["code"] = serde_json::json!(cited_by);
+                        }
                         println!("{}", serde_json::to_string_pretty(&obj)?);
                     } else {
                         // compute inbound refs (single-file only)
@@ -1978,6 +3077,18 @@ pub fn run(cli: Cli) -> Result<()> {
                         }
                     }
                 }
+                if let Some(cited_by) = &cited_by
+                    && !matches!(output, OutputFormat::Json)
+                {
+                    if cited_by.is_empty() {
+                        eprintln!("⌘ No code cites MINDMAP[{}]", id);
+                    } else {
+                        eprintln!("⌘ Cited in code ({}):", cited_by.len());
+                        for c in cited_by {
+                            eprintln!("  {}  {}", citations::location(c), c.text);
+                        }
+                    }
+                }
             }
             None => {
                 let min_id = mm.nodes.iter().map(|n| n.id).min();
@@ -2000,9 +3111,11 @@ pub fn run(cli: Cli) -> Result<()> {
             exact_match,
             regex_mode,
         } => {
+            let types = &map_config.types;
+            let type_filter = r#type.as_deref().map(|t| types.canonical(t).unwrap_or(t));
             let items = cmd_list(
                 &mm,
-                r#type.as_deref(),
+                type_filter,
                 grep.as_deref(),
                 case_sensitive,
                 exact_match,
@@ -2010,7 +3123,7 @@ pub fn run(cli: Cli) -> Result<()> {
             );
             let count = items.len();
 
-            if matches!(cli.output, OutputFormat::Json) {
+            if matches!(output, OutputFormat::Json) {
                 let arr: Vec<_> = items
                     .into_iter()
                     .map(|line| serde_json::json!({"line": line}))
@@ -2052,8 +3165,9 @@ pub fn run(cli: Cli) -> Result<()> {
             if follow {
                 // Recursive mode: get all incoming refs across files
                 let workspace = path.parent().unwrap_or_else(|| std::path::Path::new("."));
-                let mut cache = crate::cache::MindmapCache::new(workspace.to_path_buf());
-                let mut ctx = crate::context::NavigationContext::new();
+                let mut cache = map_config.cache.cache(workspace);
+                let mut ctx =
+                    crate::context::NavigationContext::with_max_depth(map_config.cache.max_depth);
                 let mut visited = std::collections::HashSet::new();
                 visited.insert(path.clone());
 
@@ -2062,7 +3176,7 @@ pub fn run(cli: Cli) -> Result<()> {
                         .unwrap_or_default();
                 let count = inbound.len();
 
-                if matches!(cli.output, OutputFormat::Json) {
+                if matches!(output, OutputFormat::Json) {
                     let items: Vec<_> = inbound
                         .iter()
                         .map(|(ref_id, ref_path, ref_node)| {
@@ -2107,7 +3221,7 @@ pub fn run(cli: Cli) -> Result<()> {
                 let items = cmd_refs(&mm, id);
                 let count = items.len();
 
-                if matches!(cli.output, OutputFormat::Json) {
+                if matches!(output, OutputFormat::Json) {
                     let obj = serde_json::json!({
                         "command": "refs",
                         "target": id,
@@ -2153,8 +3267,9 @@ pub fn run(cli: Cli) -> Result<()> {
             if follow {
                 // Recursive mode: get all outgoing refs across files
                 let workspace = path.parent().unwrap_or_else(|| std::path::Path::new("."));
-                let mut cache = crate::cache::MindmapCache::new(workspace.to_path_buf());
-                let mut ctx = crate::context::NavigationContext::new();
+                let mut cache = map_config.cache.cache(workspace);
+                let mut ctx =
+                    crate::context::NavigationContext::with_max_depth(map_config.cache.max_depth);
                 let mut visited = std::collections::HashSet::new();
                 visited.insert(path.clone());
 
@@ -2163,7 +3278,7 @@ pub fn run(cli: Cli) -> Result<()> {
                         .unwrap_or_default();
                 let count = outbound.len();
 
-                if matches!(cli.output, OutputFormat::Json) {
+                if matches!(output, OutputFormat::Json) {
                     let items: Vec<_> = outbound
                         .iter()
                         .map(|(ref_id, ref_path, ref_node)| {
@@ -2211,7 +3326,7 @@ pub fn run(cli: Cli) -> Result<()> {
                             .iter()
                             .filter(|r| matches!(r, Reference::Internal(_)))
                             .count();
-                        if matches!(cli.output, OutputFormat::Json) {
+                        if matches!(output, OutputFormat::Json) {
                             let obj = serde_json::json!({
                                 "command": "links",
                                 "source": id,
@@ -2257,144 +3372,136 @@ pub fn run(cli: Cli) -> Result<()> {
             exact_match,
             regex_mode,
             follow,
+            limit,
         } => {
+            // nodes to search, with the file they come from when following references
+            let mut docs: Vec<(Option<PathBuf>, Node)> =
+                mm.nodes.iter().map(|n| (None, n.clone())).collect();
             if follow {
-                // Recursive mode: search across referenced files
                 let workspace = path.parent().unwrap_or_else(|| std::path::Path::new("."));
-                let mut cache = crate::cache::MindmapCache::new(workspace.to_path_buf());
-                let _ctx = crate::context::NavigationContext::new();
+                let mut cache = map_config.cache.cache(workspace);
                 let mut visited_files = std::collections::HashSet::new();
                 visited_files.insert(path.clone());
-
-                // Search main file
-                let mut all_items = cmd_list(
-                    &mm,
-                    None,
-                    Some(&query),
-                    case_sensitive,
-                    exact_match,
-                    regex_mode,
-                );
-
-                // Track processed files to avoid duplicates
                 let mut processed_files = std::collections::HashSet::new();
                 processed_files.insert(path.clone());
 
-                // Search referenced files
                 for node in &mm.nodes {
                     for ref_item in &node.references {
                         if let Reference::External(_id, ref_path) = ref_item {
-                            // Try to get canonical path
                             let canonical_path = match cache.resolve_path(&path, ref_path) {
                                 Ok(p) => p,
                                 Err(_) => continue,
                             };
-
-                            // Skip if already processed
-                            if processed_files.contains(&canonical_path) {
+                            if !processed_files.insert(canonical_path.clone()) {
                                 continue;
                             }
-                            processed_files.insert(canonical_path.clone());
-
-                            // Try to load external file
                             if let Ok(ext_mm) = cache.load(&path, ref_path, &visited_files) {
-                                let ext_items = cmd_list(
-                                    ext_mm,
-                                    None,
-                                    Some(&query),
-                                    case_sensitive,
-                                    exact_match,
-                                    regex_mode,
+                                docs.extend(
+                                    ext_mm
+                                        .nodes
+                                        .iter()
+                                        .map(|n| (Some(canonical_path.clone()), n.clone())),
                                 );
-                                for item in ext_items {
-                                    // Append file path to item
-                                    all_items.push(format!(
-                                        "{} ({})",
-                                        item,
-                                        canonical_path.display()
-                                    ));
-                                }
                             }
                         }
                     }
                 }
+            }
 
-                let count = all_items.len();
-
-                if matches!(cli.output, OutputFormat::Json) {
-                    let arr: Vec<_> = all_items
-                        .into_iter()
-                        .map(|line| serde_json::json!({"line": line}))
-                        .collect();
-                    let obj = serde_json::json!({
-                        "command": "search",
-                        "query": query,
-                        "follow": true,
-                        "count": count,
-                        "items": arr
-                    });
-                    println!("{}", serde_json::to_string_pretty(&obj)?);
+            let mut hits: Vec<search::Hit> = if regex_mode {
+                let pattern = if case_sensitive {
+                    query.clone()
                 } else {
-                    if count == 0 {
-                        eprintln!("No matches for '{}' (0 results)", query);
-                    } else {
-                        eprintln!(
-                            "Search results for '{}' (recursive, {} result{})",
-                            query,
-                            count,
-                            if count == 1 { "" } else { "s" }
-                        );
-                    }
-                    if let Some(p) = &printer {
-                        p.list(&all_items)?;
-                    } else {
-                        for it in all_items {
-                            println!("{}", it);
-                        }
-                    }
-                }
+                    format!("(?i){}", query)
+                };
+                let re = regex::Regex::new(&pattern)
+                    .map_err(|e| anyhow::anyhow!("Invalid regex pattern: {}", e))?;
+                let find = |text: &str| re.find_iter(text).map(|m| m.range()).collect();
+                docs.iter()
+                    .enumerate()
+                    .map(|(index, (_, n))| search::Hit {
+                        index,
+                        node: n,
+                        score: 0.0,
+                        title_matches: find(&n.raw_title),
+                        description_matches: find(&n.description),
+                    })
+                    .filter(|h| !h.title_matches.is_empty() || !h.description_matches.is_empty())
+                    .collect()
             } else {
-                // Single-file mode: original behavior
-                let items = cmd_list(
-                    &mm,
-                    None,
-                    Some(&query),
+                let opts = search::Options {
                     case_sensitive,
-                    exact_match,
-                    regex_mode,
-                );
-                let count = items.len();
-
-                if matches!(cli.output, OutputFormat::Json) {
-                    let arr: Vec<_> = items
-                        .into_iter()
-                        .map(|line| serde_json::json!({"line": line}))
-                        .collect();
-                    let obj = serde_json::json!({
-                        "command": "search",
-                        "query": query,
-                        "follow": false,
-                        "count": count,
-                        "items": arr
-                    });
-                    println!("{}", serde_json::to_string_pretty(&obj)?);
+                    exact: exact_match,
+                };
+                search::search(
+                    docs.iter().map(|(_, n)| n),
+                    &search::Query::parse(&query),
+                    &opts,
+                )
+            };
+            hits.truncate(limit.unwrap_or(usize::MAX));
+            let count = hits.len();
+            let file_of = |h: &search::Hit| docs[h.index].0.clone();
+
+            if matches!(output, OutputFormat::Json) {
+                let arr: Vec<_> = hits
+                    .iter()
+                    .map(|h| {
+                        let n = h.node;
+                        let mut item = serde_json::json!({
+                            "id": n.id,
+                            "raw_title": n.raw_title,
+                            "score": (!regex_mode).then_some(h.score),
+                            "line": format!("[{}] **{}** - {}", n.id, n.raw_title, n.description),
+                            "snippet": search::snippet(&n.description, &h.description_matches, search::SNIPPET_WIDTH),
+                            "matches": {
+                                "title": h.title_matches.iter().map(|r| [r.start, r.end]).collect::<Vec<_>>(),
+                                "description": h.description_matches.iter().map(|r| [r.start, r.end]).collect::<Vec<_>>(),
+                            },
+                        });
+                        if let Some(file) = file_of(h) {
+                            item["file"] = serde_json::json!(file.to_string_lossy());
+                        }
+                        item
+                    })
+                    .collect();
+                let obj = serde_json::json!({
+                    "command": "search",
+                    "query": query,
+                    "follow": follow,
+                    "ranked": !regex_mode,
+                    "count": count,
+                    "items": arr
+                });
+                println!("{}", serde_json::to_string_pretty(&obj)?);
+            } else {
+                if count == 0 {
+                    eprintln!("No matches for '{}' (0 results)", query);
                 } else {
-                    if count == 0 {
-                        eprintln!("No matches for '{}' (0 results)", query);
-                    } else {
-                        eprintln!(
-                            "Search results for '{}' ({} result{})",
-                            query,
-                            count,
-                            if count == 1 { "" } else { "s" }
-                        );
-                    }
-                    if let Some(p) = &printer {
-                        p.list(&items)?;
-                    } else {
-                        for it in items {
-                            println!("{}", it);
+                    eprintln!(
+                        "Search results for '{}' ({}{} result{}{})",
+                        query,
+                        if follow { "recursive, " } else { "" },
+                        count,
+                        if count == 1 { "" } else { "s" },
+                        if regex_mode { "" } else { ", best first" }
+                    );
+                }
+                let items: Vec<String> = hits
+                    .iter()
+                    .map(|h| {
+                        let line = search::format_hit(h);
+                        match file_of(h) {
+                            Some(file) => format!("{} ({})", line, file.display()),
+                            None => line,
                         }
+                    })
+                    .collect();
+                if let Some(p) = &printer {
+                    p.list(&items)?;
+                } else {
+                    for it in items {
+                        println!("{}", it);
                     }
                 }
             }
@@ -2404,15 +3511,19 @@ pub fn run(cli: Cli) -> Result<()> {
             title,
             desc,
             strict,
+            check_dupes,
         } => {
             if mm.path.as_os_str() == "-" {
                 return Err(cannot_write_err("add"));
             }
             match (r#type.as_deref(), title.as_deref(), desc.as_deref()) {
                 (Some(tp), Some(tt), Some(dd)) => {
-                    let id = cmd_add(&mut mm, tp, tt, dd)?;
+                    let id = cmd_add(&mut mm, tp, tt, dd, &map_config.types)?;
+                    if let Some(mode) = check_dupes {
+                        check_added_dupes(&mm, id, mode, map_config.dupes.threshold)?;
+                    }
                     mm.save()?;
-                    if matches!(cli.output, OutputFormat::Json)
+                    if matches!(output, OutputFormat::Json)
                         && let Some(node) = mm.get_node(id)
                     {
                         let obj = serde_json::json!({"command": "add", "node": {"id": node.id, "raw_title": node.raw_title, "description": node.description, "references": node.references}});
@@ -2427,10 +3538,13 @@ pub fn run(cli: Cli) -> Result<()> {
                             "add via editor requires an interactive terminal"
                         ));
                     }
-                    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+                    let editor = config.editor();
                     let id = cmd_add_editor(&mut mm, &editor, strict)?;
+                    if let Some(mode) = check_dupes {
+                        check_added_dupes(&mm, id, mode, map_config.dupes.threshold)?;
+                    }
                     mm.save()?;
-                    if matches!(cli.output, OutputFormat::Json)
+                    if matches!(output, OutputFormat::Json)
                         && let Some(node) = mm.get_node(id)
                     {
                         let obj = serde_json::json!({"command": "add", "node": {"id": node.id, "raw_title": node.raw_title, "description": node.description, "references": node.references}});
@@ -2451,7 +3565,7 @@ pub fn run(cli: Cli) -> Result<()> {
             }
             cmd_deprecate(&mut mm, id, to)?;
             mm.save()?;
-            if matches!(cli.output, OutputFormat::Json)
+            if matches!(output, OutputFormat::Json)
                 && let Some(node) = mm.get_node(id)
             {
                 let obj = serde_json::json!({"command": "deprecate", "node": {"id": node.id, "raw_title": node.raw_title}});
@@ -2463,10 +3577,10 @@ pub fn run(cli: Cli) -> Result<()> {
             if mm.path.as_os_str() == "-" {
                 return Err(cannot_write_err("edit"));
             }
-            let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+            let editor = config.editor();
             cmd_edit(&mut mm, id, &editor)?;
             mm.save()?;
-            if matches!(cli.output, OutputFormat::Json)
+            if matches!(output, OutputFormat::Json)
                 && let Some(node) = mm.get_node(id)
             {
                 let obj = serde_json::json!({"command": "edit", "node": {"id": node.id, "raw_title": node.raw_title, "description": node.description, "references": node.references}});
@@ -2491,9 +3605,10 @@ pub fn run(cli: Cli) -> Result<()> {
                 title.as_deref(),
                 desc.as_deref(),
                 strict,
+                &map_config.types,
             )?;
             mm.save()?;
-            if matches!(cli.output, OutputFormat::Json)
+            if matches!(output, OutputFormat::Json)
                 && let Some(node) = mm.get_node(id)
             {
                 let obj = serde_json::json!({"command": "patch", "node": {"id": node.id, "raw_title": node.raw_title, "description": node.description, "references": node.references}});
@@ -2505,9 +3620,9 @@ pub fn run(cli: Cli) -> Result<()> {
             if mm.path.as_os_str() == "-" {
                 return Err(cannot_write_err("put"));
             }
-            cmd_put(&mut mm, id, &line, strict)?;
+            cmd_put(&mut mm, id, &line, strict, &map_config.types)?;
             mm.save()?;
-            if matches!(cli.output, OutputFormat::Json)
+            if matches!(output, OutputFormat::Json)
                 && let Some(node) = mm.get_node(id)
             {
                 let obj = serde_json::json!({"command": "put", "node": {"id": node.id, "raw_title": node.raw_title, "description": node.description, "references": node.references}});
@@ -2521,7 +3636,7 @@ pub fn run(cli: Cli) -> Result<()> {
             }
             cmd_verify(&mut mm, id)?;
             mm.save()?;
-            if matches!(cli.output, OutputFormat::Json)
+            if matches!(output, OutputFormat::Json)
                 && let Some(node) = mm.get_node(id)
             {
                 let obj = serde_json::json!({"command": "verify", "node": {"id": node.id, "description": node.description}});
@@ -2535,72 +3650,78 @@ pub fn run(cli: Cli) -> Result<()> {
             }
             cmd_delete(&mut mm, id, force)?;
             mm.save()?;
-            if matches!(cli.output, OutputFormat::Json) {
+            if matches!(output, OutputFormat::Json) {
                 let obj = serde_json::json!({"command": "delete", "deleted": id});
                 println!("{}", serde_json::to_string_pretty(&obj)?);
             }
             eprintln!("Deleted node [{}]", id);
         }
-        Commands::Lint { fix } => {
-            if fix {
-                if mm.path.as_os_str() == "-" {
+        Commands::Lint {
+            fix,
+            format,
+            missing_refs,
+            dry_run,
+            ..
+        } => {
+            let report = if fix {
+                if mm.path.as_os_str() == "-" && !dry_run {
                     return Err(cannot_write_err("lint --fix"));
                 }
 
-                // apply fixes
-                let report = mm.apply_fixes()?;
-                if report.any_changes() {
+                // apply fixes (in memory only for --dry-run)
+                let report = mm.apply_fixes_with(&FixOptions { missing_refs })?;
+                if report.any_changes() && !dry_run {
                     mm.save()?;
                 }
+                Some(report)
+            } else {
+                None
+            };
 
-                if matches!(cli.output, OutputFormat::Json) {
-                    let obj = serde_json::json!({"command": "lint", "fixed": report.any_changes(), "fixes": report});
-                    println!("{}", serde_json::to_string_pretty(&obj)?);
-                } else {
-                    if !report.spacing.is_empty() {
-                        eprintln!(
-                            "Fixed spacing: inserted {} blank lines",
-                            report.spacing.len()
-                        );
+            // lint (after fixes): remaining findings decide the exit code
+            let res = cmd_lint(&mm, &map_config)?;
+            if let (Some(_), Some(report)) = (format, &report) {
+                // stdout carries the findings; fixes go to stderr
+                for line in report.describe(if dry_run { "Would fix" } else { "Fixed" }) {
+                    eprintln!("{}", line);
+                }
+            }
+            match (format, &report) {
+                (Some(LintFormat::Sarif), _) => {
+                    println!("{}", serde_json::to_string_pretty(&lint::sarif(&mm, &res))?);
+                }
+                (Some(LintFormat::Github), _) => {
+                    for annotation in lint::github(&mm, &res) {
+                        println!("{}", annotation);
                     }
-                    for tf in &report.title_fixes {
-                        eprintln!(
-                            "Fixed title for node {}: '{}' -> '{}'",
-                            tf.id, tf.old, tf.new
-                        );
+                }
+                (None, Some(report)) if matches!(output, OutputFormat::Json) => {
+                    let obj = serde_json::json!({"command": "lint", "fixed": report.any_changes() && !dry_run, "dry_run": dry_run, "fixes": report, "findings": res});
+                    println!("{}", serde_json::to_string_pretty(&obj)?);
+                }
+                (None, None) if matches!(output, OutputFormat::Json) => {
+                    let warnings: Vec<String> = res.iter().map(|f| f.to_string()).collect();
+                    let obj = serde_json::json!({"command": "lint", "findings": res, "warnings": warnings});
+                    println!("{}", serde_json::to_string_pretty(&obj)?);
+                }
+                (None, Some(report)) => {
+                    for line in report.describe(if dry_run { "Would fix" } else { "Fixed" }) {
+                        eprintln!("{}", line);
                     }
                     if !report.any_changes() {
                         eprintln!("No fixes necessary");
                     }
-
-                    // run lint after fixes and print any remaining warnings
-                    let res = cmd_lint(&mm)?;
-                    for r in res {
-                        eprintln!("{}", r);
-                    }
-                }
-            } else {
-                let res = cmd_lint(&mm)?;
-                if matches!(cli.output, OutputFormat::Json) {
-                    let obj = serde_json::json!({"command": "lint", "warnings": res.iter().filter(|r| *r != "Lint OK").collect::<Vec<_>>()});
-                    println!("{}", serde_json::to_string_pretty(&obj)?);
-                } else if res.len() == 1 && res[0] == "Lint OK" {
-                    eprintln!("✓ Lint OK (0 warnings)");
-                } else {
-                    eprintln!(
-                        "Lint found {} warning{}:",
-                        res.len(),
-                        if res.len() == 1 { "" } else { "s" }
-                    );
-                    for r in res {
-                        eprintln!("  - {}", r);
+                    for f in &res {
+                        eprintln!("{}", f.describe());
                     }
                 }
+                (None, None) => print_findings(&res),
             }
+            lint_errors(&res, &mm.path.display().to_string())?;
         }
         Commands::Orphans { with_descriptions } => {
-            let res = cmd_orphans(&mm, with_descriptions)?;
-            if matches!(cli.output, OutputFormat::Json) {
+            let res = cmd_orphans(&mm, with_descriptions, &map_config)?;
+            if matches!(output, OutputFormat::Json) {
                 let count = if res.iter().any(|r| r == "No orphans") {
                     0
                 } else {
@@ -2633,8 +3754,8 @@ pub fn run(cli: Cli) -> Result<()> {
             }
         }
         Commands::Type { of } => {
-            let res = cmd_types(&mm, of.as_deref())?;
-            if matches!(cli.output, OutputFormat::Json) {
+            let res = cmd_types(&mm, of.as_deref(), &map_config.types)?;
+            if matches!(output, OutputFormat::Json) {
                 let obj = serde_json::json!({"command": "type", "filter": of, "results": res});
                 println!("{}", serde_json::to_string_pretty(&obj)?);
             } else {
@@ -2652,8 +3773,9 @@ pub fn run(cli: Cli) -> Result<()> {
             if follow {
                 // Recursive mode: get all relationships across files
                 let workspace = path.parent().unwrap_or_else(|| std::path::Path::new("."));
-                let mut cache = crate::cache::MindmapCache::new(workspace.to_path_buf());
-                let mut ctx = crate::context::NavigationContext::new();
+                let mut cache = map_config.cache.cache(workspace);
+                let mut ctx =
+                    crate::context::NavigationContext::with_max_depth(map_config.cache.max_depth);
                 let mut visited = std::collections::HashSet::new();
                 visited.insert(path.clone());
 
@@ -2669,7 +3791,7 @@ pub fn run(cli: Cli) -> Result<()> {
                     get_outgoing_recursive(&mut cache, &mm, &path, id, &visited, &mut ctx)
                         .unwrap_or_default();
 
-                if matches!(cli.output, OutputFormat::Json) {
+                if matches!(output, OutputFormat::Json) {
                     let incoming_json: Vec<_> = incoming
                         .iter()
                         .map(|(ref_id, ref_path, ref_node)| {
@@ -2726,7 +3848,7 @@ pub fn run(cli: Cli) -> Result<()> {
             } else {
                 // Single-file mode: original behavior
                 let (incoming, outgoing) = cmd_relationships(&mm, id)?;
-                if matches!(cli.output, OutputFormat::Json) {
+                if matches!(output, OutputFormat::Json) {
                     let obj = serde_json::json!({
                         "command": "relationships",
                         "node": id,
@@ -2800,7 +3922,7 @@ pub fn run(cli: Cli) -> Result<()> {
 
             let items = cmd_list(&mm, None, None, false, false, false);
 
-            if matches!(cli.output, OutputFormat::Json) {
+            if matches!(output, OutputFormat::Json) {
                 let arr: Vec<_> = items
                     .into_iter()
                     .map(|line| serde_json::json!({"line": line}))
@@ -2851,18 +3973,7 @@ pub fn run(cli: Cli) -> Result<()> {
             let base_hash = blake3_hash(base_content.as_bytes());
 
             // Read batch input
-            let mut buf = String::new();
-            match input {
-                Some(p) if p.as_os_str() == "-" => {
-                    std::io::stdin().read_to_string(&mut buf)?;
-                }
-                Some(p) => {
-                    buf = std::fs::read_to_string(p)?;
-                }
-                None => {
-                    std::io::stdin().read_to_string(&mut buf)?;
-                }
-            }
+            let buf = read_input(input.as_deref())?;
 
             // Parse ops
             let mut ops: Vec<BatchOp> = Vec::new();
@@ -2900,211 +4011,440 @@ pub fn run(cli: Cli) -> Result<()> {
             // Clone mm and work on clone (do not persist until all ops succeed)
             let mut mm_clone = Mindmap::from_string(base_content.clone(), path.clone())?;
 
-            // Replay ops
-            let mut result = BatchResult {
-                total_ops: ops.len(),
-                applied: 0,
-                added_ids: Vec::new(),
-                patched_ids: Vec::new(),
-                deleted_ids: Vec::new(),
-                warnings: Vec::new(),
-            };
+            let mut result = apply_batch_ops(&mut mm_clone, &ops, fix, &map_config.types)?;
+            result
+                .warnings
+                .extend(staged_lint_warnings(&mm_clone, &map_config)?);
+
+            finish_staged(
+                &mut mm_clone,
+                result,
+                "batch",
+                dry_run,
+                false,
+                &base_hash,
+                &output,
+            )?;
+        }
+        Commands::Export {
+            format,
+            out,
+            follow,
+        } => {
+            if matches!(format, ExportFormat::Sqlite) {
+                let out = out.ok_or_else(|| {
+                    anyhow::anyhow!("export --format sqlite requires --out <file.db>")
+                })?;
+                let stats = export::cmd_export_sqlite(&mm, &out, follow)?;
+                if matches!(output, OutputFormat::Json) {
+                    let obj = serde_json::json!({"command": "export", "format": "sqlite", "out": out.to_string_lossy(), "stats": stats});
+                    println!("{}", serde_json::to_string_pretty(&obj)?);
+                }
+                eprintln!(
+                    "Exported {} nodes, {} edges from {} file{} to {}",
+                    stats.nodes,
+                    stats.edges,
+                    stats.files,
+                    if stats.files == 1 { "" } else { "s" },
+                    out.display()
+                );
+                return Ok(());
+            }
+            if follow {
+                return Err(anyhow::anyhow!(
+                    "export --follow is only supported with --format sqlite"
+                ));
+            }
 
-            for (i, op) in ops.iter().enumerate() {
-                match op {
-                    BatchOp::Add {
-                        type_prefix,
-                        title,
-                        desc,
-                    } => match cmd_add(&mut mm_clone, type_prefix, title, desc) {
-                        Ok(id) => {
-                            result.added_ids.push(id);
-                            result.applied += 1;
-                        }
-                        Err(e) => {
-                            return Err(anyhow::anyhow!("Op {}: add failed: {}", i, e));
-                        }
-                    },
-                    BatchOp::Patch {
-                        id,
-                        type_prefix,
-                        title,
-                        desc,
-                    } => {
-                        match cmd_patch(
-                            &mut mm_clone,
-                            *id,
-                            type_prefix.as_deref(),
-                            title.as_deref(),
-                            desc.as_deref(),
-                            false,
-                        ) {
-                            Ok(_) => {
-                                result.patched_ids.push(*id);
-                                result.applied += 1;
-                            }
-                            Err(e) => {
-                                return Err(anyhow::anyhow!("Op {}: patch failed: {}", i, e));
-                            }
-                        }
-                    }
-                    BatchOp::Put { id, line } => match cmd_put(&mut mm_clone, *id, line, false) {
-                        Ok(_) => {
-                            result.patched_ids.push(*id);
-                            result.applied += 1;
-                        }
-                        Err(e) => {
-                            return Err(anyhow::anyhow!("Op {}: put failed: {}", i, e));
-                        }
-                    },
-                    BatchOp::Delete { id, force } => match cmd_delete(&mut mm_clone, *id, *force) {
-                        Ok(_) => {
-                            result.deleted_ids.push(*id);
-                            result.applied += 1;
-                        }
-                        Err(e) => {
-                            return Err(anyhow::anyhow!("Op {}: delete failed: {}", i, e));
-                        }
-                    },
-                    BatchOp::Deprecate { id, to } => match cmd_deprecate(&mut mm_clone, *id, *to) {
-                        Ok(_) => {
-                            result.patched_ids.push(*id);
-                            result.applied += 1;
-                        }
-                        Err(e) => {
-                            return Err(anyhow::anyhow!("Op {}: deprecate failed: {}", i, e));
-                        }
-                    },
-                    BatchOp::Verify { id } => match cmd_verify(&mut mm_clone, *id) {
-                        Ok(_) => {
-                            result.patched_ids.push(*id);
-                            result.applied += 1;
-                        }
-                        Err(e) => {
-                            return Err(anyhow::anyhow!("Op {}: verify failed: {}", i, e));
-                        }
-                    },
+            match out {
+                Some(out) => {
+                    export::write_export(&mm, &format, &out)?;
+                    eprintln!("Exported {} nodes to {}", mm.nodes.len(), out.display());
                 }
+                None => print!("{}", export::render(&mm, &format)?),
+            }
+        }
+        Commands::Import {
+            input,
+            format,
+            r#type,
+            ids,
+            dedupe,
+            dry_run,
+        } => {
+            if path.as_os_str() == "-" {
+                return Err(cannot_write_err("import"));
             }
 
-            // Apply auto-fixes if requested
-            if fix {
-                match mm_clone.apply_fixes() {
-                    Ok(report) => {
-                        if !report.spacing.is_empty() {
-                            result.warnings.push(format!(
-                                "Auto-fixed: inserted {} spacing lines",
-                                report.spacing.len()
-                            ));
-                        }
-                        for tf in &report.title_fixes {
-                            result.warnings.push(format!(
-                                "Auto-fixed title for node {}: '{}' -> '{}'",
-                                tf.id, tf.old, tf.new
-                            ));
-                        }
-                    }
-                    Err(e) => {
-                        return Err(anyhow::anyhow!("Failed to apply fixes: {}", e));
+            let base_content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read base file {}", path.display()))?;
+            let base_hash = blake3_hash(base_content.as_bytes());
+
+            // `--from other/MINDMAP.md` is shorthand for `--from mindmap other/MINDMAP.md`
+            let (format, input) = match format {
+                ImportSource::Format(f) => (f, input),
+                ImportSource::Mindmap(p) => {
+                    if input.is_some() {
+                        return Err(anyhow::anyhow!(
+                            "import --from <file.md> does not take an INPUT argument"
+                        ));
                     }
+                    (ImportFormat::Mindmap, Some(p))
                 }
+            };
+            if r#type.is_some() && !matches!(format, ImportFormat::Outline) {
+                return Err(anyhow::anyhow!(
+                    "import --type is only used with --from outline"
+                ));
+            }
+            if (!ids.is_empty() || dedupe) && !matches!(format, ImportFormat::Mindmap) {
+                return Err(anyhow::anyhow!(
+                    "import --ids/--dedupe are only used when merging a MINDMAP file"
+                ));
             }
 
-            // Run lint and collect warnings (non-blocking)
-            match cmd_lint(&mm_clone) {
-                Ok(warnings) => {
-                    result.warnings.extend(warnings);
+            let mut mm_clone = Mindmap::from_string(base_content, path.clone())?;
+            let mut result = match format {
+                ImportFormat::Mindmap => {
+                    let other_path = input.ok_or_else(|| {
+                        anyhow::anyhow!("import --from mindmap requires the MINDMAP file to merge")
+                    })?;
+                    let other = Mindmap::load(other_path)?;
+                    let workspace = path.parent().unwrap_or_else(|| Path::new("."));
+                    let cache = map_config.cache.cache(workspace);
+                    import::cmd_import_mindmap(
+                        &mut mm_clone,
+                        &other,
+                        &cache,
+                        &ids,
+                        dedupe,
+                        &map_config.types,
+                    )?
                 }
-                Err(e) => {
-                    return Err(anyhow::anyhow!("Lint check failed: {}", e));
+                ImportFormat::Json => {
+                    import::cmd_import_json(&mut mm_clone, &read_input(input.as_deref())?)?
                 }
-            }
+                ImportFormat::Csv => import::cmd_import_csv(
+                    &mut mm_clone,
+                    &read_input(input.as_deref())?,
+                    b',',
+                    &map_config.types,
+                )?,
+                ImportFormat::Tsv => import::cmd_import_csv(
+                    &mut mm_clone,
+                    &read_input(input.as_deref())?,
+                    b'\t',
+                    &map_config.types,
+                )?,
+                ImportFormat::Outline => {
+                    let buf = read_input(input.as_deref())?;
+                    let tp = r#type.as_deref().ok_or_else(|| {
+                        anyhow::anyhow!("import --from outline requires --type (e.g., DOC)")
+                    })?;
+                    import::cmd_import_outline(&mut mm_clone, &buf, tp, &map_config.types)?
+                }
+            };
 
-            if dry_run {
-                // Print what would be written
-                if matches!(cli.output, OutputFormat::Json) {
-                    let obj = serde_json::json!({
-                        "command": "batch",
-                        "dry_run": true,
-                        "result": result,
-                        "content": mm_clone.lines.join("\n") + "\n"
-                    });
-                    println!("{}", serde_json::to_string_pretty(&obj)?);
-                } else {
-                    eprintln!("--- DRY RUN: No changes written ---");
+            result
+                .warnings
+                .extend(staged_lint_warnings(&mm_clone, &map_config)?);
+            let id_map = result.id_map.clone();
+            finish_staged(
+                &mut mm_clone,
+                result,
+                "import",
+                dry_run,
+                true,
+                &base_hash,
+                &output,
+            )?;
+            // stdout carries the dry-run preview; JSON output has the mapping as `id_map`
+            if matches!(output, OutputFormat::Default) && !id_map.is_empty() {
+                eprintln!("ID mapping (old → new):");
+                for m in &id_map {
                     eprintln!(
-                        "Would apply {} operations: {} added, {} patched, {} deleted",
-                        result.applied,
-                        result.added_ids.len(),
-                        result.patched_ids.len(),
-                        result.deleted_ids.len()
+                        "  [{}] → [{}]{}",
+                        m.old,
+                        m.new,
+                        if m.merged { " (merged)" } else { "" }
                     );
-                    if !result.warnings.is_empty() {
-                        eprintln!("Warnings:");
-                        for w in &result.warnings {
-                            eprintln!("  {}", w);
-                        }
-                    }
-                    println!("{}", mm_clone.lines.join("\n"));
                 }
+            }
+        }
+        Commands::Serve {
+            stdio,
+            http,
+            allow_remote,
+        } => match http {
+            Some(addr) => http::run(mm, &addr, allow_remote, map_config)?,
+            None if stdio => mcp::run_stdio(mm, map_config)?,
+            None => {
+                return Err(anyhow::anyhow!(
+                    "serve requires a transport: --stdio or --http <ADDR>"
+                ));
+            }
+        },
+        Commands::Tui => {
+            if path.as_os_str() == "-" {
+                return Err(anyhow::anyhow!(
+                    "Cannot run tui: mindmap was loaded from stdin ('-'); use --file <path>"
+                ));
+            }
+            tui::run(mm, map_config)?;
+        }
+        Commands::Stale {
+            older_than,
+            types,
+            no_git,
+        } => {
+            let older_than = older_than
+                .or(map_config.stale.older_than)
+                .unwrap_or(stale::Age { days: 90 });
+            let today = stale::today();
+            let modified = if no_git || path.as_os_str() == "-" {
+                HashMap::new()
             } else {
-                // Check file hash again before writing (concurrency guard)
-                let current_content = fs::read_to_string(&path).with_context(|| {
-                    format!("Failed to re-read file before commit {}", path.display())
-                })?;
-                let current_hash = blake3_hash(current_content.as_bytes());
-
-                if current_hash != base_hash {
-                    return Err(anyhow::anyhow!(
-                        "Cannot commit batch: target file changed since batch began (hash mismatch).\n\
-                         Base hash: {}\n\
-                         Current hash: {}\n\
-                         The file was likely modified by another process. \
-                         Re-run begin your batch on the current file.",
-                        base_hash,
-                        current_hash
-                    ));
+                history::last_modified(&path, today).unwrap_or_else(|e| {
+                    eprintln!("Note: no git history ({}); using verify tags only", e);
+                    HashMap::new()
+                })
+            };
+            let items = stale::report(&mm, older_than, today, &modified, &types);
+            if matches!(output, OutputFormat::Json) {
+                let obj = serde_json::json!({"command": "stale", "older_than_days": older_than.days, "count": items.len(), "items": items});
+                println!("{}", serde_json::to_string_pretty(&obj)?);
+            } else {
+                if items.is_empty() {
+                    eprintln!("✓ No nodes older than {} days", older_than.days);
+                } else {
+                    eprintln!(
+                        "Stale nodes ({} result{}, older than {} days):",
+                        items.len(),
+                        if items.len() == 1 { "" } else { "s" },
+                        older_than.days
+                    );
+                }
+                for line in stale::format_report(&items) {
+                    println!("{}", line);
                 }
+            }
+        }
+        Commands::Dupes { threshold, limit } => {
+            let threshold = match threshold {
+                Some(t) => t,
+                None => map_config.dupes.threshold,
+            };
+            let mut pairs = dupes::find(&mm, threshold);
+            pairs.truncate(limit.unwrap_or(usize::MAX));
+            let title = |id: u32| {
+                mm.get_node(id)
+                    .map(|n| n.raw_title.clone())
+                    .unwrap_or_default()
+            };
 
-                // Persist changes atomically
-                mm_clone.save()?;
+            if matches!(output, OutputFormat::Json) {
+                let items: Vec<_> = pairs
+                    .iter()
+                    .map(|c| {
+                        serde_json::json!({
+                            "keep": {"id": c.keep, "raw_title": title(c.keep)},
+                            "duplicate": {"id": c.duplicate, "raw_title": title(c.duplicate)},
+                            "score": c.similarity.score,
+                            "title_score": c.similarity.title,
+                            "description_score": c.similarity.description,
+                        })
+                    })
+                    .collect();
+                let obj = serde_json::json!({"command": "dupes", "threshold": threshold, "count": items.len(), "items": items});
+                println!("{}", serde_json::to_string_pretty(&obj)?);
+            } else if pairs.is_empty() {
+                eprintln!("✓ No near-duplicates (similarity ≥ {:.2})", threshold);
+            } else {
+                eprintln!(
+                    "Merge candidates ({} pair{}, similarity ≥ {:.2}):",
+                    pairs.len(),
+                    if pairs.len() == 1 { "" } else { "s" },
+                    threshold
+                );
+                for c in &pairs {
+                    println!(
+                        "{:.2}  [{}] **{}** ≈ [{}] **{}**",
+                        c.similarity.score,
+                        c.keep,
+                        title(c.keep),
+                        c.duplicate,
+                        title(c.duplicate)
+                    );
+                }
+                eprintln!(
+                    "Tip: merge with `mindmap-cli deprecate <duplicate> --to <keep>` after moving any unique details"
+                );
+            }
+        }
+        Commands::ScanCode { dir, uncited } => {
+            // paths relative to the mindmap's directory, as in `show --code`
+            let base = path
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or_else(|| Path::new("."));
+            let scan = citations::scan(&dir, base, map_config.cache.max_file_size)?;
+            let problems = citations::check(&mm, &scan.citations);
+            let uncited_nodes = if uncited {
+                citations::uncited(&mm, &scan.citations, "AE")
+            } else {
+                Vec::new()
+            };
+            let cited: std::collections::BTreeSet<u32> =
+                scan.citations.iter().map(|c| c.id).collect();
 
-                if matches!(cli.output, OutputFormat::Json) {
-                    let obj = serde_json::json!({
-                        "command": "batch",
-                        "dry_run": false,
-                        "result": result
-                    });
-                    println!("{}", serde_json::to_string_pretty(&obj)?);
-                } else {
-                    eprintln!("Batch applied successfully: {} ops applied", result.applied);
-                    if !result.added_ids.is_empty() {
-                        eprintln!("  Added nodes: {:?}", result.added_ids);
-                    }
-                    if !result.patched_ids.is_empty() {
-                        eprintln!("  Patched nodes: {:?}", result.patched_ids);
-                    }
-                    if !result.deleted_ids.is_empty() {
-                        eprintln!("  Deleted nodes: {:?}", result.deleted_ids);
-                    }
-                    if !result.warnings.is_empty() {
-                        eprintln!("Warnings:");
-                        for w in &result.warnings {
-                            eprintln!("  {}", w);
+            if matches!(output, OutputFormat::Json) {
+                let items: Vec<_> = problems
+                    .iter()
+                    .map(|(c, p)| serde_json::json!({"citation": c, "problem": p}))
+                    .collect();
+                let mut obj = serde_json::json!({
+                    "command": "scan-code",
+                    "dir": dir.to_string_lossy(),
+                    "files_scanned": scan.files_scanned,
+                    "citations": scan.citations,
+                    "problems": items,
+                });
+                if uncited {
+                    obj["uncited"] = serde_json::json!(
+                        uncited_nodes
+                            .iter()
+                            .map(|n| serde_json::json!({"id": n.id, "raw_title": n.raw_title}))
+                            .collect::<Vec<_>>()
+                    );
+                }
+                println!("{}", serde_json::to_string_pretty(&obj)?);
+            } else {
+                eprintln!(
+                    "Scanned {} file{}: {} citation{} of {} node{}",
+                    scan.files_scanned,
+                    if scan.files_scanned == 1 { "" } else { "s" },
+                    scan.citations.len(),
+                    if scan.citations.len() == 1 { "" } else { "s" },
+                    cited.len(),
+                    if cited.len() == 1 { "" } else { "s" }
+                );
+                for (c, problem) in &problems {
+                    let what = match problem {
+                        citations::Problem::Missing => "missing node".to_string(),
+                        citations::Problem::Deprecated {
+                            successor: Some(to),
+                        } => format!("deprecated node (use MINDMAP[{}])", to),
+                        citations::Problem::Deprecated { successor: None } => {
+                            "deprecated node".to_string()
+                        }
+                    };
+                    println!(
+                        "{}: MINDMAP[{}] cites {}",
+                        citations::location(c),
+                        c.id,
+                        what
+                    );
+                }
+                if uncited {
+                    if uncited_nodes.is_empty() {
+                        eprintln!("✓ Every AE node is cited in code");
+                    } else {
+                        eprintln!("AE nodes not cited in code ({}):", uncited_nodes.len());
+                        for n in &uncited_nodes {
+                            println!("[{}] **{}**", n.id, n.raw_title);
                         }
                     }
                 }
             }
+            if !problems.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "Found {} citation{} of missing or deprecated nodes in {}",
+                    problems.len(),
+                    if problems.len() == 1 { "" } else { "s" },
+                    dir.display()
+                ));
+            }
+        }
+        Commands::History { id } => {
+            if path.as_os_str() == "-" {
+                return Err(anyhow::anyhow!(
+                    "Cannot read history: mindmap was loaded from stdin ('-'); use --file <path>"
+                ));
+            }
+            let entries = history::node_history(&path, id)?;
+            if matches!(output, OutputFormat::Json) {
+                let obj = serde_json::json!({"command": "history", "id": id, "history": entries});
+                println!("{}", serde_json::to_string_pretty(&obj)?);
+            } else {
+                for line in history::format_history(id, &entries) {
+                    println!("{}", line);
+                }
+            }
+        }
+        Commands::Watch {
+            follow,
+            exports,
+            interval,
+        } => {
+            watch::run(
+                path,
+                follow,
+                exports,
+                std::time::Duration::from_millis(interval.max(50)),
+                matches!(output, OutputFormat::Json),
+            )?;
+        }
+        Commands::Shell => {
+            if path.as_os_str() == "-" {
+                return Err(anyhow::anyhow!(
+                    "Cannot run shell: mindmap was loaded from stdin ('-'); use --file <path>"
+                ));
+            }
+            shell::run(mm, map_config)?;
+        }
+        Commands::Lsp
+        | Commands::MergeDriver { .. }
+        | Commands::Diff { .. }
+        | Commands::Hooks { .. } => {
+            unreachable!("dispatched before loading the mindmap")
         }
     }
 
     Ok(())
 }
 
+/// What `lint --fix --missing-refs` does with a reference to a missing node
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum MissingRefFix {
+    /// Delete the reference
+    Remove,
+    /// Replace it with an HTML comment (`<!-- missing ref N -->`)
+    Comment,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FixOptions {
+    /// None leaves references to missing nodes alone
+    pub missing_refs: Option<MissingRefFix>,
+}
+
 #[derive(Debug, Clone, serde::Serialize, Default)]
 pub struct FixReport {
     pub spacing: Vec<usize>,
     pub title_fixes: Vec<TitleFix>,
+    pub line_fixes: Vec<LineFix>,
+}
+
+/// A rewritten line (1-based `line`, before spacing is normalized)
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LineFix {
+    pub line: usize,
+    /// trailing-whitespace, malformed-node, deprecated-marker, duplicate-id or missing-ref
+    pub kind: &'static str,
+    /// What was done, without repeating the kind
+    pub description: String,
+    pub old: String,
+    pub new: String,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -3116,7 +4456,32 @@ pub struct TitleFix {
 
 impl FixReport {
     pub fn any_changes(&self) -> bool {
-        !self.spacing.is_empty() || !self.title_fixes.is_empty()
+        !self.spacing.is_empty() || !self.title_fixes.is_empty() || !self.line_fixes.is_empty()
+    }
+
+    /// One human-readable line per fix; `verb` is "Fixed" or "Would fix"
+    pub fn describe(&self, verb: &str) -> Vec<String> {
+        let mut out = Vec::new();
+        for lf in &self.line_fixes {
+            out.push(format!(
+                "{} line {} ({}: {}): '{}' -> '{}'",
+                verb, lf.line, lf.kind, lf.description, lf.old, lf.new
+            ));
+        }
+        if !self.spacing.is_empty() {
+            out.push(format!(
+                "{} spacing: inserted {} blank lines",
+                verb,
+                self.spacing.len()
+            ));
+        }
+        for tf in &self.title_fixes {
+            out.push(format!(
+                "{} title for node {}: '{}' -> '{}'",
+                verb, tf.id, tf.old, tf.new
+            ));
+        }
+        out
     }
 }
 
@@ -3179,14 +4544,14 @@ mod tests {
         file.write_str("[bad] not a node\n[1] **AE: A** - base\n[1] **AE: Adup** - dup\n[2] **AE: Orphan** - lonely\n")?;
 
         let mm = Mindmap::load(file.path().to_path_buf())?;
-        let warnings = cmd_lint(&mm)?;
-        // Expect at least syntax and duplicate warnings from lint
-        let joined = warnings.join("\n");
+        let findings = cmd_lint(&mm, &config::Config::default())?;
+        // Expect at least syntax and duplicate findings from lint
+        let joined: String = findings.iter().map(|f| f.describe() + "\n").collect();
         assert!(joined.contains("Syntax"));
         assert!(joined.contains("Duplicate ID"));
 
         // Orphan detection is now a separate command; verify orphans via cmd_orphans()
-        let orphans = cmd_orphans(&mm, false)?;
+        let orphans = cmd_orphans(&mm, false, &config::Config::default())?;
         let joined_o = orphans.join("\n");
         // expect node id 2 to be reported as orphan
         assert!(joined_o.contains("2"));
@@ -3203,12 +4568,20 @@ mod tests {
 
         let mut mm = Mindmap::load(file.path().to_path_buf())?;
         // patch title only for node 1
-        cmd_patch(&mut mm, 1, Some("AE"), Some("OneNew"), None, false)?;
+        cmd_patch(
+            &mut mm,
+            1,
+            Some("AE"),
+            Some("OneNew"),
+            None,
+            false,
+            &config::TypesConfig::default(),
+        )?;
         assert_eq!(mm.get_node(1).unwrap().raw_title, "AE: OneNew");
 
         // put full line for node 2
         let new_line = "[2] **DR: Replaced** - replaced desc [1]";
-        cmd_put(&mut mm, 2, new_line, false)?;
+        cmd_put(&mut mm, 2, new_line, false, &config::TypesConfig::default())?;
         assert_eq!(mm.get_node(2).unwrap().raw_title, "DR: Replaced");
         assert_eq!(
             mm.get_node(2).unwrap().references,
@@ -3295,7 +4668,13 @@ mod tests {
         let file = temp.child("MINDMAP.md");
         file.write_str("[1] **AE: One** - first\n")?;
         let mut mm = Mindmap::load(file.path().to_path_buf())?;
-        let id = cmd_add(&mut mm, "AE", "Two", "second")?;
+        let id = cmd_add(
+            &mut mm,
+            "AE",
+            "Two",
+            "second",
+            &config::TypesConfig::default(),
+        )?;
         assert_eq!(id, 2);
         assert_eq!(mm.nodes.len(), 2);
         let node = mm.get_node(2).unwrap();
@@ -3326,6 +4705,16 @@ mod tests {
         cmd_verify(&mut mm, 1)?;
         let node = mm.get_node(1).unwrap();
         assert!(node.description.contains("(verify"));
+
+        // an existing tag is refreshed rather than left alone or duplicated
+        mm.lines[0] = "[1] **AE: One** - first (verify 2020-01-01) rest".to_string();
+        mm.nodes[0].description = "first (verify 2020-01-01) rest".to_string();
+        cmd_verify(&mut mm, 1)?;
+        let today = stale::today().format("%Y-%m-%d").to_string();
+        assert_eq!(
+            mm.lines[0],
+            format!("[1] **AE: One** - first (verify {}) rest", today)
+        );
         temp.close()?;
         Ok(())
     }
@@ -3372,7 +4761,14 @@ mod tests {
         let file = temp.child("MINDMAP.md");
         file.write_str("[1] **AE: One** - first\n")?;
         let mut mm = Mindmap::load(file.path().to_path_buf())?;
-        let err = cmd_put(&mut mm, 99, "[99] **AE: New** - new", false).unwrap_err();
+        let err = cmd_put(
+            &mut mm,
+            99,
+            "[99] **AE: New** - new",
+            false,
+            &config::TypesConfig::default(),
+        )
+        .unwrap_err();
         assert!(format!("{}", err).contains("Node [99] not found"));
         temp.close()?;
         Ok(())
@@ -3384,7 +4780,16 @@ mod tests {
         let file = temp.child("MINDMAP.md");
         file.write_str("[1] **AE: One** - first\n")?;
         let mut mm = Mindmap::load(file.path().to_path_buf())?;
-        let err = cmd_patch(&mut mm, 99, None, Some("New"), None, false).unwrap_err();
+        let err = cmd_patch(
+            &mut mm,
+            99,
+            None,
+            Some("New"),
+            None,
+            false,
+            &config::TypesConfig::default(),
+        )
+        .unwrap_err();
         assert!(format!("{}", err).contains("Node [99] not found"));
         temp.close()?;
         Ok(())
@@ -3432,7 +4837,7 @@ mod tests {
         let file = temp.child("MINDMAP.md");
         file.write_str("[1] **AE: One** - first\n[2] **AE: Orphan** - lonely\n")?;
         let mm = Mindmap::load(file.path().to_path_buf())?;
-        let orphans = cmd_orphans(&mm, false)?;
+        let orphans = cmd_orphans(&mm, false, &config::Config::default())?;
         assert_eq!(orphans, vec!["1".to_string(), "2".to_string()]);
         temp.close()?;
         Ok(())
@@ -3496,6 +4901,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rewrite_refs() {
+        let out = rewrite_refs("see [1], [2](./a.md) and [3]", |r| match r {
+            Reference::Internal(1) => Some("[10]".to_string()),
+            Reference::External(id, _) => Some(format!("[{}]", id)),
+            _ => None,
+        });
+        assert_eq!(out, "see [10], [2] and [3]");
+    }
+
     #[test]
     fn test_normalize_adjacent_nodes() -> Result<()> {
         let temp = assert_fs::TempDir::new()?;
@@ -3621,6 +5036,103 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_lint_fix_malformed_lines_and_markers() -> Result<()> {
+        let mut mm = Mindmap::from_string(
+            "[1]**AE: One** - a   \n\n[2] *AE: Two* — b\n\n[3] **[deprecated->1] AE: Old** - c\n\n[4] **AE: Empty** - \n\nHard  \nbreak\n"
+                .to_string(),
+            PathBuf::from("-"),
+        )?;
+        let report = mm.apply_fixes()?;
+        let kinds: Vec<&str> = report.line_fixes.iter().map(|f| f.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "trailing-whitespace",
+                "malformed-node",
+                "malformed-node",
+                "deprecated-marker"
+            ]
+        );
+        assert_eq!(
+            mm.lines,
+            vec![
+                "[1] **AE: One** - a",
+                "",
+                "[2] **AE: Two** - b",
+                "",
+                "[3] **[DEPRECATED → 1] AE: Old** - c",
+                "",
+                "[4] **AE: Empty** - ",
+                "",
+                "Hard  ",
+                "break",
+            ]
+        );
+        assert!(!mm.apply_fixes()?.any_changes());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lint_fix_duplicate_ids_and_missing_refs() -> Result<()> {
+        let content =
+            "[1] **AE: A** - see [9] and [2]\n\n[2] **AE: B** - b\n\n[2] **AE: C** - c [8]\n";
+        let mut mm = Mindmap::from_string(content.to_string(), PathBuf::from("-"))?;
+        let report = mm.apply_fixes()?;
+        assert_eq!(report.line_fixes.len(), 1);
+        assert_eq!(
+            report.line_fixes[0].description,
+            "reassigned [2] to [3]; references to [2] in [1] now point at the first [2] and may mean [3]"
+        );
+        assert_eq!(mm.lines[4], "[3] **AE: C** - c [8]");
+        assert!(mm.lines[0].contains("[9]"));
+
+        // the reassigned node's own [2] now points at the first [2] too
+        let mut mm = Mindmap::from_string(
+            "[2] **AE: B** - b [2]\n\n[2] **AE: C** - see [2]\n".to_string(),
+            PathBuf::from("-"),
+        )?;
+        let report = mm.apply_fixes()?;
+        assert_eq!(
+            report.line_fixes[0].description,
+            "reassigned [2] to [3]; references to [2] in [3] now point at the first [2] and may mean [3]"
+        );
+
+        let mut mm = Mindmap::from_string(content.to_string(), PathBuf::from("-"))?;
+        mm.apply_fixes_with(&FixOptions {
+            missing_refs: Some(MissingRefFix::Remove),
+        })?;
+        assert_eq!(mm.lines[0], "[1] **AE: A** - see and [2]");
+        assert_eq!(mm.lines[4], "[3] **AE: C** - c");
+
+        let mut mm = Mindmap::from_string(content.to_string(), PathBuf::from("-"))?;
+        let report = mm.apply_fixes_with(&FixOptions {
+            missing_refs: Some(MissingRefFix::Comment),
+        })?;
+        assert_eq!(
+            mm.lines[0],
+            "[1] **AE: A** - see <!-- missing ref 9 --> and [2]"
+        );
+        assert_eq!(report.line_fixes[1].description, "commented out [9]");
+        assert!(mm.nodes[0].references == vec![Reference::Internal(2)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_spans_keeps_other_whitespace() {
+        let s = "Keep  this, drop [8] [9] here [7]. End [6]";
+        let spans: Vec<std::ops::Range<usize>> = ref_spans(s).into_iter().map(|(r, _)| r).collect();
+        assert_eq!(remove_spans(s, &spans), "Keep  this, drop here. End");
+        let s = "[9]  lead  and trail  ";
+        assert_eq!(
+            remove_spans(
+                s,
+                &ref_spans(s).into_iter().map(|(r, _)| r).collect::<Vec<_>>()
+            ),
+            "lead  and trail  "
+        );
+    }
+
     #[test]
     fn test_lint_fix_collapse_multiple_blanks() -> Result<()> {
         let temp = assert_fs::TempDir::new()?;
@@ -3727,7 +5239,13 @@ mod tests {
                     title,
                     desc,
                 } => {
-                    cmd_add(&mut mm, &type_prefix, &title, &desc)?;
+                    cmd_add(
+                        &mut mm,
+                        &type_prefix,
+                        &title,
+                        &desc,
+                        &config::TypesConfig::default(),
+                    )?;
                 }
                 _ => {}
             }
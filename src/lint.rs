@@ -0,0 +1,996 @@
+//! Lint: rule engine for mindmap checks
+//!
+//! This module provides:
+//! - `Rule`: one check with a stable ID, a default severity and a description
+//! - `Finding`: what a rule reports, with a line/column span and an optional fix
+//! - `lint`: run every rule, applying the levels from `[lint.rules]` (a rule set to
+//!   `off` is skipped, others have their severity overridden)
+//! - `sarif` / `github`: findings as a SARIF 2.1.0 log or GitHub Actions annotations
+
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use std::{cell::OnceCell, collections::HashMap, fmt, path::PathBuf};
+
+use crate::{
+    Mindmap, Node, Reference,
+    anchors::{self, Anchor, AnchorStatus},
+    config::{Config, LintConfig, RuleLevel},
+    parse_node_line, ref_spans, repair_node_line, split_title, stale,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warn,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// Location of a finding: 1-based line, byte columns `start..end` within that line
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A suggested edit: replace the 1-based `line` with `replacement` (None deletes it)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Fix {
+    pub description: String,
+    pub line: usize,
+    pub replacement: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<Fix>,
+}
+
+impl Finding {
+    /// A finding for `rule`; the engine sets the severity
+    pub fn new(rule: &'static str, message: impl Into<String>) -> Self {
+        Finding {
+            rule,
+            severity: Severity::Warn,
+            message: message.into(),
+            span: None,
+            fix: None,
+        }
+    }
+
+    /// Span `range` (byte offsets) on the 0-based line `line_index`
+    pub fn at(mut self, line_index: usize, range: std::ops::Range<usize>) -> Self {
+        self.span = Some(Span {
+            line: line_index + 1,
+            start: range.start,
+            end: range.end,
+        });
+        self
+    }
+
+    /// Span the whole 0-based line `line_index`
+    pub fn at_line(self, mm: &Mindmap, line_index: usize) -> Self {
+        let len = mm.lines.get(line_index).map(String::len).unwrap_or(0);
+        self.at(line_index, 0..len)
+    }
+
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+
+    /// 1-based line, if the finding has a span
+    pub fn line(&self) -> Option<usize> {
+        self.span.as_ref().map(|s| s.line)
+    }
+
+    /// `line:col: severity[rule] message` (1-based column; no location without a span)
+    pub fn describe(&self) -> String {
+        let head = format!("{}[{}] {}", self.severity, self.rule, self.message);
+        match &self.span {
+            Some(s) => format!("{}:{}: {}", s.line, s.start + 1, head),
+            None => head,
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// How an external reference resolved
+pub enum ExternalStatus {
+    /// The path could not be resolved (missing, or outside the workspace)
+    Missing,
+    Unreadable(String),
+    Loaded {
+        canonical: PathBuf,
+        has_node: bool,
+    },
+}
+
+/// One `[N](path)` reference and where it points
+pub struct ExternalRef<'a> {
+    pub node: &'a Node,
+    pub id: u32,
+    pub path: String,
+    /// Byte range of the reference in the node's line
+    pub range: std::ops::Range<usize>,
+    pub status: ExternalStatus,
+}
+
+/// One file anchor (`src/cache.rs:120`, `[DESIGN](./DESIGN.md#goals)`) and how it checked out
+pub struct AnchorRef<'a> {
+    pub node: &'a Node,
+    pub anchor: Anchor,
+    /// Byte range of the anchor in the node's line
+    pub range: std::ops::Range<usize>,
+    pub status: AnchorStatus,
+}
+
+/// What rules see: the mindmap plus lazily computed shared data
+pub struct LintContext<'a> {
+    pub mm: &'a Mindmap,
+    pub config: &'a Config,
+    externals: OnceCell<Vec<ExternalRef<'a>>>,
+    anchors: OnceCell<Vec<AnchorRef<'a>>>,
+}
+
+/// Byte offset of a node's description within its line
+pub fn description_offset(mm: &Mindmap, node: &Node) -> usize {
+    let line = &mm.lines[node.line_index];
+    line.len().saturating_sub(node.description.len())
+}
+
+impl<'a> LintContext<'a> {
+    pub fn new(mm: &'a Mindmap, config: &'a Config) -> Self {
+        LintContext {
+            mm,
+            config,
+            externals: OnceCell::new(),
+            anchors: OnceCell::new(),
+        }
+    }
+
+    fn workspace(&self) -> &'a std::path::Path {
+        self.mm
+            .path
+            .parent()
+            .unwrap_or_else(|| std::path::Path::new("."))
+    }
+
+    /// Every file anchor in a description, checked once
+    pub fn anchors(&self) -> &[AnchorRef<'a>] {
+        self.anchors.get_or_init(|| {
+            let mm = self.mm;
+            let cache = self.config.cache.cache(self.workspace());
+            let mut out = Vec::new();
+            for node in &mm.nodes {
+                let offset = description_offset(mm, node);
+                for anchor in anchors::extract(&node.description) {
+                    let status =
+                        anchors::check(&cache, &mm.path, &anchor, self.config.cache.max_file_size);
+                    out.push(AnchorRef {
+                        node,
+                        range: anchor.range.start + offset..anchor.range.end + offset,
+                        anchor,
+                        status,
+                    });
+                }
+            }
+            out
+        })
+    }
+
+    /// Every external reference, resolved once (same path rules as `--follow`)
+    pub fn externals(&self) -> &[ExternalRef<'a>] {
+        self.externals.get_or_init(|| {
+            let mm = self.mm;
+            let mut cache = self.config.cache.cache(self.workspace());
+            let mut out = Vec::new();
+            for node in &mm.nodes {
+                let offset = description_offset(mm, node);
+                for (range, r) in ref_spans(&node.description) {
+                    let Reference::External(id, path) = r else {
+                        continue;
+                    };
+                    let status = match cache.resolve_path(&mm.path, &path) {
+                        Err(_) => ExternalStatus::Missing,
+                        Ok(canonical) => {
+                            match cache.load(&mm.path, &path, &std::collections::HashSet::new()) {
+                                Ok(ext) => ExternalStatus::Loaded {
+                                    has_node: ext.by_id.contains_key(&id),
+                                    canonical,
+                                },
+                                Err(e) => ExternalStatus::Unreadable(e.to_string()),
+                            }
+                        }
+                    };
+                    out.push(ExternalRef {
+                        node,
+                        id,
+                        path,
+                        range: range.start + offset..range.end + offset,
+                        status,
+                    });
+                }
+            }
+            out
+        })
+    }
+}
+
+pub trait Rule {
+    /// Stable ID used in output and in `[lint.rules]`
+    fn id(&self) -> &'static str;
+    fn default_severity(&self) -> Severity;
+    fn description(&self) -> &'static str;
+    fn check(&self, ctx: &LintContext, out: &mut Vec<Finding>);
+}
+
+struct Syntax;
+
+impl Rule for Syntax {
+    fn id(&self) -> &'static str {
+        "syntax"
+    }
+    fn default_severity(&self) -> Severity {
+        Severity::Warn
+    }
+    fn description(&self) -> &'static str {
+        "Line starts with '[' but is not a valid node line"
+    }
+    fn check(&self, ctx: &LintContext, out: &mut Vec<Finding>) {
+        for (i, line) in ctx.mm.lines.iter().enumerate() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with('[') && parse_node_line(trimmed, i).is_err() {
+                let mut finding = Finding::new(
+                    self.id(),
+                    format!(
+                        "Syntax: line {} starts with '[' but does not match node format",
+                        i + 1
+                    ),
+                )
+                .at_line(ctx.mm, i);
+                if let Some(repaired) = repair_node_line(line) {
+                    finding = finding.with_fix(Fix {
+                        description: "Rewrite as `[N] **Title** - description`".to_string(),
+                        line: i + 1,
+                        replacement: Some(repaired),
+                    });
+                }
+                out.push(finding);
+            }
+        }
+    }
+}
+
+struct DuplicateId;
+
+impl Rule for DuplicateId {
+    fn id(&self) -> &'static str {
+        "duplicate-id"
+    }
+    fn default_severity(&self) -> Severity {
+        Severity::Warn
+    }
+    fn description(&self) -> &'static str {
+        "The same node ID is used on more than one line"
+    }
+    fn check(&self, ctx: &LintContext, out: &mut Vec<Finding>) {
+        let mut lines_by_id: HashMap<u32, Vec<usize>> = HashMap::new();
+        for n in &ctx.mm.nodes {
+            lines_by_id.entry(n.id).or_default().push(n.line_index + 1);
+        }
+        let mut ids: Vec<_> = lines_by_id
+            .into_iter()
+            .filter(|(_, lines)| lines.len() > 1)
+            .collect();
+        ids.sort();
+        for (id, lines) in ids {
+            let id_len = id.to_string().len();
+            out.push(
+                Finding::new(
+                    self.id(),
+                    format!("Duplicate ID: node {} appears on lines {:?}", id, lines),
+                )
+                .at(lines[1] - 1, 0..id_len + 2),
+            );
+        }
+    }
+}
+
+struct MissingRef;
+
+impl Rule for MissingRef {
+    fn id(&self) -> &'static str {
+        "missing-ref"
+    }
+    fn default_severity(&self) -> Severity {
+        Severity::Warn
+    }
+    fn description(&self) -> &'static str {
+        "A [N] reference points at a node that does not exist"
+    }
+    fn check(&self, ctx: &LintContext, out: &mut Vec<Finding>) {
+        let mm = ctx.mm;
+        for n in &mm.nodes {
+            let offset = description_offset(mm, n);
+            for (range, r) in ref_spans(&n.description) {
+                if let Reference::Internal(rid) = r
+                    && !mm.by_id.contains_key(&rid)
+                {
+                    out.push(
+                        Finding::new(
+                            self.id(),
+                            format!("Missing ref: node {} references missing node {}", n.id, rid),
+                        )
+                        .at(n.line_index, range.start + offset..range.end + offset),
+                    );
+                }
+            }
+        }
+    }
+}
+
+struct MissingFile;
+
+impl Rule for MissingFile {
+    fn id(&self) -> &'static str {
+        "missing-file"
+    }
+    fn default_severity(&self) -> Severity {
+        Severity::Warn
+    }
+    fn description(&self) -> &'static str {
+        "A [N](path) reference points at a file that does not exist (or is outside the workspace)"
+    }
+    fn check(&self, ctx: &LintContext, out: &mut Vec<Finding>) {
+        for e in ctx.externals() {
+            if let ExternalStatus::Missing = e.status {
+                out.push(
+                    Finding::new(
+                        self.id(),
+                        format!(
+                            "Missing file: node [{}] references missing file {}",
+                            e.node.id, e.path
+                        ),
+                    )
+                    .at(e.node.line_index, e.range.clone()),
+                );
+            }
+        }
+    }
+}
+
+struct UnreadableFile;
+
+impl Rule for UnreadableFile {
+    fn id(&self) -> &'static str {
+        "unreadable-file"
+    }
+    fn default_severity(&self) -> Severity {
+        Severity::Warn
+    }
+    fn description(&self) -> &'static str {
+        "A [N](path) reference points at a file that cannot be read as a mindmap"
+    }
+    fn check(&self, ctx: &LintContext, out: &mut Vec<Finding>) {
+        for e in ctx.externals() {
+            if let ExternalStatus::Unreadable(err) = &e.status {
+                out.push(
+                    Finding::new(
+                        self.id(),
+                        format!(
+                            "Unreadable file: node [{}] cannot read {}: {}",
+                            e.node.id, e.path, err
+                        ),
+                    )
+                    .at(e.node.line_index, e.range.clone()),
+                );
+            }
+        }
+    }
+}
+
+struct ExternalNode;
+
+impl Rule for ExternalNode {
+    fn id(&self) -> &'static str {
+        "external-node"
+    }
+    fn default_severity(&self) -> Severity {
+        Severity::Warn
+    }
+    fn description(&self) -> &'static str {
+        "A [N](path) reference points at a node that does not exist in that file"
+    }
+    fn check(&self, ctx: &LintContext, out: &mut Vec<Finding>) {
+        for e in ctx.externals() {
+            if let ExternalStatus::Loaded {
+                canonical,
+                has_node: false,
+            } = &e.status
+            {
+                out.push(
+                    Finding::new(
+                        self.id(),
+                        format!(
+                            "Invalid node: node [{}] references non-existent [{}] in {}",
+                            e.node.id,
+                            e.id,
+                            canonical.display()
+                        ),
+                    )
+                    .at(e.node.line_index, e.range.clone()),
+                );
+            }
+        }
+    }
+}
+
+struct DuplicateTitlePrefix;
+
+impl Rule for DuplicateTitlePrefix {
+    fn id(&self) -> &'static str {
+        "duplicate-title-prefix"
+    }
+    fn default_severity(&self) -> Severity {
+        Severity::Warn
+    }
+    fn description(&self) -> &'static str {
+        "The type prefix is repeated in the title (\"AE: AE: X\"); fixed by lint --fix"
+    }
+    fn check(&self, ctx: &LintContext, out: &mut Vec<Finding>) {
+        for n in &ctx.mm.nodes {
+            let Some((leading, rest)) = n.raw_title.split_once(':') else {
+                continue;
+            };
+            let leading = leading.trim();
+            let rest = rest.trim_start();
+            let Some(after) = rest.strip_prefix(&format!("{}:", leading)) else {
+                continue;
+            };
+            let after = after.trim_start();
+            let fixed = if after.is_empty() {
+                leading.to_string()
+            } else {
+                format!("{}: {}", leading, after)
+            };
+            let title_start = title_start(&ctx.mm.lines[n.line_index]);
+            out.push(
+                Finding::new(
+                    self.id(),
+                    format!(
+                        "Duplicate type prefix: node {} title '{}' repeats '{}:'",
+                        n.id, n.raw_title, leading
+                    ),
+                )
+                .at(n.line_index, title_start..title_start + n.raw_title.len())
+                .with_fix(Fix {
+                    description: format!("Rename title to '{}'", fixed),
+                    line: n.line_index + 1,
+                    replacement: Some(format!("[{}] **{}** - {}", n.id, fixed, n.description)),
+                }),
+            );
+        }
+    }
+}
+
+/// Byte offset of the title inside a node line (`[N] **` precedes it)
+fn title_start(line: &str) -> usize {
+    line.find("**").map(|i| i + 2).unwrap_or(0)
+}
+
+struct UnknownType;
+
+impl Rule for UnknownType {
+    fn id(&self) -> &'static str {
+        "unknown-type"
+    }
+    fn default_severity(&self) -> Severity {
+        Severity::Warn
+    }
+    fn description(&self) -> &'static str {
+        "Type prefix is not declared in `[types]`"
+    }
+    fn check(&self, ctx: &LintContext, out: &mut Vec<Finding>) {
+        let types = &ctx.config.types;
+        for node in &ctx.mm.nodes {
+            let (Some(typ), _) = split_title(&node.raw_title) else {
+                continue;
+            };
+            if types.is_allowed(typ) {
+                continue;
+            }
+            let line = &ctx.mm.lines[node.line_index];
+            let start = title_start(line);
+            let mut finding = Finding::new(
+                self.id(),
+                format!(
+                    "Unknown type: node {} has type '{}' (allowed: {})",
+                    node.id,
+                    typ,
+                    types.names().join(", ")
+                ),
+            )
+            .at(node.line_index, start..start + typ.len());
+            // a different case or an alias of a declared type
+            if let Some(canonical) = types.canonical(typ) {
+                let mut replacement = line.clone();
+                replacement.replace_range(start..start + typ.len(), canonical);
+                finding = finding.with_fix(Fix {
+                    description: format!("Rename type '{}' to '{}'", typ, canonical),
+                    line: node.line_index + 1,
+                    replacement: Some(replacement),
+                });
+            }
+            out.push(finding);
+        }
+    }
+}
+
+struct TypeConvention;
+
+impl Rule for TypeConvention {
+    fn id(&self) -> &'static str {
+        "type-convention"
+    }
+    fn default_severity(&self) -> Severity {
+        Severity::Warn
+    }
+    fn description(&self) -> &'static str {
+        "Node breaks `must_reference` / `must_contain` of its `[types.<PREFIX>]`"
+    }
+    fn check(&self, ctx: &LintContext, out: &mut Vec<Finding>) {
+        let mm = ctx.mm;
+        for node in &mm.nodes {
+            let (Some(typ), _) = split_title(&node.raw_title) else {
+                continue;
+            };
+            let ref_types: Vec<&str> = node
+                .references
+                .iter()
+                .filter_map(|r| match r {
+                    Reference::Internal(id) => mm.get_node(*id),
+                    Reference::External(..) => None,
+                })
+                .filter_map(|n| split_title(&n.raw_title).0)
+                .collect();
+            for problem in ctx
+                .config
+                .types
+                .violations(typ, &node.description, &ref_types)
+            {
+                out.push(
+                    Finding::new(
+                        self.id(),
+                        format!("Type convention: node {}: {}", node.id, problem),
+                    )
+                    .at_line(mm, node.line_index),
+                );
+            }
+        }
+    }
+}
+
+struct BrokenAnchor;
+
+impl Rule for BrokenAnchor {
+    fn id(&self) -> &'static str {
+        "broken-anchor"
+    }
+    fn default_severity(&self) -> Severity {
+        // paths are picked out of free text heuristically
+        Severity::Warn
+    }
+    fn description(&self) -> &'static str {
+        "A file path, path:line anchor or markdown link points at a missing file, a file outside the workspace or a line past the end"
+    }
+    fn check(&self, ctx: &LintContext, out: &mut Vec<Finding>) {
+        for a in ctx.anchors() {
+            let problem = match &a.status {
+                AnchorStatus::Missing => "which does not exist".to_string(),
+                AnchorStatus::OutsideWorkspace => "which is outside the workspace".to_string(),
+                AnchorStatus::PastEnd(total) => format!("but the file has only {} lines", total),
+                AnchorStatus::Ok | AnchorStatus::MissingSymbol => continue,
+            };
+            out.push(
+                Finding::new(
+                    self.id(),
+                    format!(
+                        "Broken anchor: node {} references {}, {}",
+                        a.node.id,
+                        &ctx.mm.lines[a.node.line_index][a.range.clone()],
+                        problem
+                    ),
+                )
+                .at(a.node.line_index, a.range.clone()),
+            );
+        }
+    }
+}
+
+struct MissingSymbol;
+
+impl Rule for MissingSymbol {
+    fn id(&self) -> &'static str {
+        "missing-symbol"
+    }
+    fn default_severity(&self) -> Severity {
+        Severity::Warn
+    }
+    fn description(&self) -> &'static str {
+        "A path#symbol anchor names a symbol (or markdown heading) no longer found in the file"
+    }
+    fn check(&self, ctx: &LintContext, out: &mut Vec<Finding>) {
+        for a in ctx.anchors() {
+            if a.status == AnchorStatus::MissingSymbol {
+                out.push(
+                    Finding::new(
+                        self.id(),
+                        format!(
+                            "Missing symbol: node {} references '{}', which no longer occurs in {}",
+                            a.node.id,
+                            a.anchor.symbol.as_deref().unwrap_or_default(),
+                            a.anchor.path
+                        ),
+                    )
+                    .at(a.node.line_index, a.range.clone()),
+                );
+            }
+        }
+    }
+}
+
+struct StaleVerification;
+
+impl Rule for StaleVerification {
+    fn id(&self) -> &'static str {
+        "stale-verification"
+    }
+    fn default_severity(&self) -> Severity {
+        Severity::Error
+    }
+    fn description(&self) -> &'static str {
+        "Node of a type in `[stale.types]` was not verified within its maximum age"
+    }
+    fn check(&self, ctx: &LintContext, out: &mut Vec<Finding>) {
+        let limits = &ctx.config.stale.types;
+        if limits.is_empty() {
+            return;
+        }
+        let today = stale::today();
+        for node in &ctx.mm.nodes {
+            let Some(max) = split_title(&node.raw_title).0.and_then(|t| limits.get(t)) else {
+                continue;
+            };
+            let finding = match stale::verify_tag(&node.description) {
+                None => Finding::new(
+                    self.id(),
+                    format!("Unverified: node {} has never been verified", node.id),
+                )
+                .at_line(ctx.mm, node.line_index),
+                Some((range, date)) if (today - date).num_days() > max.days => {
+                    let offset = description_offset(ctx.mm, node);
+                    Finding::new(
+                        self.id(),
+                        format!(
+                            "Unverified: node {} was last verified {} ({} days ago, max {})",
+                            node.id,
+                            date,
+                            (today - date).num_days(),
+                            max.days
+                        ),
+                    )
+                    .at(node.line_index, range.start + offset..range.end + offset)
+                }
+                Some(_) => continue,
+            };
+            out.push(finding);
+        }
+    }
+}
+
+/// Every built-in rule, in reporting order
+pub fn rules() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(Syntax),
+        Box::new(DuplicateId),
+        Box::new(MissingRef),
+        Box::new(MissingFile),
+        Box::new(UnreadableFile),
+        Box::new(ExternalNode),
+        Box::new(BrokenAnchor),
+        Box::new(MissingSymbol),
+        Box::new(DuplicateTitlePrefix),
+        Box::new(UnknownType),
+        Box::new(TypeConvention),
+        Box::new(StaleVerification),
+    ]
+}
+
+/// Reject `[lint.rules]` entries naming rules that do not exist
+pub fn validate_config(config: &LintConfig) -> Result<()> {
+    let known: Vec<&str> = rules().iter().map(|r| r.id()).collect();
+    for id in config.rules.keys() {
+        if !known.contains(&id.as_str()) {
+            return Err(anyhow!(
+                "Unknown lint rule '{}' in config (known: {})",
+                id,
+                known.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Run all enabled rules; findings are ordered by line (file-level ones first)
+pub fn lint(mm: &Mindmap, config: &Config) -> Vec<Finding> {
+    let ctx = LintContext::new(mm, config);
+    let mut findings = Vec::new();
+    for rule in rules() {
+        let severity = match config.lint.rules.get(rule.id()) {
+            Some(RuleLevel::Off) => continue,
+            Some(RuleLevel::Error) => Severity::Error,
+            Some(RuleLevel::Warn) => Severity::Warn,
+            Some(RuleLevel::Info) => Severity::Info,
+            None => rule.default_severity(),
+        };
+        let start = findings.len();
+        rule.check(&ctx, &mut findings);
+        for f in &mut findings[start..] {
+            f.severity = severity;
+        }
+    }
+    findings.sort_by_key(|f| f.line().unwrap_or(0));
+    findings
+}
+
+/// Number of findings at `severity`
+pub fn count(findings: &[Finding], severity: Severity) -> usize {
+    findings.iter().filter(|f| f.severity == severity).count()
+}
+
+/// 1-based line and 1-based UTF-16 start/end columns of a finding (line 1 without a span)
+fn location(mm: &Mindmap, f: &Finding) -> (usize, usize, usize) {
+    let Some(span) = &f.span else {
+        return (1, 1, 1);
+    };
+    let line = mm
+        .lines
+        .get(span.line - 1)
+        .map(String::as_str)
+        .unwrap_or("");
+    let col = |byte: usize| line[..byte.min(line.len())].encode_utf16().count() + 1;
+    (span.line, col(span.start), col(span.end))
+}
+
+/// Findings as a SARIF 2.1.0 log, for code scanning uploads
+pub fn sarif(mm: &Mindmap, findings: &[Finding]) -> serde_json::Value {
+    let rules = rules();
+    let level = |s: Severity| match s {
+        Severity::Error => "error",
+        Severity::Warn => "warning",
+        Severity::Info => "note",
+    };
+    let uri = mm.path.to_string_lossy().replace('\\', "/");
+    let results: Vec<serde_json::Value> = findings
+        .iter()
+        .map(|f| {
+            let (line, start, end) = location(mm, f);
+            serde_json::json!({
+                "ruleId": f.rule,
+                "ruleIndex": rules.iter().position(|r| r.id() == f.rule),
+                "level": level(f.severity),
+                "message": {"text": f.message},
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": {"uri": uri},
+                        "region": {"startLine": line, "startColumn": start, "endColumn": end},
+                    }
+                }],
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "mindmap-cli",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.iter().map(|r| serde_json::json!({
+                        "id": r.id(),
+                        "shortDescription": {"text": r.description()},
+                        "defaultConfiguration": {"level": level(r.default_severity())},
+                    })).collect::<Vec<_>>(),
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+/// Escape data for a GitHub Actions workflow command; `property` also escapes `:` and `,`
+fn github_escape(s: &str, property: bool) -> String {
+    let s = s
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A");
+    if property {
+        s.replace(':', "%3A").replace(',', "%2C")
+    } else {
+        s
+    }
+}
+
+/// Findings as GitHub Actions annotations (`::error file=…,line=…::message`), shown
+/// inline on pull request diffs
+pub fn github(mm: &Mindmap, findings: &[Finding]) -> Vec<String> {
+    let file = github_escape(&mm.path.to_string_lossy(), true);
+    findings
+        .iter()
+        .map(|f| {
+            let (line, start, end) = location(mm, f);
+            let command = match f.severity {
+                Severity::Error => "error",
+                Severity::Warn => "warning",
+                Severity::Info => "notice",
+            };
+            format!(
+                "::{} file={},line={},col={},endColumn={},title={}::{}",
+                command,
+                file,
+                line,
+                start,
+                end,
+                github_escape(&format!("mindmap-cli {}", f.rule), true),
+                github_escape(&f.message, false)
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn load(content: &str) -> Result<Mindmap> {
+        Mindmap::load_from_reader(std::io::Cursor::new(content), PathBuf::from("-"))
+    }
+
+    #[test]
+    fn test_rules_report_ids_severities_and_spans() -> Result<()> {
+        let mm = load("[x] bad\n[1] **AE: AE: One** - see [9]\n[1] **WF: Dup** - x\n")?;
+        let findings = lint(&mm, &Config::default());
+        let ids: Vec<&str> = findings.iter().map(|f| f.rule).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "syntax",
+                "missing-ref",
+                "duplicate-title-prefix",
+                "duplicate-id"
+            ]
+        );
+
+        let missing = &findings[1];
+        assert_eq!(missing.severity, Severity::Warn);
+        let span = missing.span.as_ref().unwrap();
+        assert_eq!(span.line, 2);
+        assert_eq!(&mm.lines[1][span.start..span.end], "[9]");
+
+        let prefix = &findings[2];
+        assert_eq!(prefix.severity, Severity::Warn);
+        assert_eq!(
+            prefix.fix.as_ref().unwrap().replacement.as_deref(),
+            Some("[1] **AE: One** - see [9]")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_overrides_and_disables() -> Result<()> {
+        let mm = load("[1] **AE: One** - see [9]\n")?;
+        let config = Config::parse("[lint.rules]\nmissing-ref = \"info\"\n")?;
+        let findings = lint(&mm, &config);
+        assert_eq!(findings[0].severity, Severity::Info);
+
+        let config = Config::parse("[lint.rules]\nmissing-ref = \"off\"\n")?;
+        assert!(lint(&mm, &config).is_empty());
+
+        let config = Config::parse("[lint.rules]\nno-such-rule = \"off\"\n")?;
+        assert!(validate_config(&config.lint).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sarif_and_github_output() -> Result<()> {
+        let mut mm = load("[1] **AE: Ünï** - see [9]\n")?;
+        mm.path = PathBuf::from("docs/MINDMAP.md");
+        let findings = lint(&mm, &Config::default());
+
+        let log = sarif(&mm, &findings);
+        let result = &log["runs"][0]["results"][0];
+        assert_eq!(result["ruleId"], "missing-ref");
+        assert_eq!(result["level"], "warning");
+        let region = &result["locations"][0]["physicalLocation"]["region"];
+        assert_eq!(region["startLine"], 1);
+        assert_eq!(region["startColumn"], 23);
+        assert_eq!(region["endColumn"], 26);
+        let rule_index = result["ruleIndex"].as_u64().unwrap() as usize;
+        assert_eq!(
+            log["runs"][0]["tool"]["driver"]["rules"][rule_index]["id"],
+            "missing-ref"
+        );
+
+        assert_eq!(
+            github(&mm, &findings),
+            vec![
+                "::warning file=docs/MINDMAP.md,line=1,col=23,endColumn=26,title=mindmap-cli missing-ref::Missing ref: node 1 references missing node 9"
+                    .to_string()
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_type_rules() -> Result<()> {
+        let mm =
+            load("[1] **ae: One** - x\n\n[2] **DR: Pick** - chose [3]\n\n[3] **WF: Flow** - y\n")?;
+        let config =
+            Config::parse("[types.AE]\n[types.WF]\n[types.DR]\nmust_reference = [\"AE\"]\n")?;
+        let findings = lint(&mm, &config);
+        let ids: Vec<&str> = findings.iter().map(|f| f.rule).collect();
+        assert_eq!(ids, vec!["unknown-type", "type-convention"]);
+        assert_eq!(
+            findings[0].fix.as_ref().unwrap().replacement.as_deref(),
+            Some("[1] **AE: One** - x")
+        );
+        assert_eq!(
+            findings[1].message,
+            "Type convention: node 2: DR must reference at least one AE node"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_stale_verification() -> Result<()> {
+        let mm = load(
+            "[1] **DR: Old** - x (verify 2000-01-01)\n\n[2] **DR: New** - y (verify 2999-01-01)\n\n[3] **DR: Never** - z\n\n[4] **AE: Other** - w\n",
+        )?;
+        // inactive until [stale.types] is configured
+        assert!(lint(&mm, &Config::default()).is_empty());
+
+        let config = Config::parse("[stale.types]\nDR = \"180d\"\n")?;
+        let findings = lint(&mm, &config);
+        let lines: Vec<Option<usize>> = findings.iter().map(|f| f.line()).collect();
+        assert!(findings.iter().all(|f| f.rule == "stale-verification"));
+        assert_eq!(lines, vec![Some(1), Some(5)]);
+        let span = findings[0].span.as_ref().unwrap();
+        assert_eq!((span.start, span.end), (20, 39));
+        Ok(())
+    }
+}
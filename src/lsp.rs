@@ -0,0 +1,908 @@
+//! LSP: a small Language Server for MINDMAP files on stdin/stdout
+//!
+//! This module provides:
+//! - JSON-RPC framing (`Content-Length` headers)
+//! - Go-to-definition for `[N]` and `[N](./file.md)` (external files via `MindmapCache`)
+//! - Find-references (the `cmd_refs` logic), hover showing the target node
+//! - Completion of node IDs (after `[`) and type prefixes (after `[N] **`), including
+//!   `[types]` names and aliases from `.mindmap.toml`
+//! - Rename of a node ID across the file and the `[N](path)` links to it from open
+//!   documents and files it links to (references include those links too)
+//! - Diagnostics published from `cmd_lint`
+//!
+//! Documents are synced in full; open buffers take precedence over files on disk.
+
+use anyhow::{Context, Result, anyhow};
+use serde_json::{Value, json};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use crate::{
+    Mindmap, Node, Reference, cache::MindmapCache, cmd_lint, config::Config, lint::Severity,
+    ref_spans, referring_nodes, split_title,
+};
+
+// JSON-RPC / LSP error codes
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INVALID_REQUEST: i64 = -32600;
+const REQUEST_FAILED: i64 = -32803;
+
+type RpcError = (i64, String);
+
+/// Read one message; Ok(None) at end of input
+pub fn read_message(r: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut len: Option<usize> = None;
+    loop {
+        let mut header = String::new();
+        if r.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            if len.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            len = Some(
+                value
+                    .trim()
+                    .parse()
+                    .context("Invalid Content-Length header")?,
+            );
+        }
+    }
+    let mut body = vec![0u8; len.unwrap_or(0)];
+    r.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/// Write one message with its `Content-Length` header
+pub fn write_message(w: &mut impl Write, msg: &Value) -> Result<()> {
+    let body = serde_json::to_string(msg)?;
+    write!(w, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    w.flush()?;
+    Ok(())
+}
+
+/// Serve LSP on stdin/stdout until `exit`
+pub fn run_stdio() -> Result<()> {
+    let stdin = std::io::stdin();
+    let mut reader = stdin.lock();
+    let mut stdout = std::io::stdout().lock();
+    let mut server = LspServer::new(std::env::current_dir()?);
+
+    while let Some(msg) = read_message(&mut reader)? {
+        if msg.get("method").and_then(Value::as_str) == Some("exit") {
+            return if server.shutdown {
+                Ok(())
+            } else {
+                Err(anyhow!("LSP client sent exit without shutdown"))
+            };
+        }
+        for out in server.handle(&msg) {
+            write_message(&mut stdout, &out)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    let bytes = rest.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = rest.get(i + 1..i + 3)
+            && let Ok(b) = u8::from_str_radix(hex, 16)
+        {
+            out.push(b);
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    let path = String::from_utf8(out).ok()?;
+    // file:///C:/x -> C:/x
+    let path = match path.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => path[1..].to_string(),
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
+pub fn path_to_uri(path: &Path) -> String {
+    let mut s = path.to_string_lossy().replace('\\', "/");
+    if !s.starts_with('/') {
+        s.insert(0, '/');
+    }
+    let mut uri = String::from("file://");
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~:".contains(&b) {
+            uri.push(b as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", b));
+        }
+    }
+    uri
+}
+
+/// UTF-16 column (LSP positions) of a byte offset in a line
+fn utf16_col(line: &str, byte: usize) -> u32 {
+    line[..byte].encode_utf16().count() as u32
+}
+
+/// Byte offset of a UTF-16 column, clamped to the line
+fn byte_offset(line: &str, col: u32) -> usize {
+    let mut units = 0u32;
+    for (i, c) in line.char_indices() {
+        if units >= col {
+            return i;
+        }
+        units += c.len_utf16() as u32;
+    }
+    line.len()
+}
+
+fn range(line_index: usize, line: &str, start: usize, end: usize) -> Value {
+    json!({
+        "start": {"line": line_index, "character": utf16_col(line, start)},
+        "end": {"line": line_index, "character": utf16_col(line, end)},
+    })
+}
+
+/// A `[N]` / `[N](path)` token on a node line (byte range of the line). The node's
+/// own leading `[N]` is the definition token.
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    start: usize,
+    end: usize,
+    reference: Reference,
+    definition: bool,
+}
+
+fn node_tokens(line: &str, node: &Node) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    if let (Some(open), Some(close)) = (line.find('['), line.find(']')) {
+        tokens.push(Token {
+            start: open,
+            end: close + 1,
+            reference: Reference::Internal(node.id),
+            definition: true,
+        });
+    }
+    let desc_start = line.len() - node.description.len();
+    for (span, reference) in ref_spans(&node.description) {
+        tokens.push(Token {
+            start: desc_start + span.start,
+            end: desc_start + span.end,
+            reference,
+            definition: false,
+        });
+    }
+    tokens
+}
+
+fn node_at_line(mm: &Mindmap, line: usize) -> Option<&Node> {
+    mm.nodes.iter().find(|n| n.line_index == line)
+}
+
+/// Lint findings as LSP diagnostics, at the finding's span (line 0 when it has none)
+fn diagnostics(mm: &Mindmap) -> Vec<Value> {
+    let findings = match Config::for_mindmap(&mm.path).and_then(|c| cmd_lint(mm, &c)) {
+        Ok(findings) => findings,
+        Err(e) => {
+            return vec![json!({
+                "range": range(0, "", 0, 0),
+                "severity": 1,
+                "source": "mindmap",
+                "message": format!("Lint failed: {}", e),
+            })];
+        }
+    };
+    findings
+        .iter()
+        .map(|f| {
+            let (line_index, start, end) = match &f.span {
+                Some(s) => (s.line - 1, s.start, s.end),
+                None => (0, 0, 0),
+            };
+            let line = mm.lines.get(line_index).map(String::as_str).unwrap_or("");
+            json!({
+                "range": range(line_index, line, start.min(line.len()), end.min(line.len())),
+                "severity": match f.severity {
+                    Severity::Error => 1,
+                    Severity::Warn => 2,
+                    Severity::Info => 3,
+                },
+                "code": f.rule,
+                "source": "mindmap",
+                "message": f.message,
+            })
+        })
+        .collect()
+}
+
+/// The node a token points at: the document it lives in and its ID
+struct Target {
+    uri: String,
+    mm: Mindmap,
+    id: u32,
+    external: Option<String>,
+}
+
+/// An `[N](path)` link in another file to the node of a `Target`
+struct ExternalLink {
+    uri: String,
+    line_index: usize,
+    line: String,
+    token: Token,
+}
+
+pub struct LspServer {
+    /// Open documents: uri -> full text
+    docs: HashMap<String, String>,
+    cache: MindmapCache,
+    shutdown: bool,
+}
+
+/// Cache for the workspace root, with the limits from its `.mindmap.toml`
+fn root_cache(root: &Path) -> MindmapCache {
+    Config::discover(root).unwrap_or_default().cache.cache(root)
+}
+
+impl LspServer {
+    pub fn new(root: PathBuf) -> Self {
+        LspServer {
+            docs: HashMap::new(),
+            cache: root_cache(&root),
+            shutdown: false,
+        }
+    }
+
+    /// Handle one incoming message and return the messages to send back
+    pub fn handle(&mut self, msg: &Value) -> Vec<Value> {
+        let Some(method) = msg.get("method").and_then(Value::as_str) else {
+            return Vec::new(); // responses to server requests are not used
+        };
+        let params = msg.get("params").cloned().unwrap_or(Value::Null);
+        match msg.get("id") {
+            Some(id) => {
+                let reply = match self.request(method, &params) {
+                    Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+                    Err((code, message)) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {"code": code, "message": message},
+                    }),
+                };
+                vec![reply]
+            }
+            None => self.notification(method, &params),
+        }
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        if self.shutdown {
+            return Err((INVALID_REQUEST, "Server is shutting down".to_string()));
+        }
+        match method {
+            "initialize" => Ok(self.initialize(params)),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/definition" => self.definition(params),
+            "textDocument/references" => self.references(params),
+            "textDocument/hover" => self.hover(params),
+            "textDocument/completion" => self.completion(params),
+            "textDocument/rename" => self.rename(params),
+            _ => Err((METHOD_NOT_FOUND, format!("Unhandled method {}", method))),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.docs.insert(uri.to_string(), text.to_string());
+                self.publish(uri)
+            }
+            "textDocument/didChange" => {
+                // full sync: the last change carries the whole text
+                if let Some(text) = params["contentChanges"]
+                    .as_array()
+                    .and_then(|c| c.last())
+                    .and_then(|c| c["text"].as_str())
+                {
+                    self.docs.insert(uri.to_string(), text.to_string());
+                }
+                self.publish(uri)
+            }
+            "textDocument/didSave" => {
+                // other files may have changed on disk too
+                self.cache.clear();
+                self.publish(uri)
+            }
+            "textDocument/didClose" => {
+                self.docs.remove(uri);
+                vec![json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": {"uri": uri, "diagnostics": []},
+                })]
+            }
+            "workspace/didChangeWatchedFiles" => {
+                self.cache.clear();
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn initialize(&mut self, params: &Value) -> Value {
+        let root = params["rootUri"]
+            .as_str()
+            .and_then(uri_to_path)
+            .or_else(|| params["rootPath"].as_str().map(PathBuf::from))
+            .or_else(|| {
+                params["workspaceFolders"][0]["uri"]
+                    .as_str()
+                    .and_then(uri_to_path)
+            });
+        if let Some(root) = root {
+            self.cache = root_cache(&root);
+        }
+        json!({
+            "capabilities": {
+                "textDocumentSync": 1,
+                "definitionProvider": true,
+                "referencesProvider": true,
+                "hoverProvider": true,
+                "completionProvider": {"triggerCharacters": ["[", "*"]},
+                "renameProvider": true,
+            },
+            "serverInfo": {"name": "mindmap-cli", "version": env!("CARGO_PKG_VERSION")},
+        })
+    }
+
+    fn publish(&self, uri: &str) -> Vec<Value> {
+        let Some(mm) = self.document(uri) else {
+            return Vec::new();
+        };
+        vec![json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": {"uri": uri, "diagnostics": diagnostics(&mm)},
+        })]
+    }
+
+    /// Parse an open document
+    fn document(&self, uri: &str) -> Option<Mindmap> {
+        let text = self.docs.get(uri)?;
+        let path = uri_to_path(uri).unwrap_or_else(|| PathBuf::from(uri));
+        Mindmap::load_from_reader(text.as_bytes(), path).ok()
+    }
+
+    /// Open document, line and token under the cursor of a TextDocumentPositionParams
+    fn token_at(&self, params: &Value) -> Result<(String, Mindmap, usize, Token), RpcError> {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .ok_or((INVALID_PARAMS, "missing textDocument.uri".to_string()))?;
+        let line = params["position"]["line"].as_u64().unwrap_or(0) as usize;
+        let col = params["position"]["character"].as_u64().unwrap_or(0) as u32;
+        let mm = self
+            .document(uri)
+            .ok_or((REQUEST_FAILED, format!("Document not open: {}", uri)))?;
+
+        let token = node_at_line(&mm, line).and_then(|node| {
+            let text = &mm.lines[line];
+            let byte = byte_offset(text, col);
+            node_tokens(text, node)
+                .into_iter()
+                .find(|t| t.start <= byte && byte <= t.end)
+        });
+        match token {
+            Some(token) => Ok((uri.to_string(), mm, line, token)),
+            None => Err((REQUEST_FAILED, "No node reference at position".to_string())),
+        }
+    }
+
+    /// Resolve a token to the document holding its node (external files via the cache)
+    fn target(&mut self, uri: &str, mm: Mindmap, token: &Token) -> Option<Target> {
+        match &token.reference {
+            Reference::Internal(id) => Some(Target {
+                uri: uri.to_string(),
+                mm,
+                id: *id,
+                external: None,
+            }),
+            Reference::External(id, rel) => {
+                let canonical = self.cache.resolve_path(&mm.path, rel).ok()?;
+                let ext_uri = path_to_uri(&canonical);
+                let ext = match self.document(&ext_uri) {
+                    Some(open) => open,
+                    None => self
+                        .cache
+                        .load(&mm.path, rel, &HashSet::new())
+                        .ok()?
+                        .clone(),
+                };
+                Some(Target {
+                    uri: ext_uri,
+                    mm: ext,
+                    id: *id,
+                    external: Some(rel.clone()),
+                })
+            }
+        }
+    }
+
+    /// Links to `target` from other files: open documents plus the files reachable
+    /// from it through external references (within the cache's depth limit)
+    fn external_links(&mut self, target: &Target) -> Vec<ExternalLink> {
+        let canonical = |p: &Path| std::fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+        let target_path = canonical(&target.mm.path);
+
+        let mut files: Vec<(String, Mindmap)> = Vec::new();
+        let mut seen = HashSet::from([target_path.clone()]);
+        let uris: Vec<String> = self.docs.keys().cloned().collect();
+        for uri in uris {
+            if let Some(mm) = self.document(&uri)
+                && seen.insert(canonical(&mm.path))
+            {
+                files.push((uri, mm));
+            }
+        }
+        let mut frontier = vec![target.mm.clone()];
+        for _ in 0..self.cache.max_depth() {
+            let mut next = Vec::new();
+            for mm in &frontier {
+                for node in &mm.nodes {
+                    for reference in &node.references {
+                        let Reference::External(_, rel) = reference else {
+                            continue;
+                        };
+                        let Ok(path) = self.cache.resolve_path(&mm.path, rel) else {
+                            continue;
+                        };
+                        if !seen.insert(path.clone()) {
+                            continue;
+                        }
+                        if let Ok(linked) = self.cache.load(&mm.path, rel, &HashSet::new()) {
+                            let linked = linked.clone();
+                            files.push((path_to_uri(&path), linked.clone()));
+                            next.push(linked);
+                        }
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+
+        let mut links = Vec::new();
+        for (uri, mm) in &files {
+            for node in &mm.nodes {
+                let line = &mm.lines[node.line_index];
+                for token in node_tokens(line, node) {
+                    if let Reference::External(id, rel) = &token.reference
+                        && *id == target.id
+                        && self.cache.resolve_path(&mm.path, rel).ok().as_ref()
+                            == Some(&target_path)
+                    {
+                        links.push(ExternalLink {
+                            uri: uri.clone(),
+                            line_index: node.line_index,
+                            line: line.clone(),
+                            token,
+                        });
+                    }
+                }
+            }
+        }
+        links.sort_by(|a, b| (&a.uri, a.line_index).cmp(&(&b.uri, b.line_index)));
+        links
+    }
+
+    fn definition(&mut self, params: &Value) -> Result<Value, RpcError> {
+        let Ok((uri, mm, _, token)) = self.token_at(params) else {
+            return Ok(Value::Null);
+        };
+        let Some(target) = self.target(&uri, mm, &token) else {
+            return Ok(Value::Null);
+        };
+        let Some(node) = target.mm.get_node(target.id) else {
+            return Ok(Value::Null);
+        };
+        let line = &target.mm.lines[node.line_index];
+        let def = &node_tokens(line, node)[0];
+        Ok(json!({
+            "uri": target.uri,
+            "range": range(node.line_index, line, def.start, def.end),
+        }))
+    }
+
+    fn references(&mut self, params: &Value) -> Result<Value, RpcError> {
+        let Ok((uri, mm, _, token)) = self.token_at(params) else {
+            return Ok(json!([]));
+        };
+        let Some(target) = self.target(&uri, mm, &token) else {
+            return Ok(json!([]));
+        };
+        let include_decl = params["context"]["includeDeclaration"]
+            .as_bool()
+            .unwrap_or(false);
+        let wanted = Reference::Internal(target.id);
+
+        let mut nodes: Vec<&Node> = Vec::new();
+        if include_decl && let Some(node) = target.mm.get_node(target.id) {
+            nodes.push(node);
+        }
+        nodes.extend(referring_nodes(&target.mm, target.id));
+
+        let mut locations = Vec::new();
+        for node in nodes {
+            let line = &target.mm.lines[node.line_index];
+            for t in node_tokens(line, node) {
+                if t.reference == wanted {
+                    locations.push(json!({
+                        "uri": target.uri,
+                        "range": range(node.line_index, line, t.start, t.end),
+                    }));
+                }
+            }
+        }
+        for link in self.external_links(&target) {
+            locations.push(json!({
+                "uri": link.uri,
+                "range": range(link.line_index, &link.line, link.token.start, link.token.end),
+            }));
+        }
+        Ok(Value::Array(locations))
+    }
+
+    fn hover(&mut self, params: &Value) -> Result<Value, RpcError> {
+        let Ok((uri, mm, line_index, token)) = self.token_at(params) else {
+            return Ok(Value::Null);
+        };
+        let hover_range = range(line_index, &mm.lines[line_index], token.start, token.end);
+        let Some(target) = self.target(&uri, mm, &token) else {
+            return Ok(Value::Null);
+        };
+
+        let mut text = match target.mm.get_node(target.id) {
+            Some(node) => format!(
+                "**[{}] {}**\n\n{}",
+                node.id, node.raw_title, node.description
+            ),
+            None => format!("Node [{}] not found", target.id),
+        };
+        if let Some(rel) = &target.external {
+            text.push_str(&format!("\n\n_{}_", rel));
+        }
+        if token.definition {
+            let refs: Vec<String> = referring_nodes(&target.mm, target.id)
+                .map(|n| format!("[{}]", n.id))
+                .collect();
+            if !refs.is_empty() {
+                text.push_str(&format!("\n\nReferred to by: {}", refs.join(", ")));
+            }
+        }
+        Ok(json!({
+            "contents": {"kind": "markdown", "value": text},
+            "range": hover_range,
+        }))
+    }
+
+    fn completion(&mut self, params: &Value) -> Result<Value, RpcError> {
+        static TYPE_PREFIX: LazyLock<regex::Regex> =
+            LazyLock::new(|| regex::Regex::new(r"^\s*\[\d+\] \*\*[A-Za-z0-9_-]*$").unwrap());
+        static NEW_NODE: LazyLock<regex::Regex> =
+            LazyLock::new(|| regex::Regex::new(r"^\s*\[\d*$").unwrap());
+        static ID_REF: LazyLock<regex::Regex> =
+            LazyLock::new(|| regex::Regex::new(r"\[\d*$").unwrap());
+
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let Some(mm) = self.document(uri) else {
+            return Ok(json!([]));
+        };
+        let line_index = params["position"]["line"].as_u64().unwrap_or(0) as usize;
+        let col = params["position"]["character"].as_u64().unwrap_or(0) as u32;
+        let line = mm.lines.get(line_index).map(String::as_str).unwrap_or("");
+        let prefix = &line[..byte_offset(line, col)];
+
+        let items: Vec<Value> = if TYPE_PREFIX.is_match(prefix) {
+            // types used in the document plus the `[types]` registry (names and aliases)
+            let registry = Config::for_mindmap(&mm.path).unwrap_or_default().types;
+            registry
+                .completions(mm.nodes.iter().filter_map(|n| split_title(&n.raw_title).0))
+                .into_iter()
+                .map(|t| {
+                    let mut item = json!({
+                        "label": t.label,
+                        "kind": 14,
+                        "insertText": format!("{}: ", t.prefix),
+                    });
+                    if !t.detail.is_empty() {
+                        item["detail"] = json!(t.detail);
+                    }
+                    item
+                })
+                .collect()
+        } else if NEW_NODE.is_match(prefix) {
+            // a new node line: offer the next free ID
+            let id = mm.next_id();
+            vec![json!({"label": id.to_string(), "kind": 12, "detail": "next free ID"})]
+        } else if ID_REF.is_match(prefix) {
+            mm.nodes
+                .iter()
+                .map(|n| {
+                    json!({
+                        "label": n.id.to_string(),
+                        "kind": 18,
+                        "detail": n.raw_title,
+                        "documentation": n.description,
+                    })
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Ok(json!({"isIncomplete": false, "items": items}))
+    }
+
+    fn rename(&mut self, params: &Value) -> Result<Value, RpcError> {
+        let (uri, mm, _, token) = self.token_at(params)?;
+        let Reference::Internal(old) = token.reference else {
+            return Err((
+                REQUEST_FAILED,
+                "Rename external references in the file that defines them".to_string(),
+            ));
+        };
+        let new_name = params["newName"].as_str().unwrap_or_default();
+        let new: u32 = new_name
+            .trim()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse()
+            .map_err(|_| (INVALID_PARAMS, format!("Invalid node ID '{}'", new_name)))?;
+        if mm.get_node(old).is_none() {
+            return Err((REQUEST_FAILED, format!("Node [{}] not found", old)));
+        }
+        if new != old && mm.get_node(new).is_some() {
+            return Err((REQUEST_FAILED, format!("Node [{}] already exists", new)));
+        }
+
+        let mut edits = Vec::new();
+        for node in &mm.nodes {
+            let line = &mm.lines[node.line_index];
+            for t in node_tokens(line, node) {
+                if t.reference == Reference::Internal(old) {
+                    edits.push(json!({
+                        "range": range(node.line_index, line, t.start, t.end),
+                        "newText": format!("[{}]", new),
+                    }));
+                }
+            }
+        }
+        // `[old](path)` links in other files: only the `[old]` part changes
+        let target = Target {
+            uri: uri.clone(),
+            mm,
+            id: old,
+            external: None,
+        };
+        let mut changes = serde_json::Map::new();
+        for link in self.external_links(&target) {
+            let end = link.token.start + format!("[{}]", old).len();
+            let edit = json!({
+                "range": range(link.line_index, &link.line, link.token.start, end),
+                "newText": format!("[{}]", new),
+            });
+            match changes.get_mut(&link.uri) {
+                Some(Value::Array(edits)) => edits.push(edit),
+                _ => {
+                    changes.insert(link.uri, json!([edit]));
+                }
+            }
+        }
+        changes.insert(uri, Value::Array(edits));
+        Ok(json!({ "changes": changes }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URI: &str = "file:///w/MINDMAP.md";
+
+    fn server(text: &str) -> LspServer {
+        let mut s = LspServer::new(PathBuf::from("."));
+        s.handle(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": {"textDocument": {"uri": URI, "text": text}},
+        }));
+        s
+    }
+
+    fn request(s: &mut LspServer, method: &str, line: u32, character: u32, extra: Value) -> Value {
+        let mut params = json!({
+            "textDocument": {"uri": URI},
+            "position": {"line": line, "character": character},
+        });
+        if let (Some(p), Some(e)) = (params.as_object_mut(), extra.as_object()) {
+            p.extend(e.clone());
+        }
+        let out = s.handle(&json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}));
+        out[0].clone()
+    }
+
+    #[test]
+    fn test_framing_roundtrip() -> Result<()> {
+        let msg = json!({"jsonrpc": "2.0", "id": 1, "method": "shutdown"});
+        let mut buf = Vec::new();
+        write_message(&mut buf, &msg)?;
+        let mut reader = std::io::Cursor::new(buf);
+        assert_eq!(read_message(&mut reader)?, Some(msg));
+        assert_eq!(read_message(&mut reader)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_uri_paths() {
+        let uri = path_to_uri(Path::new("/w/my notes/MINDMAP.md"));
+        assert_eq!(uri, "file:///w/my%20notes/MINDMAP.md");
+        assert_eq!(
+            uri_to_path(&uri),
+            Some(PathBuf::from("/w/my notes/MINDMAP.md"))
+        );
+    }
+
+    #[test]
+    fn test_definition_hover_references() {
+        let mut s = server("[1] **AE: Auth** - login\n\n[2] **WF: Flow** - uses [1] and [1]\n");
+        // cursor on a [1] reference in node 2
+        let def = request(&mut s, "textDocument/definition", 2, 25, json!({}));
+        assert_eq!(def["result"]["range"]["start"]["line"], 0);
+        assert_eq!(def["result"]["range"]["end"]["character"], 3);
+
+        let hover = request(&mut s, "textDocument/hover", 2, 25, json!({}));
+        let text = hover["result"]["contents"]["value"].as_str().unwrap();
+        assert!(text.contains("[1] AE: Auth"));
+
+        let refs = request(
+            &mut s,
+            "textDocument/references",
+            0,
+            1,
+            json!({"context": {"includeDeclaration": true}}),
+        );
+        assert_eq!(refs["result"].as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_completion_and_rename() {
+        let mut s = server("[1] **AE: Auth** - login\n\n[2] **WF: Flow** - uses [1]\n\n[3] **\n");
+        let ids = request(&mut s, "textDocument/completion", 2, 25, json!({}));
+        assert_eq!(ids["result"]["items"].as_array().unwrap().len(), 2);
+        let none = request(&mut s, "textDocument/completion", 2, 20, json!({}));
+        assert_eq!(none["result"]["items"].as_array().unwrap().len(), 0);
+        let types = request(&mut s, "textDocument/completion", 4, 6, json!({}));
+        let labels: Vec<&str> = types["result"]["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i["label"].as_str().unwrap())
+            .collect();
+        assert_eq!(labels, vec!["AE", "WF"]);
+
+        let edit = request(
+            &mut s,
+            "textDocument/rename",
+            2,
+            25,
+            json!({"newName": "10"}),
+        );
+        let edits = edit["result"]["changes"][URI].as_array().unwrap();
+        assert_eq!(edits.len(), 2);
+        assert!(edits.iter().all(|e| e["newText"] == "[10]"));
+
+        let taken = request(&mut s, "textDocument/rename", 0, 1, json!({"newName": "2"}));
+        assert_eq!(taken["error"]["code"], REQUEST_FAILED);
+    }
+
+    #[test]
+    fn test_type_completion_includes_registry() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        std::fs::write(
+            temp.path().join(crate::config::CONFIG_FILE),
+            "[types.DR]\ndescription = \"Decision\"\naliases = [\"decision\"]\n",
+        )?;
+        let uri = path_to_uri(&temp.path().join("MINDMAP.md"));
+        let mut s = LspServer::new(temp.path().to_path_buf());
+        s.handle(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": {"textDocument": {"uri": uri, "text": "[1] **AE: Auth** - x\n\n[2] **\n"}},
+        }));
+        let out = s.handle(
+            &json!({"jsonrpc": "2.0", "id": 1, "method": "textDocument/completion", "params": {
+                "textDocument": {"uri": uri},
+                "position": {"line": 2, "character": 6},
+            }}),
+        );
+        let items = out[0]["result"]["items"].as_array().unwrap().clone();
+        let labels: Vec<&str> = items.iter().map(|i| i["label"].as_str().unwrap()).collect();
+        assert_eq!(labels, vec!["AE", "DR", "decision"]);
+        assert_eq!(items[1]["detail"], "Decision");
+        assert_eq!(items[2]["insertText"], "DR: ");
+        Ok(())
+    }
+
+    #[test]
+    fn test_references_and_rename_across_files() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let root = std::fs::canonicalize(temp.path())?;
+        let text = "[1] **AE: A** - see [2](./B.md)\n";
+        std::fs::write(root.join("A.md"), text)?;
+        std::fs::write(
+            root.join("B.md"),
+            "[2] **AE: B** - back to [1](./A.md) and [1]\n",
+        )?;
+        let uri = path_to_uri(&root.join("A.md"));
+        let b_uri = path_to_uri(&root.join("B.md"));
+        let mut s = LspServer::new(root.clone());
+        s.handle(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": {"textDocument": {"uri": uri, "text": text}},
+        }));
+        let position =
+            json!({"textDocument": {"uri": uri}, "position": {"line": 0, "character": 1}});
+
+        let out = s.handle(&json!({"jsonrpc": "2.0", "id": 1, "method": "textDocument/references", "params": position}));
+        let refs = out[0]["result"].as_array().unwrap();
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0]["uri"], b_uri);
+        assert_eq!(refs[0]["range"]["start"]["character"], 24);
+
+        let mut params = position.clone();
+        params["newName"] = json!("7");
+        let out = s.handle(
+            &json!({"jsonrpc": "2.0", "id": 2, "method": "textDocument/rename", "params": params}),
+        );
+        let edits = out[0]["result"]["changes"][&b_uri].as_array().unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0]["newText"], "[7]");
+        assert_eq!(edits[0]["range"]["end"]["character"], 27);
+        Ok(())
+    }
+
+    #[test]
+    fn test_diagnostics_from_lint() {
+        let mm = Mindmap::load_from_reader(
+            "[1] **AE: A** - ok\n\n[2] **AE: B** - see [9]\n".as_bytes(),
+            PathBuf::from("-"),
+        )
+        .unwrap();
+        let diags = diagnostics(&mm);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0]["range"]["start"]["line"], 2);
+    }
+}
@@ -0,0 +1,673 @@
+//! MCP: Model Context Protocol tool server for AI agents
+//!
+//! This module provides:
+//! - JSON-RPC 2.0 over stdio, one message per line (`initialize`, `tools/list`, `tools/call`)
+//! - Typed tools with JSON schemas: `show`, `list`, `refs`, `links`, `search` (read,
+//!   ranked by relevance) and `add`, `patch`, `put`, `delete`, `batch` (write)
+//! - A `Session` that keeps the mindmap loaded between calls, reloads it when the file
+//!   changes on disk, and re-validates the on-disk blake3 hash before every save
+
+use anyhow::{Context, Result, anyhow};
+use serde_json::{Value, json};
+use std::{
+    fmt, fs,
+    io::{BufRead, Write},
+};
+
+use crate::{
+    Mindmap, Node, apply_batch_ops, blake3_hash, cmd_add, cmd_delete, cmd_links, cmd_lint,
+    cmd_list, cmd_patch, cmd_put,
+    config::{Config, TypesConfig},
+    ensure_unchanged, parse_batch_op_json, parse_node_line, referring_nodes, search, split_title,
+};
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// A loaded mindmap plus the blake3 hash of the file content it was loaded from
+pub struct Session {
+    pub mm: Mindmap,
+    hash: String,
+    /// The map's `.mindmap.toml`, resolved once by the caller
+    pub config: Config,
+}
+
+impl Session {
+    pub fn new(mm: Mindmap, config: Config) -> Result<Self> {
+        let hash = if mm.path.as_os_str() == "-" {
+            String::new()
+        } else {
+            blake3_hash(&fs::read(&mm.path)?)
+        };
+        Ok(Session { mm, hash, config })
+    }
+
+    /// Hash of the content the session reflects (empty for stdin)
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    /// Reload the mindmap if the file changed on disk since it was loaded
+    pub fn refresh(&mut self) -> Result<()> {
+        if self.mm.path.as_os_str() == "-" {
+            return Ok(());
+        }
+        let content = fs::read_to_string(&self.mm.path)
+            .with_context(|| format!("Failed to read {}", self.mm.path.display()))?;
+        let hash = blake3_hash(content.as_bytes());
+        if hash != self.hash {
+            self.mm = Mindmap::from_string(content, self.mm.path.clone())?;
+            self.hash = hash;
+        }
+        Ok(())
+    }
+
+    /// Apply `f` to a staged copy (with the session's `[types]`), then save it if the
+    /// file is still the one the session loaded. Lint warnings of the result are
+    /// returned under "warnings".
+    pub fn mutate(
+        &mut self,
+        name: &str,
+        f: impl FnOnce(&mut Mindmap, &TypesConfig) -> Result<Value>,
+    ) -> Result<Value> {
+        if self.mm.path.as_os_str() == "-" {
+            return Err(anyhow!(
+                "Cannot {}: mindmap was loaded from stdin ('-'); use --file <path> to save changes",
+                name
+            ));
+        }
+        self.refresh()?;
+
+        let mut staged = self.mm.clone();
+        let mut out = f(&mut staged, &self.config.types)?;
+        let warnings: Vec<String> = cmd_lint(&staged, &self.config)?
+            .iter()
+            .map(|f| f.describe())
+            .collect();
+
+        ensure_unchanged(&staged.path, &self.hash, name)
+            .map_err(|e| ToolError::Conflict(e.to_string()))?;
+        staged.save()?;
+
+        // keep the session identical to disk (save normalizes spacing)
+        let content = fs::read_to_string(&staged.path)?;
+        self.hash = blake3_hash(content.as_bytes());
+        self.mm = Mindmap::from_string(content, staged.path)?;
+
+        if let Some(obj) = out.as_object_mut() {
+            obj.insert("warnings".to_string(), json!(warnings));
+        }
+        Ok(out)
+    }
+}
+
+/// A node as structured JSON (title split into type/title, 1-based line, incoming ids)
+pub fn node_json(mm: &Mindmap, node: &Node) -> Value {
+    let (node_type, title) = split_title(&node.raw_title);
+    let incoming: Vec<u32> = referring_nodes(mm, node.id).map(|n| n.id).collect();
+    json!({
+        "id": node.id,
+        "type": node_type,
+        "title": title,
+        "raw_title": node.raw_title,
+        "description": node.description,
+        "references": node.references,
+        "incoming": incoming,
+        "line": node.line_index + 1,
+    })
+}
+
+/// Tool failures that front ends report distinctly (HTTP 404 and 409)
+#[derive(Debug)]
+pub enum ToolError {
+    /// The node named by the `id` argument does not exist
+    NotFound(u32),
+    /// The file changed on disk while a mutation was being applied
+    Conflict(String),
+}
+
+impl fmt::Display for ToolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToolError::NotFound(id) => write!(f, "Node [{}] not found", id),
+            ToolError::Conflict(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for ToolError {}
+
+fn get_node_json(mm: &Mindmap, id: u32) -> Result<Value> {
+    mm.get_node(id)
+        .map(|n| node_json(mm, n))
+        .ok_or_else(|| ToolError::NotFound(id).into())
+}
+
+/// The `id` argument, which must name an existing node
+fn existing_id(mm: &Mindmap, args: &Value) -> Result<u32> {
+    let id = arg_u32(args, "id")?;
+    if mm.get_node(id).is_none() {
+        return Err(ToolError::NotFound(id).into());
+    }
+    Ok(id)
+}
+
+fn arg_u32(args: &Value, name: &str) -> Result<u32> {
+    args.get(name)
+        .and_then(Value::as_u64)
+        .and_then(|v| u32::try_from(v).ok())
+        .ok_or_else(|| anyhow!("missing or invalid '{}' (integer)", name))
+}
+
+fn arg_str<'a>(args: &'a Value, name: &str) -> Result<&'a str> {
+    args.get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("missing or invalid '{}' (string)", name))
+}
+
+fn opt_str<'a>(args: &'a Value, name: &str) -> Option<&'a str> {
+    args.get(name).and_then(Value::as_str)
+}
+
+fn opt_bool(args: &Value, name: &str) -> bool {
+    args.get(name).and_then(Value::as_bool).unwrap_or(false)
+}
+
+/// `limit` argument (no limit when absent)
+fn opt_limit(args: &Value) -> usize {
+    args.get("limit")
+        .and_then(Value::as_u64)
+        .map_or(usize::MAX, |l| l as usize)
+}
+
+/// Run `cmd_list` and return at most `limit` matching nodes as structured JSON
+fn list_nodes(
+    mm: &Mindmap,
+    type_filter: Option<&str>,
+    query: Option<&str>,
+    args: &Value,
+    limit: usize,
+) -> Result<Value> {
+    let regex = opt_bool(args, "regex");
+    if regex && let Some(q) = query {
+        regex::Regex::new(q).map_err(|e| anyhow!("Invalid regex pattern: {}", e))?;
+    }
+    let lines = cmd_list(
+        mm,
+        type_filter,
+        query,
+        opt_bool(args, "case_sensitive"),
+        opt_bool(args, "exact_match"),
+        regex,
+    );
+    let nodes: Vec<Value> = lines
+        .iter()
+        .filter_map(|l| parse_node_line(l, 0).ok())
+        .filter_map(|n| mm.get_node(n.id))
+        .take(limit)
+        .map(|n| node_json(mm, n))
+        .collect();
+    Ok(json!({"count": nodes.len(), "nodes": nodes}))
+}
+
+/// Ranked search: matching nodes best first, each with its score and a highlighted
+/// snippet (`regex` falls back to the unranked filter)
+fn search_nodes(mm: &Mindmap, query: &str, args: &Value) -> Result<Value> {
+    let limit = opt_limit(args);
+    if opt_bool(args, "regex") {
+        return list_nodes(mm, None, Some(query), args, limit);
+    }
+    let opts = search::Options {
+        case_sensitive: opt_bool(args, "case_sensitive"),
+        exact: opt_bool(args, "exact_match"),
+    };
+    let nodes: Vec<Value> = search::search(&mm.nodes, &search::Query::parse(query), &opts)
+        .iter()
+        .take(limit)
+        .map(|h| {
+            let mut v = node_json(mm, h.node);
+            v["score"] = json!(h.score);
+            v["snippet"] = json!(search::snippet(
+                &h.node.description,
+                &h.description_matches,
+                search::SNIPPET_WIDTH
+            ));
+            v
+        })
+        .collect();
+    Ok(json!({"count": nodes.len(), "nodes": nodes}))
+}
+
+/// Tool definitions advertised by `tools/list`
+pub fn tool_definitions() -> Value {
+    let id = json!({"type": "integer", "minimum": 0, "description": "Node ID"});
+    let search_flags = json!({
+        "case_sensitive": {"type": "boolean", "description": "Case-sensitive match"},
+        "exact_match": {"type": "boolean", "description": "Match whole words only (search: no partial or typo matches)"},
+        "regex": {"type": "boolean", "description": "Treat the query as a regular expression"},
+    });
+    let mut list_props = json!({
+        "type": {"type": "string", "description": "Type prefix filter, e.g. AE"},
+        "grep": {"type": "string", "description": "Text filter on title and description"},
+    });
+    let mut search_props = json!({
+        "query": {"type": "string", "description": "Words, \"quoted phrases\", title:word or desc:word"},
+        "limit": {"type": "integer", "minimum": 1, "description": "Maximum number of results"},
+    });
+    for props in [&mut list_props, &mut search_props] {
+        if let (Some(p), Some(flags)) = (props.as_object_mut(), search_flags.as_object()) {
+            p.extend(flags.clone());
+        }
+    }
+
+    json!([
+        {
+            "name": "show",
+            "description": "Show a node with its references and the IDs of nodes referring to it",
+            "inputSchema": {"type": "object", "properties": {"id": id}, "required": ["id"]},
+        },
+        {
+            "name": "list",
+            "description": "List nodes, optionally filtered by type prefix and text",
+            "inputSchema": {"type": "object", "properties": list_props},
+        },
+        {
+            "name": "refs",
+            "description": "Nodes that reference the given node",
+            "inputSchema": {"type": "object", "properties": {"id": id}, "required": ["id"]},
+        },
+        {
+            "name": "links",
+            "description": "References made by the given node",
+            "inputSchema": {"type": "object", "properties": {"id": id}, "required": ["id"]},
+        },
+        {
+            "name": "search",
+            "description": "Search node titles and descriptions, most relevant first (with scores and snippets)",
+            "inputSchema": {"type": "object", "properties": search_props, "required": ["query"]},
+        },
+        {
+            "name": "add",
+            "description": "Add a node with the next free ID",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "type": {"type": "string", "description": "Type prefix, e.g. AE"},
+                    "title": {"type": "string"},
+                    "desc": {"type": "string", "description": "Description with [N] references"},
+                },
+                "required": ["type", "title", "desc"],
+            },
+        },
+        {
+            "name": "patch",
+            "description": "Partially update a node's type, title and/or description",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": id,
+                    "type": {"type": "string"},
+                    "title": {"type": "string"},
+                    "desc": {"type": "string"},
+                    "strict": {"type": "boolean", "description": "Fail on missing references"},
+                },
+                "required": ["id"],
+            },
+        },
+        {
+            "name": "put",
+            "description": "Replace a node's full line ([N] **Type: Title** - description)",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": id,
+                    "line": {"type": "string"},
+                    "strict": {"type": "boolean", "description": "Fail on missing references"},
+                },
+                "required": ["id", "line"],
+            },
+        },
+        {
+            "name": "delete",
+            "description": "Delete a node (force deletes even if other nodes reference it)",
+            "inputSchema": {
+                "type": "object",
+                "properties": {"id": id, "force": {"type": "boolean"}},
+                "required": ["id"],
+            },
+        },
+        {
+            "name": "batch",
+            "description": "Apply several operations atomically (same op objects as `batch --format json`)",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "ops": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "op": {"enum": ["add", "patch", "put", "delete", "deprecate", "verify"]},
+                            },
+                            "required": ["op"],
+                        },
+                    },
+                    "fix": {"type": "boolean", "description": "Apply auto-fixes before saving"},
+                },
+                "required": ["ops"],
+            },
+        },
+    ])
+}
+
+/// Execute a tool. Ok(None) means the tool does not exist.
+pub fn call_tool(session: &mut Session, name: &str, args: &Value) -> Result<Option<Value>> {
+    let out = match name {
+        "show" | "refs" | "links" | "list" | "search" => {
+            session.refresh()?;
+            let mm = &session.mm;
+            let types = &session.config.types;
+            match name {
+                "show" => get_node_json(mm, arg_u32(args, "id")?)?,
+                "refs" => {
+                    let id = existing_id(mm, args)?;
+                    let nodes: Vec<Value> =
+                        referring_nodes(mm, id).map(|n| node_json(mm, n)).collect();
+                    json!({"id": id, "count": nodes.len(), "nodes": nodes})
+                }
+                "links" => {
+                    let id = existing_id(mm, args)?;
+                    let links = cmd_links(mm, id).ok_or(ToolError::NotFound(id))?;
+                    json!({"id": id, "links": links})
+                }
+                "list" => {
+                    let type_filter =
+                        opt_str(args, "type").map(|t| types.canonical(t).unwrap_or(t));
+                    list_nodes(mm, type_filter, opt_str(args, "grep"), args, usize::MAX)?
+                }
+                _ => search_nodes(mm, arg_str(args, "query")?, args)?,
+            }
+        }
+        "add" => session.mutate(name, |mm, types| {
+            let id = cmd_add(
+                mm,
+                arg_str(args, "type")?,
+                arg_str(args, "title")?,
+                arg_str(args, "desc")?,
+                types,
+            )?;
+            Ok(json!({"node": get_node_json(mm, id)?}))
+        })?,
+        "patch" => session.mutate(name, |mm, types| {
+            let id = existing_id(mm, args)?;
+            cmd_patch(
+                mm,
+                id,
+                opt_str(args, "type"),
+                opt_str(args, "title"),
+                opt_str(args, "desc"),
+                opt_bool(args, "strict"),
+                types,
+            )?;
+            Ok(json!({"node": get_node_json(mm, id)?}))
+        })?,
+        "put" => session.mutate(name, |mm, types| {
+            let id = existing_id(mm, args)?;
+            cmd_put(
+                mm,
+                id,
+                arg_str(args, "line")?,
+                opt_bool(args, "strict"),
+                types,
+            )?;
+            Ok(json!({"node": get_node_json(mm, id)?}))
+        })?,
+        "delete" => session.mutate(name, |mm, _| {
+            let id = existing_id(mm, args)?;
+            cmd_delete(mm, id, opt_bool(args, "force"))?;
+            Ok(json!({"deleted": id}))
+        })?,
+        "batch" => session.mutate(name, |mm, types| {
+            let ops = args
+                .get("ops")
+                .and_then(Value::as_array)
+                .ok_or_else(|| anyhow!("missing or invalid 'ops' (array)"))?
+                .iter()
+                .enumerate()
+                .map(|(i, v)| {
+                    parse_batch_op_json(v)
+                        .map_err(|e| anyhow!("Failed to parse batch op {}: {}", i, e))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let result = apply_batch_ops(mm, &ops, opt_bool(args, "fix"), types)?;
+            Ok(json!({"result": result}))
+        })?,
+        _ => return Ok(None),
+    };
+    Ok(Some(out))
+}
+
+pub struct McpServer {
+    session: Session,
+}
+
+impl McpServer {
+    pub fn new(mm: Mindmap, config: Config) -> Result<Self> {
+        Ok(McpServer {
+            session: Session::new(mm, config)?,
+        })
+    }
+
+    /// Handle one incoming message; returns the response for requests
+    pub fn handle(&mut self, msg: &Value) -> Option<Value> {
+        let id = msg.get("id")?.clone(); // notifications get no response
+        let method = msg
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let params = msg.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            "initialize" => {
+                let requested = params["protocolVersion"].as_str().unwrap_or_default();
+                let version = PROTOCOL_VERSIONS
+                    .iter()
+                    .find(|v| **v == requested)
+                    .unwrap_or(&PROTOCOL_VERSIONS[0]);
+                Ok(json!({
+                    "protocolVersion": version,
+                    "capabilities": {"tools": {}},
+                    "serverInfo": {"name": "mindmap-cli", "version": env!("CARGO_PKG_VERSION")},
+                }))
+            }
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({"tools": tool_definitions()})),
+            "tools/call" => {
+                let name = params["name"].as_str().unwrap_or_default();
+                let args = params.get("arguments").cloned().unwrap_or(json!({}));
+                match call_tool(&mut self.session, name, &args) {
+                    Ok(Some(out)) => Ok(json!({
+                        "content": [{"type": "text", "text": serde_json::to_string_pretty(&out).unwrap_or_default()}],
+                        "structuredContent": out,
+                        "isError": false,
+                    })),
+                    Ok(None) => Err((INVALID_PARAMS, format!("Unknown tool: {}", name))),
+                    // tool failures are reported to the model, not as protocol errors
+                    Err(e) => Ok(json!({
+                        "content": [{"type": "text", "text": e.to_string()}],
+                        "isError": true,
+                    })),
+                }
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        };
+
+        Some(match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {"code": code, "message": message},
+            }),
+        })
+    }
+}
+
+/// Serve MCP on stdin/stdout until end of input
+pub fn run_stdio(mm: Mindmap, config: Config) -> Result<()> {
+    let mut server = McpServer::new(mm, config)?;
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout().lock();
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match serde_json::from_str::<Value>(&line) {
+            Ok(msg) => server.handle(&msg),
+            Err(e) => Some(json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": {"code": PARSE_ERROR, "message": format!("Parse error: {}", e)},
+            })),
+        };
+        if let Some(reply) = reply {
+            writeln!(stdout, "{}", serde_json::to_string(&reply)?)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(server: &mut McpServer, name: &str, args: Value) -> Value {
+        let reply = server
+            .handle(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "tools/call",
+                "params": {"name": name, "arguments": args},
+            }))
+            .unwrap();
+        reply["result"].clone()
+    }
+
+    fn server(temp: &tempfile::TempDir, content: &str) -> Result<McpServer> {
+        let path = temp.path().join("MINDMAP.md");
+        fs::write(&path, content)?;
+        let config = Config::for_mindmap(&path)?;
+        McpServer::new(Mindmap::load(path)?, config)
+    }
+
+    #[test]
+    fn test_tools_list_and_reads() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let mut s = server(
+            &temp,
+            "[1] **AE: Auth** - login\n\n[2] **WF: Flow** - uses [1]\n",
+        )?;
+
+        let reply = s
+            .handle(&json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}))
+            .unwrap();
+        assert_eq!(reply["result"]["tools"].as_array().unwrap().len(), 10);
+
+        let show = call(&mut s, "show", json!({"id": 1}));
+        assert_eq!(show["structuredContent"]["type"], "AE");
+        assert_eq!(show["structuredContent"]["incoming"], json!([2]));
+
+        let search = call(&mut s, "search", json!({"query": "uses"}));
+        assert_eq!(search["structuredContent"]["count"], 1);
+        let search = call(&mut s, "search", json!({"query": "lgin auth", "limit": 1}));
+        assert_eq!(search["structuredContent"]["count"], 1);
+        assert_eq!(search["structuredContent"]["nodes"][0]["id"], 1);
+        assert_eq!(
+            search["structuredContent"]["nodes"][0]["snippet"],
+            "«login»"
+        );
+        let search = call(
+            &mut s,
+            "search",
+            json!({"query": "^(AE|WF):", "regex": true, "limit": 1}),
+        );
+        assert_eq!(search["structuredContent"]["count"], 1);
+        let bad = call(&mut s, "list", json!({"grep": "(", "regex": true}));
+        assert_eq!(bad["isError"], true);
+        assert!(
+            bad["content"][0]["text"]
+                .as_str()
+                .unwrap()
+                .contains("Invalid regex pattern")
+        );
+
+        let missing = call(&mut s, "show", json!({"id": 9}));
+        assert_eq!(missing["isError"], true);
+        let missing = call(&mut s, "refs", json!({"id": 9}));
+        assert_eq!(missing["isError"], true);
+
+        // notifications get no reply
+        assert!(
+            s.handle(&json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+                .is_none()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_mutations_save_and_detect_external_changes() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let mut s = server(&temp, "[1] **AE: Auth** - login\n")?;
+        let path = temp.path().join("MINDMAP.md");
+
+        let added = call(
+            &mut s,
+            "add",
+            json!({"type": "WF", "title": "Flow", "desc": "uses [1]"}),
+        );
+        assert_eq!(added["structuredContent"]["node"]["id"], 2);
+        assert!(fs::read_to_string(&path)?.contains("[2] **WF: Flow** - uses [1]"));
+
+        // an edit made by someone else is picked up before the next call
+        let content = fs::read_to_string(&path)?;
+        fs::write(&path, content + "\n[3] **DR: Other** - x\n")?;
+        let patched = call(&mut s, "patch", json!({"id": 3, "desc": "y [1]"}));
+        assert_eq!(patched["isError"], false);
+
+        let batch = call(
+            &mut s,
+            "batch",
+            json!({"ops": [{"op": "delete", "id": 3}, {"op": "nope"}]}),
+        );
+        assert_eq!(batch["isError"], true);
+        assert!(fs::read_to_string(&path)?.contains("[3] **DR: Other** - y [1]"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_mutations_use_the_session_config() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let mut s = server(&temp, "[1] **AE: Auth** - login\n")?;
+        s.session.config = Config::parse("[types.AE]\naliases = [\"arch\"]\n")?;
+
+        let added = call(
+            &mut s,
+            "add",
+            json!({"type": "arch", "title": "Store", "desc": "x"}),
+        );
+        assert_eq!(added["structuredContent"]["node"]["type"], "AE");
+        let refused = call(
+            &mut s,
+            "put",
+            json!({"id": 1, "line": "[1] **WF: Auth** - login"}),
+        );
+        assert_eq!(refused["isError"], true);
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+//! Merge: node-level three-way merge, usable as a git merge driver
+//!
+//! This module provides:
+//! - A merge keyed by node ID instead of by line: type, title and description are
+//!   merged field by field, so non-overlapping edits to one node combine cleanly
+//! - Renumbering of nodes both sides added under the same ID (the usual result of two
+//!   branches running `add`); references to the renumbered node on their side follow
+//! - Conflict markers only for genuine same-field edits (or edit vs delete)
+//!
+//! Configure with
+//! `git config merge.mindmap.driver "mindmap-cli merge-driver %O %A %B %L %P"` and
+//! `MINDMAP.md merge=mindmap` in `.gitattributes`.
+
+use anyhow::Result;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::PathBuf,
+};
+
+use crate::{Mindmap, Node, Reference, rewrite_refs, split_title};
+
+/// The mergeable parts of a node
+#[derive(Debug, Clone, PartialEq)]
+struct Fields {
+    r#type: Option<String>,
+    title: String,
+    description: String,
+}
+
+impl Fields {
+    fn of(node: &Node) -> Self {
+        let (t, title) = split_title(&node.raw_title);
+        Fields {
+            r#type: t.map(str::to_string),
+            title: title.to_string(),
+            description: node.description.clone(),
+        }
+    }
+
+    fn line(&self, id: u32) -> String {
+        let raw_title = match &self.r#type {
+            Some(t) => format!("{}: {}", t, self.title),
+            None => self.title.clone(),
+        };
+        format!("[{}] **{}** - {}", id, raw_title, self.description)
+    }
+}
+
+/// Three-way merge of one field: a side that kept the base value yields to the other
+fn merge_field<T: PartialEq + Clone>(base: &T, ours: &T, theirs: &T) -> Option<T> {
+    if ours == theirs || theirs == base {
+        Some(ours.clone())
+    } else if ours == base {
+        Some(theirs.clone())
+    } else {
+        None
+    }
+}
+
+enum Merged {
+    Node(Fields),
+    Deleted,
+    /// ours, base, theirs (None where the side deleted the node)
+    Conflict(Option<Fields>, Option<Fields>, Option<Fields>),
+}
+
+/// Result of `merge`
+#[derive(Debug, Default)]
+pub struct MergeOutcome {
+    pub content: String,
+    /// Node IDs left with conflict markers
+    pub conflicts: Vec<u32>,
+    /// Their added nodes moved to a fresh ID: (old, new)
+    pub renumbered: Vec<(u32, u32)>,
+    /// Both sides changed the non-node text differently
+    pub text_conflict: bool,
+}
+
+fn parse(content: &str) -> Result<Mindmap> {
+    Mindmap::from_string(content.to_string(), PathBuf::from("-"))
+}
+
+fn fields_by_id(mm: &Mindmap) -> BTreeMap<u32, Fields> {
+    mm.nodes.iter().map(|n| (n.id, Fields::of(n))).collect()
+}
+
+/// Non-blank lines that are not nodes
+fn prose(mm: &Mindmap) -> Vec<&str> {
+    let node_lines: BTreeSet<usize> = mm.nodes.iter().map(|n| n.line_index).collect();
+    mm.lines
+        .iter()
+        .enumerate()
+        .filter(|(i, l)| !node_lines.contains(i) && !l.trim().is_empty())
+        .map(|(_, l)| l.as_str())
+        .collect()
+}
+
+/// Merge `ours` and `theirs` against their common ancestor `base`.
+/// `marker_size` is the conflict marker length (git's `%L`, default 7).
+pub fn merge(base: &str, ours: &str, theirs: &str, marker_size: usize) -> Result<MergeOutcome> {
+    let (o_mm, a_mm, b_mm) = (parse(base)?, parse(ours)?, parse(theirs)?);
+    let (o, a, b_orig) = (
+        fields_by_id(&o_mm),
+        fields_by_id(&a_mm),
+        fields_by_id(&b_mm),
+    );
+
+    // both sides added the same ID: keep ours, move theirs to a fresh ID
+    let mut next_id = o
+        .keys()
+        .chain(a.keys())
+        .chain(b_orig.keys())
+        .max()
+        .copied()
+        .unwrap_or(0)
+        + 1;
+    let mut renumber: HashMap<u32, u32> = HashMap::new();
+    let mut outcome = MergeOutcome::default();
+    for (id, fields) in &b_orig {
+        if !o.contains_key(id) && a.get(id).is_some_and(|f| f != fields) {
+            renumber.insert(*id, next_id);
+            outcome.renumbered.push((*id, next_id));
+            next_id += 1;
+        }
+    }
+    // in their nodes, [old] meant their node: point it at the new ID
+    let b: BTreeMap<u32, Fields> = b_orig
+        .iter()
+        .map(|(id, f)| {
+            let mut f = f.clone();
+            f.description = rewrite_refs(&f.description, |r| match r {
+                Reference::Internal(rid) => renumber.get(rid).map(|n| format!("[{}]", n)),
+                Reference::External(..) => None,
+            });
+            (*renumber.get(id).unwrap_or(id), f)
+        })
+        .collect();
+
+    let ids: BTreeSet<u32> = o.keys().chain(a.keys()).chain(b.keys()).copied().collect();
+    let mut merged: BTreeMap<u32, Merged> = BTreeMap::new();
+    for id in ids {
+        // classification uses their fields before renumbering, content uses after
+        let changed_b = |base: &Fields| b_orig.get(&id) != Some(base);
+        let m = match (o.get(&id), a.get(&id), b.get(&id)) {
+            (Some(of), Some(af), Some(bf)) => {
+                let bf = if changed_b(of) { bf } else { of };
+                let r#type = merge_field(&of.r#type, &af.r#type, &bf.r#type);
+                let title = merge_field(&of.title, &af.title, &bf.title);
+                let description = merge_field(&of.description, &af.description, &bf.description);
+                match (r#type, title, description) {
+                    (Some(r#type), Some(title), Some(description)) => Merged::Node(Fields {
+                        r#type,
+                        title,
+                        description,
+                    }),
+                    _ => Merged::Conflict(Some(af.clone()), Some(of.clone()), Some(bf.clone())),
+                }
+            }
+            (Some(of), None, Some(bf)) if changed_b(of) => {
+                Merged::Conflict(None, Some(of.clone()), Some(bf.clone()))
+            }
+            (Some(of), Some(af), None) if af != of => {
+                Merged::Conflict(Some(af.clone()), Some(of.clone()), None)
+            }
+            (Some(_), _, _) => Merged::Deleted,
+            (None, Some(af), _) => Merged::Node(af.clone()),
+            (None, None, Some(bf)) => Merged::Node(bf.clone()),
+            (None, None, None) => continue,
+        };
+        if matches!(m, Merged::Conflict(..)) {
+            outcome.conflicts.push(id);
+        }
+        merged.insert(id, m);
+    }
+
+    // layout (prose and node order) comes from the side that changed the prose
+    let (o_prose, a_prose, b_prose) = (prose(&o_mm), prose(&a_mm), prose(&b_mm));
+    let theirs_layout = b_prose != o_prose && b_prose != a_prose && a_prose == o_prose;
+    outcome.text_conflict = b_prose != o_prose && b_prose != a_prose && a_prose != o_prose;
+    let (layout, layout_renumber) = if theirs_layout {
+        (&b_mm, renumber.clone())
+    } else {
+        (&a_mm, HashMap::new())
+    };
+
+    let marker = |c: char| c.to_string().repeat(marker_size);
+    let conflict_block =
+        |id: u32, ours: &Option<Fields>, base: &Option<Fields>, theirs: &Option<Fields>| {
+            let side = |f: &Option<Fields>| f.as_ref().map(|f| f.line(id));
+            let mut block = vec![format!("{} ours", marker('<'))];
+            block.extend(side(ours));
+            block.push(format!("{} base", marker('|')));
+            block.extend(side(base));
+            block.push(marker('='));
+            block.extend(side(theirs));
+            block.push(format!("{} theirs", marker('>')));
+            block
+        };
+    let emit = |merged: &mut BTreeMap<u32, Merged>, id: u32, out: &mut Vec<String>| match merged
+        .remove(&id)
+    {
+        Some(Merged::Node(f)) => {
+            out.push(f.line(id));
+            true
+        }
+        Some(Merged::Conflict(ours, base, theirs)) => {
+            out.extend(conflict_block(id, &ours, &base, &theirs));
+            true
+        }
+        Some(Merged::Deleted) | None => false,
+    };
+
+    let node_at: HashMap<usize, u32> = layout.nodes.iter().map(|n| (n.line_index, n.id)).collect();
+    let mut out: Vec<String> = Vec::new();
+    let mut dropped = false;
+    for (i, line) in layout.lines.iter().enumerate() {
+        if let Some(id) = node_at.get(&i) {
+            let id = *layout_renumber.get(id).unwrap_or(id);
+            dropped = !emit(&mut merged, id, &mut out);
+            continue;
+        }
+        // a removed node takes one separating blank line with it
+        if dropped && line.trim().is_empty() && out.last().is_none_or(|l| l.trim().is_empty()) {
+            dropped = false;
+            continue;
+        }
+        dropped = false;
+        out.push(line.clone());
+    }
+
+    let remaining: Vec<u32> = merged.keys().copied().collect();
+    for id in remaining {
+        let mut block = Vec::new();
+        if emit(&mut merged, id, &mut block) {
+            if out.last().is_some_and(|l| !l.trim().is_empty()) {
+                out.push(String::new());
+            }
+            out.extend(block);
+        }
+    }
+
+    if outcome.text_conflict {
+        let ours_only: Vec<&str> = a_prose
+            .iter()
+            .filter(|l| !b_prose.contains(l))
+            .copied()
+            .collect();
+        let theirs_only: Vec<&str> = b_prose
+            .iter()
+            .filter(|l| !a_prose.contains(l))
+            .copied()
+            .collect();
+        if out.last().is_some_and(|l| !l.trim().is_empty()) {
+            out.push(String::new());
+        }
+        out.push(format!("{} ours (text changed on both sides)", marker('<')));
+        out.extend(ours_only.iter().map(|l| l.to_string()));
+        out.push(marker('='));
+        out.extend(theirs_only.iter().map(|l| l.to_string()));
+        out.push(format!("{} theirs", marker('>')));
+    }
+
+    while out.last().is_some_and(|l| l.trim().is_empty()) {
+        out.pop();
+    }
+    let mut result = parse(&(out.join("\n") + "\n"))?;
+    result.normalize_spacing()?;
+    outcome.content = result.lines.join("\n") + "\n";
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "# Map\n\n[1] **AE: Auth** - login\n\n[2] **WF: Flow** - uses [1]\n";
+
+    #[test]
+    fn test_concurrent_adds_are_renumbered() -> Result<()> {
+        let ours = format!("{}\n[3] **DR: Ours** - see [1]\n", BASE);
+        let theirs = format!(
+            "{}\n[3] **DR: Theirs** - see [2]\n\n[4] **DR: Next** - after [3]\n",
+            BASE.replace("uses [1]", "uses [1] and [3]")
+        );
+        let m = merge(BASE, &ours, &theirs, 7)?;
+        assert!(m.conflicts.is_empty());
+        assert_eq!(m.renumbered, vec![(3, 5)]);
+        assert_eq!(
+            m.content,
+            "# Map\n\n[1] **AE: Auth** - login\n\n[2] **WF: Flow** - uses [1] and [5]\n\n\
+             [3] **DR: Ours** - see [1]\n\n[4] **DR: Next** - after [5]\n\n[5] **DR: Theirs** - see [2]\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_field_level_merge_and_deletes() -> Result<()> {
+        let ours = BASE.replace("AE: Auth", "AE: Authentication");
+        let theirs = BASE
+            .replace("- login", "- login and logout")
+            .replace("\n[2] **WF: Flow** - uses [1]\n", "");
+        let m = merge(BASE, &ours, &theirs, 7)?;
+        assert!(m.conflicts.is_empty());
+        assert_eq!(
+            m.content,
+            "# Map\n\n[1] **AE: Authentication** - login and logout\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_same_field_edits_conflict() -> Result<()> {
+        let ours = BASE.replace("- login", "- sign in");
+        let theirs = BASE.replace("- login", "- log on");
+        let m = merge(BASE, &ours, &theirs, 7)?;
+        assert_eq!(m.conflicts, vec![1]);
+        assert!(m.content.contains(
+            "<<<<<<< ours\n[1] **AE: Auth** - sign in\n||||||| base\n[1] **AE: Auth** - login\n\
+             =======\n[1] **AE: Auth** - log on\n>>>>>>> theirs\n"
+        ));
+        // node 2 is untouched
+        assert!(m.content.contains("[2] **WF: Flow** - uses [1]"));
+
+        // edit vs delete
+        let theirs = BASE.replace("\n[2] **WF: Flow** - uses [1]\n", "");
+        let ours = BASE.replace("uses [1]", "uses [1] daily");
+        let m = merge(BASE, &ours, &theirs, 7)?;
+        assert_eq!(m.conflicts, vec![2]);
+        assert!(m.content.contains("=======\n>>>>>>> theirs"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_prose_from_the_side_that_changed_it() -> Result<()> {
+        let ours = format!("{}\n[3] **DR: New** - x\n", BASE);
+        let theirs = BASE.replace("# Map", "# Project map\n\nIntro.");
+        let m = merge(BASE, &ours, &theirs, 7)?;
+        assert!(!m.text_conflict);
+        assert!(m.content.starts_with("# Project map\n\nIntro.\n\n[1]"));
+        assert!(m.content.ends_with("[3] **DR: New** - x\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,543 @@
+//! Search: ranked full-text search over node titles and descriptions
+//!
+//! This module provides:
+//! - `Query::parse`: words, `"quoted phrases"` and `title:` / `desc:` qualifiers
+//! - `search`: BM25 ranking per field with a title boost; unless exact, a word also
+//!   matches longer words containing it (`cache` in `MindmapCache`) and words within
+//!   one or two typos, at reduced weight. Nodes matching any clause are returned, those
+//!   matching more clauses first
+//! - `snippet` / `highlight` / `format_hit`: match context with hits wrapped in `«…»`
+
+use std::ops::Range;
+
+use crate::Node;
+
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+const TITLE_BOOST: f64 = 2.0;
+const PARTIAL_WEIGHT: f64 = 0.8;
+const FUZZY_WEIGHT: f64 = 0.5;
+/// Bytes of description shown around the first match
+pub const SNIPPET_WIDTH: usize = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Title,
+    Description,
+}
+
+/// One part of a query: a word, or a phrase of consecutive words
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clause {
+    /// Only this field, from a `title:` or `desc:` qualifier
+    pub field: Option<Field>,
+    pub words: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    pub clauses: Vec<Clause>,
+}
+
+impl Query {
+    /// `cache "lazy loading" title:parser desc:"file size"`; a bare word that splits into
+    /// several words (`src/cache.rs`) is a phrase
+    pub fn parse(query: &str) -> Query {
+        let mut clauses = Vec::new();
+        let mut rest = query.trim_start();
+        while !rest.is_empty() {
+            let mut field = None;
+            for (prefix, f) in [
+                ("title:", Field::Title),
+                ("desc:", Field::Description),
+                ("description:", Field::Description),
+            ] {
+                if rest.len() > prefix.len()
+                    && rest.is_char_boundary(prefix.len())
+                    && rest[..prefix.len()].eq_ignore_ascii_case(prefix)
+                {
+                    field = Some(f);
+                    rest = &rest[prefix.len()..];
+                    break;
+                }
+            }
+            let text;
+            if let Some(quoted) = rest.strip_prefix('"') {
+                let end = quoted.find('"').unwrap_or(quoted.len());
+                text = &quoted[..end];
+                rest = quoted.get(end + 1..).unwrap_or("");
+            } else {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                text = &rest[..end];
+                rest = &rest[end..];
+            }
+            let words: Vec<String> = words(text).into_iter().map(|(w, _)| w).collect();
+            if !words.is_empty() {
+                clauses.push(Clause { field, words });
+            }
+            rest = rest.trim_start();
+        }
+        Query { clauses }
+    }
+}
+
+/// How words are compared
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options {
+    pub case_sensitive: bool,
+    /// Whole words only: no partial or typo-tolerant matches
+    pub exact: bool,
+}
+
+/// A ranked result; `index` is the node's position in the searched sequence
+#[derive(Debug, Clone)]
+pub struct Hit<'a> {
+    pub index: usize,
+    pub node: &'a Node,
+    pub score: f64,
+    /// Byte ranges of matches in `raw_title` and `description`
+    pub title_matches: Vec<Range<usize>>,
+    pub description_matches: Vec<Range<usize>>,
+}
+
+/// Alphanumeric (and `_`) runs with their byte ranges
+fn words(text: &str) -> Vec<(String, Range<usize>)> {
+    let mut out = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        let is_word = c.is_alphanumeric() || c == '_';
+        match (start, is_word) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                out.push((text[s..i].to_string(), s..i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Optimal string alignment distance (edits with adjacent transpositions), or None
+/// when it exceeds `max`
+fn edit_distance(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    let mut prev2: Vec<usize> = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut cur = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            cur[j] = (prev[j] + 1).min(cur[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                cur[j] = cur[j].min(prev2[j - 2] + 1);
+            }
+        }
+        prev2 = std::mem::replace(&mut prev, cur);
+    }
+    let d = prev[b.len()];
+    (d <= max).then_some(d)
+}
+
+/// Weight of a query word matching a document word, and the matched byte range
+/// within `word` (the whole word for typo matches); None when it does not match
+fn word_match(query: &str, word: &str, exact: bool) -> Option<(f64, Range<usize>)> {
+    if query == word {
+        return Some((1.0, 0..word.len()));
+    }
+    if exact {
+        return None;
+    }
+    let q: Vec<char> = query.chars().collect();
+    if q.len() >= 3
+        && let Some(at) = word.find(query)
+    {
+        return Some((PARTIAL_WEIGHT, at..at + query.len()));
+    }
+    let max_edits = match q.len() {
+        0..=3 => return None,
+        4..=7 => 1,
+        _ => 2,
+    };
+    let w: Vec<char> = word.chars().collect();
+    edit_distance(&q, &w, max_edits).map(|_| (FUZZY_WEIGHT, 0..word.len()))
+}
+
+/// One word of a field as compared, with where it came from
+struct Word {
+    /// Lowercased unless the search is case-sensitive
+    text: String,
+    /// Byte range of the original word in the field
+    range: Range<usize>,
+    /// For each byte of `text`, the byte range within the original word of the
+    /// character it came from (lowercasing can change a character's length: `ẞ`, `İ`)
+    origin: Vec<Range<usize>>,
+}
+
+impl Word {
+    fn new(word: &str, range: Range<usize>, fold: bool) -> Self {
+        let mut text = String::with_capacity(word.len());
+        let mut origin = Vec::with_capacity(word.len());
+        for (i, c) in word.char_indices() {
+            let before = text.len();
+            if fold {
+                text.extend(c.to_lowercase());
+            } else {
+                text.push(c);
+            }
+            origin.extend(std::iter::repeat_n(
+                i..i + c.len_utf8(),
+                text.len() - before,
+            ));
+        }
+        Word {
+            text,
+            range,
+            origin,
+        }
+    }
+
+    /// A byte range of `text` as the byte range of the field it covers, widened to
+    /// whole original characters
+    fn original(&self, within: Range<usize>) -> Range<usize> {
+        let start = self
+            .origin
+            .get(within.start)
+            .map_or(self.range.len(), |o| o.start);
+        let end = within
+            .end
+            .checked_sub(1)
+            .map_or(start, |i| self.origin[i].end.max(start));
+        self.range.start + start..self.range.start + end
+    }
+}
+
+/// One field of one node, tokenised
+struct FieldText {
+    words: Vec<Word>,
+}
+
+impl FieldText {
+    fn new(text: &str, opts: &Options) -> Self {
+        let words = words(text)
+            .into_iter()
+            .map(|(w, range)| Word::new(&w, range, !opts.case_sensitive))
+            .collect();
+        FieldText { words }
+    }
+
+    /// Term frequency of `clause` (weighted by match quality) and the matched ranges
+    fn matches(&self, clause: &[String], opts: &Options) -> (f64, Vec<Range<usize>>) {
+        let mut tf = 0.0;
+        let mut ranges = Vec::new();
+        if let [query] = clause {
+            for word in &self.words {
+                if let Some((weight, within)) = word_match(query, &word.text, opts.exact) {
+                    tf += weight;
+                    ranges.push(word.original(within));
+                }
+            }
+        } else {
+            for window in self.words.windows(clause.len()) {
+                if window.iter().zip(clause).all(|(w, q)| w.text == *q) {
+                    tf += 1.0;
+                    ranges.push(window[0].range.start..window[clause.len() - 1].range.end);
+                }
+            }
+        }
+        (tf, ranges)
+    }
+}
+
+fn bm25(tf: f64, len: usize, avg_len: f64) -> f64 {
+    if tf == 0.0 {
+        return 0.0;
+    }
+    let norm = 1.0 - B + B * len as f64 / avg_len.max(1.0);
+    tf * (K1 + 1.0) / (tf + K1 * norm)
+}
+
+/// Rank `nodes` against `query`, best first (ties keep the input order)
+pub fn search<'a>(
+    nodes: impl IntoIterator<Item = &'a Node>,
+    query: &Query,
+    opts: &Options,
+) -> Vec<Hit<'a>> {
+    let docs: Vec<(&Node, FieldText, FieldText)> = nodes
+        .into_iter()
+        .map(|n| {
+            (
+                n,
+                FieldText::new(&n.raw_title, opts),
+                FieldText::new(&n.description, opts),
+            )
+        })
+        .collect();
+    if docs.is_empty() || query.clauses.is_empty() {
+        return Vec::new();
+    }
+    let n = docs.len() as f64;
+    let avg_title = docs.iter().map(|d| d.1.words.len()).sum::<usize>() as f64 / n;
+    let avg_desc = docs.iter().map(|d| d.2.words.len()).sum::<usize>() as f64 / n;
+    let clauses: Vec<(Option<Field>, Vec<String>)> = query
+        .clauses
+        .iter()
+        .map(|c| {
+            let words = if opts.case_sensitive {
+                c.words.clone()
+            } else {
+                c.words.iter().map(|w| w.to_lowercase()).collect()
+            };
+            (c.field, words)
+        })
+        .collect();
+
+    // per document, per clause: (title tf, title ranges, description tf, description ranges)
+    type ClauseMatch = (f64, Vec<Range<usize>>, f64, Vec<Range<usize>>);
+    let matches: Vec<Vec<ClauseMatch>> = docs
+        .iter()
+        .map(|(_, title, desc)| {
+            clauses
+                .iter()
+                .map(|(field, words)| {
+                    let (tt, tr) = if *field == Some(Field::Description) {
+                        (0.0, Vec::new())
+                    } else {
+                        title.matches(words, opts)
+                    };
+                    let (dt, dr) = if *field == Some(Field::Title) {
+                        (0.0, Vec::new())
+                    } else {
+                        desc.matches(words, opts)
+                    };
+                    (tt, tr, dt, dr)
+                })
+                .collect()
+        })
+        .collect();
+
+    let idf: Vec<f64> = (0..clauses.len())
+        .map(|c| {
+            let df = matches
+                .iter()
+                .filter(|m| m[c].0 > 0.0 || m[c].2 > 0.0)
+                .count() as f64;
+            (1.0 + (n - df + 0.5) / (df + 0.5)).ln()
+        })
+        .collect();
+
+    let mut hits: Vec<Hit<'a>> = docs
+        .iter()
+        .zip(matches)
+        .enumerate()
+        .filter_map(|(index, ((node, title, desc), m))| {
+            let mut score = 0.0;
+            let mut matched = 0;
+            let mut title_matches = Vec::new();
+            let mut description_matches = Vec::new();
+            for (c, (tt, tr, dt, dr)) in m.into_iter().enumerate() {
+                if tt == 0.0 && dt == 0.0 {
+                    continue;
+                }
+                matched += 1;
+                score += idf[c]
+                    * (TITLE_BOOST * bm25(tt, title.words.len(), avg_title)
+                        + bm25(dt, desc.words.len(), avg_desc));
+                title_matches.extend(tr);
+                description_matches.extend(dr);
+            }
+            if matched == 0 {
+                return None;
+            }
+            title_matches.sort_by_key(|r| r.start);
+            description_matches.sort_by_key(|r| r.start);
+            Some(Hit {
+                index,
+                node,
+                score: score * matched as f64 / clauses.len() as f64,
+                title_matches,
+                description_matches,
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.index.cmp(&b.index)));
+    hits
+}
+
+/// `text` with each (non-overlapping) range wrapped in `«…»`
+pub fn highlight(text: &str, ranges: &[Range<usize>]) -> String {
+    let mut out = String::with_capacity(text.len() + ranges.len() * 4);
+    let mut pos = 0;
+    for r in ranges {
+        if r.start < pos {
+            continue;
+        }
+        out.push_str(&text[pos..r.start]);
+        out.push('«');
+        out.push_str(&text[r.clone()]);
+        out.push('»');
+        pos = r.end;
+    }
+    out.push_str(&text[pos..]);
+    out
+}
+
+/// About `width` bytes of `text` around the first match, highlighted, with `…` where
+/// it was cut (the start of the text when nothing matched)
+pub fn snippet(text: &str, ranges: &[Range<usize>], width: usize) -> String {
+    if text.len() <= width {
+        return highlight(text, ranges);
+    }
+    let first = ranges.first().map_or(0, |r| r.start);
+    let floor = |mut i: usize| {
+        while !text.is_char_boundary(i) {
+            i -= 1;
+        }
+        i
+    };
+    let mut start = floor(first.saturating_sub(width / 3));
+    if start > 0 {
+        // begin at a word
+        start = text[start..first]
+            .find(char::is_whitespace)
+            .map_or(start, |i| start + i + 1);
+    }
+    let mut end = floor((start + width).min(text.len()));
+    if end < text.len()
+        && let Some(i) = text[start..end].rfind(char::is_whitespace)
+        && start + i > first
+    {
+        end = start + i;
+    }
+    let inside: Vec<Range<usize>> = ranges
+        .iter()
+        .filter(|r| r.start >= start && r.end <= end)
+        .map(|r| r.start - start..r.end - start)
+        .collect();
+    format!(
+        "{}{}{}",
+        if start > 0 { "…" } else { "" },
+        highlight(&text[start..end], &inside),
+        if end < text.len() { "…" } else { "" }
+    )
+}
+
+/// `[N] **title** - snippet` with matches highlighted
+pub fn format_hit(hit: &Hit) -> String {
+    format!(
+        "[{}] **{}** - {}",
+        hit.node.id,
+        highlight(&hit.node.raw_title, &hit.title_matches),
+        snippet(
+            &hit.node.description,
+            &hit.description_matches,
+            SNIPPET_WIDTH
+        )
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Mindmap;
+
+    fn ids(hits: &[Hit]) -> Vec<u32> {
+        hits.iter().map(|h| h.node.id).collect()
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let q = Query::parse(r#"cache "lazy  Loading" title:parser DESC:"file size" src/cache.rs"#);
+        let parsed: Vec<(Option<Field>, Vec<&str>)> = q
+            .clauses
+            .iter()
+            .map(|c| (c.field, c.words.iter().map(String::as_str).collect()))
+            .collect();
+        assert_eq!(
+            parsed,
+            vec![
+                (None, vec!["cache"]),
+                (None, vec!["lazy", "Loading"]),
+                (Some(Field::Title), vec!["parser"]),
+                (Some(Field::Description), vec!["file", "size"]),
+                (None, vec!["src", "cache", "rs"]),
+            ]
+        );
+        assert!(Query::parse("  \"\" ").clauses.is_empty());
+    }
+
+    #[test]
+    fn test_ranking() -> anyhow::Result<()> {
+        let mm = Mindmap::from_string(
+            "[1] **AE: Parser** - reads lines; see the cache [2]\n\n[2] **AE: Cache** - lazy loading of files, cache size limits\n\n[3] **WF: Release** - tag and publish the crate\n\n[4] **DOC: Caches notes** - loading order\n"
+                .to_string(),
+            "-".into(),
+        )?;
+        let opts = Options::default();
+        let search = |q: &str| search(&mm.nodes, &Query::parse(q), &opts);
+
+        // title matches (even partial ones) outrank description mentions
+        assert_eq!(ids(&search("cache")), vec![2, 4, 1]);
+        // typo tolerance, unless exact
+        assert_eq!(ids(&search("relase")), vec![3]);
+        let exact = Options {
+            exact: true,
+            ..opts
+        };
+        assert!(super::search(&mm.nodes, &Query::parse("relase"), &exact).is_empty());
+        // qualifiers and phrases
+        assert_eq!(ids(&search("title:cache")), vec![2, 4]);
+        assert_eq!(ids(&search("\"lazy loading\"")), vec![2]);
+        // nodes matching more clauses come first
+        assert_eq!(ids(&search("loading publish crate")), vec![3, 4, 2]);
+
+        let hits = search("cache size");
+        assert_eq!(hits[0].node.id, 2);
+        assert_eq!(hits[0].title_matches, vec![4..9]);
+        assert_eq!(
+            highlight(&hits[0].node.description, &hits[0].description_matches),
+            "lazy loading of files, «cache» «size» limits"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_non_ascii_case_folding() -> anyhow::Result<()> {
+        // lowercasing changes the byte length of ẞ (3 → 2) and the Kelvin sign (3 → 1)
+        let mm = Mindmap::from_string(
+            "[1] **AE: Store** - ẞẞcache layer\n\n[2] **AE: ẞẞCache** - \u{212A}elvin units\n"
+                .to_string(),
+            "-".into(),
+        )?;
+        let hits = super::search(&mm.nodes, &Query::parse("cache"), &Options::default());
+        let shown: Vec<String> = hits.iter().map(format_hit).collect();
+        assert_eq!(
+            shown,
+            vec![
+                "[2] **AE: ẞẞ«Cache»** - \u{212A}elvin units",
+                "[1] **AE: Store** - ẞẞ«cache» layer",
+            ]
+        );
+        let hits = super::search(&mm.nodes, &Query::parse("kelvn"), &Options::default());
+        assert_eq!(
+            highlight(&hits[0].node.description, &hits[0].description_matches),
+            "«\u{212A}elvin» units"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_snippet() {
+        let text = "The quick brown fox jumps over the lazy dog and keeps running far away";
+        let at = text.find("lazy").unwrap();
+        let lazy = at..at + 4;
+        assert_eq!(
+            snippet(text, std::slice::from_ref(&lazy), 30),
+            "…over the «lazy» dog and keeps…"
+        );
+        let all = 0..5;
+        assert_eq!(snippet("short", std::slice::from_ref(&all), 30), "«short»");
+    }
+}
@@ -0,0 +1,632 @@
+//! Shell: interactive REPL over an in-memory mindmap
+//!
+//! This module provides:
+//! - The CLI subcommand syntax for reads (`show`, `list`, `refs`, `links`, `search`,
+//!   `lint`, `orphans`, `type`, `relationships`, `graph`) parsed with clap
+//! - Mutations in batch-line syntax (`add`, `patch`, `put`, `delete`, `deprecate`,
+//!   `verify`) staged in memory until `commit` (or dropped with `rollback`)
+//! - Detection of changes to the file on disk (blake3), history and tab completion
+//!   of commands, node IDs and type prefixes (including `[types]` names and aliases)
+
+use anyhow::{Result, anyhow};
+use clap::Parser;
+use rustyline::{
+    Context, Editor, Helper,
+    completion::{Completer, Pair},
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::Hinter,
+    history::DefaultHistory,
+    validate::Validator,
+};
+use std::{fs, path::PathBuf};
+
+use crate::{
+    BatchOp, Commands, Mindmap, apply_batch_ops, blake3_hash, cmd_graph, cmd_links, cmd_lint,
+    cmd_list, cmd_orphans, cmd_refs, cmd_relationships, cmd_show, cmd_types,
+    config::{Config, TypeCompletion, TypesConfig},
+    ensure_unchanged,
+    export::format_reference,
+    parse_batch_op_line, search, split_title,
+};
+
+const MUTATIONS: [&str; 6] = ["add", "patch", "put", "delete", "deprecate", "verify"];
+const BUILTINS: [&str; 6] = ["commit", "rollback", "status", "reload", "help", "exit"];
+const READS: [&str; 10] = [
+    "show",
+    "list",
+    "refs",
+    "links",
+    "search",
+    "lint",
+    "orphans",
+    "type",
+    "relationships",
+    "graph",
+];
+
+const HELP: &str = "\
+Reads:     show ID | list [--type T] [--grep Q] | refs ID | links ID | search Q
+           lint | orphans | type [--of T] | relationships ID | graph ID
+Mutations: add --type T --title X --desc D | patch ID [--type T] [--title X] [--desc D]
+           put ID --line L | delete ID [--force] | deprecate ID --to N | verify ID
+Session:   status | commit | rollback | reload | help | exit";
+
+/// A read command line, parsed with the CLI's own subcommand definitions
+#[derive(Parser)]
+#[command(no_binary_name = true, disable_help_subcommand = true)]
+struct ReadLine {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+/// Result of one shell line
+#[derive(Debug, Default, PartialEq)]
+pub struct Outcome {
+    pub output: String,
+    pub exit: bool,
+}
+
+impl Outcome {
+    fn text(output: impl Into<String>) -> Self {
+        Outcome {
+            output: output.into(),
+            exit: false,
+        }
+    }
+}
+
+pub struct Shell {
+    /// Mutations apply here; `base` is what is on disk
+    pub staged: Mindmap,
+    base: Mindmap,
+    base_hash: String,
+    /// Staged mutation lines, in order
+    pub pending: Vec<String>,
+    /// Disk hash already reported as changed (to warn once)
+    warned_hash: Option<String>,
+    exit_requested: bool,
+    /// The map's `.mindmap.toml`, resolved once by the caller
+    config: Config,
+}
+
+impl Shell {
+    pub fn new(mm: Mindmap, config: Config) -> Result<Self> {
+        let base_hash = blake3_hash(&fs::read(&mm.path)?);
+        Ok(Shell {
+            staged: mm.clone(),
+            base: mm,
+            base_hash,
+            pending: Vec::new(),
+            warned_hash: None,
+            exit_requested: false,
+            config,
+        })
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        let content = fs::read_to_string(&self.base.path)?;
+        self.base_hash = blake3_hash(content.as_bytes());
+        self.base = Mindmap::from_string(content, self.base.path.clone())?;
+        self.staged = self.base.clone();
+        self.pending.clear();
+        self.warned_hash = None;
+        Ok(())
+    }
+
+    /// Check the file on disk: reload silently-safe (nothing staged) or warn once
+    pub fn check_disk(&mut self) -> Result<Option<String>> {
+        let current = blake3_hash(&fs::read(&self.base.path)?);
+        if current == self.base_hash {
+            return Ok(None);
+        }
+        if self.pending.is_empty() {
+            self.reload()?;
+            return Ok(Some("File changed on disk; reloaded".to_string()));
+        }
+        if self.warned_hash.as_deref() == Some(current.as_str()) {
+            return Ok(None);
+        }
+        self.warned_hash = Some(current);
+        Ok(Some(format!(
+            "File changed on disk; commit will be refused ({} staged change{}). Use rollback or reload",
+            self.pending.len(),
+            if self.pending.len() == 1 { "" } else { "s" }
+        )))
+    }
+
+    pub fn prompt(&self) -> String {
+        match self.pending.len() {
+            0 => "mindmap> ".to_string(),
+            n => format!("mindmap [{} staged]> ", n),
+        }
+    }
+
+    /// Execute one line
+    pub fn execute(&mut self, line: &str) -> Result<Outcome> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(Outcome::default());
+        }
+        let first = line.split_whitespace().next().unwrap_or_default();
+        if first != "exit" && first != "quit" {
+            self.exit_requested = false;
+        }
+
+        match first {
+            "help" | "?" => Ok(Outcome::text(HELP)),
+            "exit" | "quit" => {
+                if !self.pending.is_empty() && !self.exit_requested {
+                    self.exit_requested = true;
+                    return Ok(Outcome::text(format!(
+                        "{} staged change(s) not committed; commit, rollback, or exit again to discard",
+                        self.pending.len()
+                    )));
+                }
+                Ok(Outcome {
+                    output: String::new(),
+                    exit: true,
+                })
+            }
+            "status" => Ok(Outcome::text(if self.pending.is_empty() {
+                "No staged changes".to_string()
+            } else {
+                self.pending
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| format!("{:>3}. {}", i + 1, p))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })),
+            "rollback" => {
+                let n = self.pending.len();
+                self.staged = self.base.clone();
+                self.pending.clear();
+                Ok(Outcome::text(format!("Rolled back {} staged change(s)", n)))
+            }
+            "reload" => {
+                self.reload()?;
+                Ok(Outcome::text(format!(
+                    "Reloaded {} ({} nodes)",
+                    self.base.path.display(),
+                    self.base.nodes.len()
+                )))
+            }
+            "commit" => self.commit(),
+            _ if MUTATIONS.contains(&first) => self.stage(line),
+            _ => self.read(line),
+        }
+    }
+
+    fn stage(&mut self, line: &str) -> Result<Outcome> {
+        let op: BatchOp = parse_batch_op_line(line)?;
+        let result = apply_batch_ops(
+            &mut self.staged,
+            std::slice::from_ref(&op),
+            false,
+            &self.config.types,
+        )?;
+        self.pending.push(line.to_string());
+
+        let mut out = Vec::new();
+        for id in &result.added_ids {
+            if let Some(n) = self.staged.get_node(*id) {
+                out.push(format!("Staged add: {}", self.staged.lines[n.line_index]));
+            }
+        }
+        for id in &result.patched_ids {
+            if let Some(n) = self.staged.get_node(*id) {
+                out.push(format!(
+                    "Staged update: {}",
+                    self.staged.lines[n.line_index]
+                ));
+            }
+        }
+        for id in &result.deleted_ids {
+            out.push(format!("Staged delete: [{}]", id));
+        }
+        Ok(Outcome::text(out.join("\n")))
+    }
+
+    fn commit(&mut self) -> Result<Outcome> {
+        if self.pending.is_empty() {
+            return Ok(Outcome::text("Nothing to commit"));
+        }
+        ensure_unchanged(&self.base.path, &self.base_hash, "shell commit")?;
+        self.staged.save()?;
+        let n = self.pending.len();
+        self.reload()?;
+
+        let mut out = vec![format!("Committed {} change(s)", n)];
+        out.extend(
+            cmd_lint(&self.base, &self.config)?
+                .iter()
+                .map(|f| f.describe()),
+        );
+        Ok(Outcome::text(out.join("\n")))
+    }
+
+    fn read(&mut self, line: &str) -> Result<Outcome> {
+        let words = shell_words::split(line)?;
+        let parsed =
+            ReadLine::try_parse_from(words).map_err(|e| anyhow!("{}", e.to_string().trim_end()))?;
+        let mm = &self.staged;
+        let not_found = |id: u32| anyhow!("Node [{}] not found", id);
+
+        let lines: Vec<String> = match parsed.command {
+            Commands::Show {
+                id,
+                follow,
+                desc,
+                code,
+            } => {
+                reject_follow(follow)?;
+                if code {
+                    return Err(anyhow!("--code is not available in the shell"));
+                }
+                let node = mm.get_node(id).ok_or_else(|| not_found(id))?;
+                if desc {
+                    vec![node.description.clone()]
+                } else {
+                    vec![cmd_show(mm, id)]
+                }
+            }
+            Commands::List {
+                r#type,
+                grep,
+                case_sensitive,
+                exact_match,
+                regex_mode,
+            } => cmd_list(
+                mm,
+                r#type
+                    .as_deref()
+                    .map(|t| self.config.types.canonical(t).unwrap_or(t)),
+                grep.as_deref(),
+                case_sensitive,
+                exact_match,
+                regex_mode,
+            ),
+            Commands::Search {
+                query,
+                case_sensitive,
+                exact_match,
+                regex_mode,
+                follow,
+                limit,
+            } => {
+                reject_follow(follow)?;
+                let mut lines = if regex_mode {
+                    cmd_list(mm, None, Some(&query), case_sensitive, false, true)
+                } else {
+                    let opts = search::Options {
+                        case_sensitive,
+                        exact: exact_match,
+                    };
+                    search::search(&mm.nodes, &search::Query::parse(&query), &opts)
+                        .iter()
+                        .map(search::format_hit)
+                        .collect()
+                };
+                lines.truncate(limit.unwrap_or(usize::MAX));
+                lines
+            }
+            Commands::Refs { id, follow } => {
+                reject_follow(follow)?;
+                cmd_refs(mm, id)
+            }
+            Commands::Links { id, follow } => {
+                reject_follow(follow)?;
+                cmd_links(mm, id)
+                    .ok_or_else(|| not_found(id))?
+                    .iter()
+                    .map(format_reference)
+                    .collect()
+            }
+            Commands::Relationships { id, follow } => {
+                reject_follow(follow)?;
+                let (incoming, outgoing) = cmd_relationships(mm, id)?;
+                let incoming: Vec<String> = incoming.iter().map(|i| format!("[{}]", i)).collect();
+                let outgoing: Vec<String> = outgoing.iter().map(format_reference).collect();
+                vec![
+                    format!("Incoming: {}", incoming.join(" ")),
+                    format!("Outgoing: {}", outgoing.join(" ")),
+                ]
+            }
+            Commands::Graph { id, follow } => {
+                reject_follow(follow)?;
+                vec![cmd_graph(mm, id)?]
+            }
+            Commands::Lint { fix, .. } => {
+                if fix {
+                    return Err(anyhow!("lint --fix is not available in the shell"));
+                }
+                let findings = cmd_lint(mm, &self.config)?;
+                if findings.is_empty() {
+                    vec!["Lint OK".to_string()]
+                } else {
+                    findings.iter().map(|f| f.describe()).collect()
+                }
+            }
+            Commands::Orphans { with_descriptions } => {
+                cmd_orphans(mm, with_descriptions, &self.config)?
+            }
+            Commands::Type { of } => cmd_types(mm, of.as_deref(), &self.config.types)?,
+            _ => {
+                return Err(anyhow!(
+                    "'{}' is not available in the shell (type 'help')",
+                    line.split_whitespace().next().unwrap_or_default()
+                ));
+            }
+        };
+        Ok(Outcome::text(lines.join("\n")))
+    }
+}
+
+fn reject_follow(follow: bool) -> Result<()> {
+    if follow {
+        return Err(anyhow!("--follow is not available in the shell"));
+    }
+    Ok(())
+}
+
+/// Tab completion of commands, node IDs and type prefixes
+#[derive(Default)]
+pub struct ShellHelper {
+    ids: Vec<(u32, String)>,
+    types: Vec<TypeCompletion>,
+}
+
+impl ShellHelper {
+    /// Re-read node IDs and type prefixes (those in use plus the `[types]` registry)
+    pub fn refresh(&mut self, mm: &Mindmap, types: &TypesConfig) {
+        self.ids = mm
+            .nodes
+            .iter()
+            .map(|n| (n.id, n.raw_title.clone()))
+            .collect();
+        self.types = types.completions(mm.nodes.iter().filter_map(|n| split_title(&n.raw_title).0));
+    }
+
+    /// Candidates for the word ending at `pos`
+    pub fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<Pair>) {
+        let before = &line[..pos];
+        let start = before
+            .rfind(char::is_whitespace)
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let word = &before[start..];
+        let previous = before[..start].split_whitespace().last();
+
+        let pair = |display: String, replacement: String| Pair {
+            display,
+            replacement,
+        };
+        let candidates = match previous {
+            None => READS
+                .iter()
+                .chain(&MUTATIONS)
+                .chain(&BUILTINS)
+                .filter(|c| c.starts_with(word))
+                .map(|c| pair(c.to_string(), c.to_string()))
+                .collect(),
+            Some("--type" | "--of") => self
+                .types
+                .iter()
+                .filter(|t| t.label.starts_with(word))
+                .map(|t| {
+                    let display = if t.detail.is_empty() {
+                        t.label.clone()
+                    } else {
+                        format!("{}  {}", t.label, t.detail)
+                    };
+                    pair(display, t.prefix.clone())
+                })
+                .collect(),
+            Some(_) if word.chars().all(|c| c.is_ascii_digit()) => self
+                .ids
+                .iter()
+                .filter(|(id, _)| id.to_string().starts_with(word))
+                .map(|(id, title)| pair(format!("{}  {}", id, title), id.to_string()))
+                .collect(),
+            Some(_) => Vec::new(),
+        };
+        (start, candidates)
+    }
+}
+
+impl Completer for ShellHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        Ok(self.candidates(line, pos))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".mindmap_cli_history"))
+}
+
+/// Run the REPL until `exit` or end of input
+pub fn run(mm: Mindmap, config: Config) -> Result<()> {
+    let mut shell = Shell::new(mm, config)?;
+    let mut rl: Editor<ShellHelper, DefaultHistory> = Editor::new()?;
+    let mut helper = ShellHelper::default();
+    helper.refresh(&shell.staged, &shell.config.types);
+    rl.set_helper(Some(helper));
+    let history = history_path();
+    if let Some(h) = &history {
+        let _ = rl.load_history(h);
+    }
+    eprintln!(
+        "mindmap shell: {} ({} nodes). Type 'help' for commands.",
+        shell.base.path.display(),
+        shell.base.nodes.len()
+    );
+
+    loop {
+        let line = match rl.readline(&shell.prompt()) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => {
+                if !shell.pending.is_empty() {
+                    eprintln!("Discarded {} staged change(s)", shell.pending.len());
+                }
+                break;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if !line.trim().is_empty() {
+            let _ = rl.add_history_entry(line.as_str());
+        }
+
+        match shell.check_disk() {
+            Ok(Some(notice)) => eprintln!("{}", notice),
+            Ok(None) => {}
+            Err(e) => eprintln!("Error: {}", e),
+        }
+        match shell.execute(&line) {
+            Ok(outcome) => {
+                if !outcome.output.is_empty() {
+                    println!("{}", outcome.output);
+                }
+                if outcome.exit {
+                    break;
+                }
+            }
+            Err(e) => eprintln!("Error: {}", e),
+        }
+        if let Some(h) = rl.helper_mut() {
+            h.refresh(&shell.staged, &shell.config.types);
+        }
+    }
+
+    if let Some(h) = &history {
+        let _ = rl.save_history(h);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shell(temp: &tempfile::TempDir) -> Result<Shell> {
+        let path = temp.path().join("MINDMAP.md");
+        fs::write(
+            &path,
+            "[1] **AE: Auth** - login\n\n[2] **WF: Flow** - uses [1]\n",
+        )?;
+        let config = Config::for_mindmap(&path)?;
+        Shell::new(Mindmap::load(path)?, config)
+    }
+
+    #[test]
+    fn test_reads_use_cli_syntax() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let mut s = shell(&temp)?;
+        assert!(s.execute("show 1")?.output.contains("Referred to by: [2]"));
+        assert_eq!(
+            s.execute("list --type WF")?.output,
+            "[2] **WF: Flow** - uses [1]"
+        );
+        assert_eq!(s.execute("links 2")?.output, "[1]");
+        assert!(s.execute("show 1 --follow").is_err());
+        assert!(s.execute("tui").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stage_commit_rollback() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let path = temp.path().join("MINDMAP.md");
+        let mut s = shell(&temp)?;
+
+        s.execute("add --type DR --title \"New one\" --desc \"see [1]\"")?;
+        assert_eq!(s.prompt(), "mindmap [1 staged]> ");
+        // reads see staged changes, the file does not
+        assert!(s.execute("refs 1")?.output.contains("DR: New one"));
+        assert!(!fs::read_to_string(&path)?.contains("New one"));
+
+        s.execute("rollback")?;
+        assert!(s.execute("show 3").is_err());
+
+        s.execute("patch 1 --desc \"logins\"")?;
+        assert!(
+            s.execute("commit")?
+                .output
+                .starts_with("Committed 1 change")
+        );
+        assert!(fs::read_to_string(&path)?.contains("[1] **AE: Auth** - logins"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_disk_changes_and_exit() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let path = temp.path().join("MINDMAP.md");
+        let mut s = shell(&temp)?;
+
+        // nothing staged: reload
+        fs::write(&path, "[1] **AE: Auth** - changed\n")?;
+        assert!(s.check_disk()?.unwrap().contains("reloaded"));
+        assert!(s.execute("show 1")?.output.contains("changed"));
+
+        // staged changes: warn once, refuse commit
+        s.execute("verify 1")?;
+        fs::write(&path, "[1] **AE: Auth** - changed again\n")?;
+        assert!(s.check_disk()?.unwrap().contains("commit will be refused"));
+        assert_eq!(s.check_disk()?, None);
+        assert!(s.execute("commit").is_err());
+
+        assert!(!s.execute("exit")?.exit);
+        assert!(s.execute("exit")?.exit);
+        Ok(())
+    }
+
+    #[test]
+    fn test_completion() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        fs::write(
+            temp.path().join(".mindmap.toml"),
+            "[types.DR]\ndescription = \"Decision\"\naliases = [\"decision\"]\n",
+        )?;
+        let s = shell(&temp)?;
+        let mut h = ShellHelper::default();
+        h.refresh(&s.staged, &s.config.types);
+
+        let (start, c) = h.candidates("sh", 2);
+        assert_eq!(start, 0);
+        assert_eq!(c[0].replacement, "show");
+
+        let (_, c) = h.candidates("show ", 5);
+        assert_eq!(c.len(), 2);
+        assert_eq!(c[0].display, "1  AE: Auth");
+
+        let (_, c) = h.candidates("list --type W", 13);
+        assert_eq!(c[0].replacement, "WF");
+
+        // registry prefixes and aliases, even when no node uses them yet
+        let (_, c) = h.candidates("list --type D", 13);
+        assert_eq!(c[0].display, "DR  Decision");
+        let (_, c) = h.candidates("add --type dec", 14);
+        assert_eq!(c[0].display, "decision  alias of DR");
+        assert_eq!(c[0].replacement, "DR");
+        Ok(())
+    }
+}
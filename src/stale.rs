@@ -0,0 +1,179 @@
+//! Stale: nodes whose last verification (or change) is too old
+//!
+//! This module provides:
+//! - `(verify YYYY-MM-DD)` tags: finding the last one in a description, which
+//!   `verify` refreshes in place
+//! - `Age`: thresholds such as `90d`, `12w`, `6m` or `1y`, for `stale --older-than` and
+//!   `[stale.types]` in `.mindmap.toml`
+//! - `report`: nodes whose last verification or last git modification (whichever is
+//!   newer) is older than a threshold, oldest first
+
+use anyhow::{Result, anyhow};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::{collections::HashMap, ops::Range, str::FromStr, sync::LazyLock};
+
+use crate::{Mindmap, split_title};
+
+/// A threshold in days, written as `<N>d`, `<N>w`, `<N>m` (30 days) or `<N>y` (365 days)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(try_from = "String")]
+pub struct Age {
+    pub days: i64,
+}
+
+impl FromStr for Age {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (n, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+        let n: i64 = n
+            .parse()
+            .map_err(|_| anyhow!("Invalid age '{}' (expected e.g. 90d, 12w, 6m, 1y)", s))?;
+        let per_unit = match unit {
+            "d" | "" => 1,
+            "w" => 7,
+            "m" => 30,
+            "y" => 365,
+            _ => return Err(anyhow!("Invalid age unit in '{}' (use d, w, m or y)", s)),
+        };
+        let days = n
+            .checked_mul(per_unit)
+            .ok_or_else(|| anyhow!("Age '{}' is too large", s))?;
+        Ok(Age { days })
+    }
+}
+
+impl TryFrom<String> for Age {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+static VERIFY_TAG: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"\(verify (\d{4}-\d{2}-\d{2})\)").unwrap());
+
+/// Byte range and date of the last `(verify YYYY-MM-DD)` tag in a description
+pub fn verify_tag(description: &str) -> Option<(Range<usize>, NaiveDate)> {
+    VERIFY_TAG.captures_iter(description).last().and_then(|c| {
+        let date = NaiveDate::parse_from_str(&c[1], "%Y-%m-%d").ok()?;
+        Some((c.get(0)?.range(), date))
+    })
+}
+
+pub fn today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
+
+/// One node in the staleness report
+#[derive(Debug, Serialize)]
+pub struct StaleNode {
+    pub id: u32,
+    pub raw_title: String,
+    pub verified: Option<NaiveDate>,
+    pub modified: Option<NaiveDate>,
+    /// Days since the newer of `verified` and `modified`; None when neither is known
+    pub age_days: Option<i64>,
+}
+
+/// Nodes last verified or modified more than `older_than` before `today`, oldest
+/// first (nodes with neither date lead). `modified` holds last git modification dates;
+/// `types` restricts the report to those type prefixes.
+pub fn report(
+    mm: &Mindmap,
+    older_than: Age,
+    today: NaiveDate,
+    modified: &HashMap<u32, NaiveDate>,
+    types: &[String],
+) -> Vec<StaleNode> {
+    let mut out: Vec<StaleNode> = mm
+        .nodes
+        .iter()
+        .filter(|n| {
+            types.is_empty()
+                || split_title(&n.raw_title)
+                    .0
+                    .is_some_and(|t| types.iter().any(|x| x.eq_ignore_ascii_case(t)))
+        })
+        .filter_map(|n| {
+            let verified = verify_tag(&n.description).map(|(_, d)| d);
+            let modified = modified.get(&n.id).copied();
+            let age_days = verified.max(modified).map(|d| (today - d).num_days());
+            if age_days.is_some_and(|a| a <= older_than.days) {
+                return None;
+            }
+            Some(StaleNode {
+                id: n.id,
+                raw_title: n.raw_title.clone(),
+                verified,
+                modified,
+                age_days,
+            })
+        })
+        .collect();
+    out.sort_by_key(|s| std::cmp::Reverse(s.age_days.unwrap_or(i64::MAX)));
+    out
+}
+
+pub fn format_report(items: &[StaleNode]) -> Vec<String> {
+    let date = |d: Option<NaiveDate>| d.map_or("never".to_string(), |d| d.to_string());
+    items
+        .iter()
+        .map(|s| {
+            format!(
+                "[{}] **{}** - {} (verified {}, modified {})",
+                s.id,
+                s.raw_title,
+                s.age_days
+                    .map_or("no date".to_string(), |a| format!("{} days", a)),
+                date(s.verified),
+                date(s.modified)
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_age_and_verify_tag() -> Result<()> {
+        assert_eq!("90d".parse::<Age>()?.days, 90);
+        assert_eq!("2w".parse::<Age>()?.days, 14);
+        assert_eq!("1y".parse::<Age>()?.days, 365);
+        assert!("soon".parse::<Age>().is_err());
+        assert!("3h".parse::<Age>().is_err());
+        assert!("99999999999999999y".parse::<Age>().is_err());
+
+        let desc = "x (verify 2020-01-01) y (verify 2021-02-03)";
+        let (range, date) = verify_tag(desc).unwrap();
+        assert_eq!(&desc[range], "(verify 2021-02-03)");
+        assert_eq!(date, NaiveDate::from_ymd_opt(2021, 2, 3).unwrap());
+        assert!(verify_tag("(verify soon)").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_report_sorted_by_age() -> Result<()> {
+        let mm = Mindmap::from_string(
+            "[1] **AE: A** - a (verify 2026-01-01)\n\n[2] **AE: B** - b\n\n[3] **WF: C** - c (verify 2025-01-01)\n\n[4] **AE: D** - d (verify 2026-10-01)\n"
+                .to_string(),
+            "-".into(),
+        )?;
+        let today = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        let modified = HashMap::from([(1, NaiveDate::from_ymd_opt(2026, 9, 1).unwrap())]);
+
+        let items = report(&mm, "30d".parse()?, today, &modified, &[]);
+        let ids: Vec<u32> = items.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![2, 3, 1]);
+        assert_eq!(items[2].age_days, Some(47));
+
+        let items = report(&mm, "30d".parse()?, today, &modified, &["wf".to_string()]);
+        assert_eq!(items.len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,718 @@
+//! TUI: interactive terminal browser for mindmaps
+//!
+//! This module provides:
+//! - A searchable node list (filters via `cmd_list`; `type:AE` selects a type prefix)
+//! - A detail pane plus incoming/outgoing reference lists that can be followed
+//!   (keyboard or mouse), crossing into external files through `MindmapCache`
+//! - Back/forward history and inline editing through the `cmd_edit` flow
+//!
+//! `App` holds all state and is independent of the terminal, so navigation is
+//! testable; `draw` renders it and `run` drives the event loop.
+
+use anyhow::{Result, anyhow};
+use ratatui::{
+    Frame,
+    crossterm::{
+        event::{
+            self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+            KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+        },
+        execute,
+    },
+    layout::{Constraint, Layout, Position, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    Mindmap, Reference, blake3_hash,
+    cache::MindmapCache,
+    cmd_edit, cmd_list,
+    config::{Config, TypesConfig},
+    ensure_unchanged, parse_node_line, referring_nodes,
+};
+
+/// A node in a specific file (history entry)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub path: PathBuf,
+    pub id: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Focus {
+    List,
+    Incoming,
+    Outgoing,
+}
+
+/// What the event loop should do after a key press
+#[derive(Debug, PartialEq)]
+pub enum Action {
+    None,
+    Quit,
+    Edit(u32),
+}
+
+/// Screen areas of the last frame, for mouse hit-testing
+#[derive(Debug, Default, Clone, Copy)]
+struct Areas {
+    list: Rect,
+    list_offset: usize,
+    incoming: Rect,
+    outgoing: Rect,
+}
+
+pub struct App {
+    /// Loaded files by (canonical) path
+    files: HashMap<PathBuf, Mindmap>,
+    cache: MindmapCache,
+    /// `[types]` of the opened map, for resolving `type:` aliases
+    types: TypesConfig,
+    current: PathBuf,
+    pub query: String,
+    pub searching: bool,
+    /// Node IDs of the current file matching the query
+    pub items: Vec<u32>,
+    pub selected: usize,
+    pub focus: Focus,
+    pub incoming_sel: usize,
+    pub outgoing_sel: usize,
+    back: Vec<Location>,
+    forward: Vec<Location>,
+    pub status: String,
+    areas: Areas,
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+impl App {
+    pub fn new(mm: Mindmap, config: Config) -> Result<Self> {
+        let current = canonical(&mm.path);
+        let workspace = current
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+        let cache = config.cache.cache(&workspace);
+        let mut app = App {
+            files: HashMap::from([(current.clone(), mm)]),
+            cache,
+            types: config.types,
+            current,
+            query: String::new(),
+            searching: false,
+            items: Vec::new(),
+            selected: 0,
+            focus: Focus::List,
+            incoming_sel: 0,
+            outgoing_sel: 0,
+            back: Vec::new(),
+            forward: Vec::new(),
+            status: String::new(),
+            areas: Areas::default(),
+        };
+        app.refilter();
+        Ok(app)
+    }
+
+    pub fn mm(&self) -> &Mindmap {
+        &self.files[&self.current]
+    }
+
+    pub fn current_id(&self) -> Option<u32> {
+        self.items.get(self.selected).copied()
+    }
+
+    pub fn location(&self) -> Option<Location> {
+        self.current_id().map(|id| Location {
+            path: self.current.clone(),
+            id,
+        })
+    }
+
+    /// Re-run the list filter; `type:XX` tokens select a type prefix, the rest is grep text
+    pub fn refilter(&mut self) {
+        let keep = self.current_id();
+        let mut type_filter = None;
+        let mut words = Vec::new();
+        for word in self.query.split_whitespace() {
+            match word.strip_prefix("type:") {
+                Some(t) if !t.is_empty() => type_filter = Some(t.to_string()),
+                _ => words.push(word),
+            }
+        }
+        let grep = words.join(" ");
+        let grep = (!grep.is_empty()).then_some(grep.as_str());
+
+        let type_filter = type_filter
+            .as_deref()
+            .map(|t| self.types.canonical(t).unwrap_or(t));
+        let mm = self.mm();
+        self.items = cmd_list(mm, type_filter, grep, false, false, false)
+            .iter()
+            .filter_map(|l| parse_node_line(l, 0).ok())
+            .map(|n| n.id)
+            .collect();
+        self.selected = keep
+            .and_then(|id| self.items.iter().position(|i| *i == id))
+            .unwrap_or(0);
+        self.incoming_sel = 0;
+        self.outgoing_sel = 0;
+    }
+
+    /// Nodes of the current file referring to the selected node
+    pub fn incoming(&self) -> Vec<u32> {
+        self.current_id()
+            .map(|id| referring_nodes(self.mm(), id).map(|n| n.id).collect())
+            .unwrap_or_default()
+    }
+
+    /// References made by the selected node
+    pub fn outgoing(&self) -> Vec<Reference> {
+        self.current_id()
+            .and_then(|id| self.mm().get_node(id))
+            .map(|n| n.references.clone())
+            .unwrap_or_default()
+    }
+
+    /// Show `loc`, loading its file if needed (without touching history)
+    fn show(&mut self, loc: &Location) -> Result<()> {
+        if !self.files.contains_key(&loc.path) {
+            self.files
+                .insert(loc.path.clone(), Mindmap::load(loc.path.clone())?);
+        }
+        if self.files[&loc.path].get_node(loc.id).is_none() {
+            return Err(anyhow!(
+                "Node [{}] not found in {}",
+                loc.id,
+                loc.path.display()
+            ));
+        }
+        self.current = loc.path.clone();
+        self.refilter();
+        if !self.items.contains(&loc.id) {
+            // the target is hidden by the current filter
+            self.query.clear();
+            self.refilter();
+        }
+        self.selected = self.items.iter().position(|i| *i == loc.id).unwrap_or(0);
+        self.incoming_sel = 0;
+        self.outgoing_sel = 0;
+        Ok(())
+    }
+
+    /// Navigate to `loc`, recording the current node in the back history
+    pub fn goto(&mut self, loc: Location) -> Result<()> {
+        let here = self.location();
+        self.show(&loc)?;
+        if let Some(here) = here
+            && here != loc
+        {
+            self.back.push(here);
+            self.forward.clear();
+        }
+        Ok(())
+    }
+
+    /// Follow a reference of the current file (external files go through the cache)
+    pub fn follow(&mut self, reference: &Reference) -> Result<()> {
+        let loc = match reference {
+            Reference::Internal(id) => Location {
+                path: self.current.clone(),
+                id: *id,
+            },
+            Reference::External(id, rel) => {
+                let base = self.mm().path.clone();
+                let path = self.cache.resolve_path(&base, rel)?;
+                if !self.files.contains_key(&path) {
+                    let ext = self.cache.load(&base, rel, &HashSet::new())?.clone();
+                    self.files.insert(path.clone(), ext);
+                }
+                Location { path, id: *id }
+            }
+        };
+        self.goto(loc)
+    }
+
+    pub fn go_back(&mut self) -> Result<()> {
+        let Some(loc) = self.back.pop() else {
+            return Ok(());
+        };
+        if let Some(here) = self.location() {
+            self.forward.push(here);
+        }
+        self.show(&loc)
+    }
+
+    pub fn go_forward(&mut self) -> Result<()> {
+        let Some(loc) = self.forward.pop() else {
+            return Ok(());
+        };
+        if let Some(here) = self.location() {
+            self.back.push(here);
+        }
+        self.show(&loc)
+    }
+
+    /// Reload every loaded file from disk
+    pub fn reload(&mut self) -> Result<()> {
+        self.cache.clear();
+        for (path, mm) in self.files.iter_mut() {
+            *mm = Mindmap::load(path.clone())?;
+        }
+        self.refilter();
+        Ok(())
+    }
+
+    /// Edit a node of the current file with $EDITOR (the `edit` command flow)
+    pub fn edit(&mut self, id: u32) -> Result<()> {
+        let path = self.current.clone();
+        let content = fs::read_to_string(&path)?;
+        let base_hash = blake3_hash(content.as_bytes());
+        let mut mm = Mindmap::from_string(content, path.clone())?;
+
+        let editor = Config::for_mindmap(&path)?.editor();
+        cmd_edit(&mut mm, id, &editor)?;
+        ensure_unchanged(&path, &base_hash, "edit")?;
+        mm.save()?;
+
+        self.files.insert(path, mm);
+        self.refilter();
+        Ok(())
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let len = match self.focus {
+            Focus::List => self.items.len(),
+            Focus::Incoming => self.incoming().len(),
+            Focus::Outgoing => self.outgoing().len(),
+        };
+        let sel = match self.focus {
+            Focus::List => &mut self.selected,
+            Focus::Incoming => &mut self.incoming_sel,
+            Focus::Outgoing => &mut self.outgoing_sel,
+        };
+        if len > 0 {
+            *sel = (*sel as isize + delta).clamp(0, len as isize - 1) as usize;
+        }
+        if self.focus == Focus::List {
+            self.incoming_sel = 0;
+            self.outgoing_sel = 0;
+        }
+    }
+
+    /// Enter: from the list, jump into the outgoing refs; from a ref pane, follow it
+    fn activate(&mut self) -> Result<()> {
+        match self.focus {
+            Focus::List => {
+                self.focus = if self.outgoing().is_empty() {
+                    Focus::Incoming
+                } else {
+                    Focus::Outgoing
+                };
+                Ok(())
+            }
+            Focus::Incoming => match self.incoming().get(self.incoming_sel) {
+                Some(id) => self.follow(&Reference::Internal(*id)),
+                None => Ok(()),
+            },
+            Focus::Outgoing => match self.outgoing().get(self.outgoing_sel).cloned() {
+                Some(r) => self.follow(&r),
+                None => Ok(()),
+            },
+        }
+    }
+
+    fn report(&mut self, result: Result<()>) {
+        self.status = match result {
+            Ok(()) => String::new(),
+            Err(e) => e.to_string(),
+        };
+    }
+
+    pub fn on_key(&mut self, key: KeyEvent) -> Action {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Action::Quit;
+        }
+        if self.searching {
+            match key.code {
+                KeyCode::Enter => self.searching = false,
+                KeyCode::Esc => {
+                    self.searching = false;
+                    self.query.clear();
+                    self.refilter();
+                }
+                KeyCode::Backspace => {
+                    self.query.pop();
+                    self.refilter();
+                }
+                KeyCode::Char(c) => {
+                    self.query.push(c);
+                    self.refilter();
+                }
+                _ => {}
+            }
+            return Action::None;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => return Action::Quit,
+            KeyCode::Char('/') => {
+                self.searching = true;
+                self.focus = Focus::List;
+            }
+            KeyCode::Esc => self.focus = Focus::List,
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::PageDown => self.move_selection(10),
+            KeyCode::PageUp => self.move_selection(-10),
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Focus::List => Focus::Incoming,
+                    Focus::Incoming => Focus::Outgoing,
+                    Focus::Outgoing => Focus::List,
+                }
+            }
+            KeyCode::BackTab => {
+                self.focus = match self.focus {
+                    Focus::List => Focus::Outgoing,
+                    Focus::Incoming => Focus::List,
+                    Focus::Outgoing => Focus::Incoming,
+                }
+            }
+            KeyCode::Enter => {
+                let r = self.activate();
+                self.report(r);
+            }
+            KeyCode::Left | KeyCode::Backspace | KeyCode::Char('b') => {
+                let r = self.go_back();
+                self.report(r);
+            }
+            KeyCode::Right | KeyCode::Char('f') => {
+                let r = self.go_forward();
+                self.report(r);
+            }
+            KeyCode::Char('r') => {
+                let r = self.reload();
+                self.report(r);
+            }
+            KeyCode::Char('e') => {
+                if let Some(id) = self.current_id() {
+                    return Action::Edit(id);
+                }
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    pub fn on_mouse(&mut self, ev: MouseEvent) {
+        let pos = Position::new(ev.column, ev.row);
+        match ev.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let a = self.areas;
+                if a.list.contains(pos) {
+                    let row = a.list_offset + (pos.y - a.list.y) as usize;
+                    if row < self.items.len() {
+                        self.focus = Focus::List;
+                        self.selected = row;
+                        self.incoming_sel = 0;
+                        self.outgoing_sel = 0;
+                    }
+                } else if a.incoming.contains(pos) {
+                    self.focus = Focus::Incoming;
+                    self.incoming_sel = (pos.y - a.incoming.y) as usize;
+                    let r = self.activate();
+                    self.report(r);
+                } else if a.outgoing.contains(pos) {
+                    self.focus = Focus::Outgoing;
+                    self.outgoing_sel = (pos.y - a.outgoing.y) as usize;
+                    let r = self.activate();
+                    self.report(r);
+                }
+            }
+            MouseEventKind::ScrollDown => self.move_selection(1),
+            MouseEventKind::ScrollUp => self.move_selection(-1),
+            _ => {}
+        }
+    }
+}
+
+fn pane<'a>(title: String, focused: bool) -> Block<'a> {
+    let block = Block::default().borders(Borders::ALL).title(title);
+    if focused {
+        block.border_style(Style::default().add_modifier(Modifier::BOLD))
+    } else {
+        block
+    }
+}
+
+fn highlight(focused: bool) -> Style {
+    if focused {
+        Style::default().add_modifier(Modifier::REVERSED)
+    } else {
+        Style::default().add_modifier(Modifier::BOLD)
+    }
+}
+
+/// Render the whole screen
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let [search_area, main, help_area] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(6),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [left, right] =
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(main);
+    let [detail_area, incoming_area, outgoing_area] = Layout::vertical([
+        Constraint::Percentage(50),
+        Constraint::Percentage(25),
+        Constraint::Percentage(25),
+    ])
+    .areas(right);
+
+    // search bar
+    let search = Paragraph::new(format!("/{}", app.query))
+        .block(pane("Search (text, type:AE)".to_string(), app.searching));
+    frame.render_widget(search, search_area);
+
+    // node list
+    let mm = app.mm();
+    let file_name = mm
+        .path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let items: Vec<ListItem> = app
+        .items
+        .iter()
+        .filter_map(|id| mm.get_node(*id))
+        .map(|n| ListItem::new(format!("[{}] {}", n.id, n.raw_title)))
+        .collect();
+    let list_block = pane(
+        format!("{} ({}/{})", file_name, app.items.len(), mm.nodes.len()),
+        app.focus == Focus::List,
+    );
+    let list_inner = list_block.inner(left);
+    let list = List::new(items)
+        .block(list_block)
+        .highlight_style(highlight(app.focus == Focus::List));
+    let mut state = ListState::default().with_selected(Some(app.selected));
+    frame.render_stateful_widget(list, left, &mut state);
+
+    // detail
+    let detail = match app.current_id().and_then(|id| mm.get_node(id)) {
+        Some(node) => vec![
+            Line::from(Span::styled(
+                format!("[{}] {}", node.id, node.raw_title),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(node.description.clone()),
+        ],
+        None => vec![Line::from("No nodes match")],
+    };
+    let detail = Paragraph::new(detail)
+        .wrap(Wrap { trim: true })
+        .block(pane("Node".to_string(), false));
+    frame.render_widget(detail, detail_area);
+
+    // incoming / outgoing
+    let title_of = |id: u32| {
+        mm.get_node(id)
+            .map(|n| n.raw_title.clone())
+            .unwrap_or_else(|| "(missing)".to_string())
+    };
+    let incoming: Vec<ListItem> = app
+        .incoming()
+        .iter()
+        .map(|id| ListItem::new(format!("[{}] {}", id, title_of(*id))))
+        .collect();
+    let outgoing: Vec<ListItem> = app
+        .outgoing()
+        .iter()
+        .map(|r| match r {
+            Reference::Internal(id) => ListItem::new(format!("[{}] {}", id, title_of(*id))),
+            Reference::External(id, rel) => ListItem::new(format!("[{}]({})", id, rel)),
+        })
+        .collect();
+
+    let in_block = pane(
+        format!("Incoming ({})", incoming.len()),
+        app.focus == Focus::Incoming,
+    );
+    let out_block = pane(
+        format!("Outgoing ({})", outgoing.len()),
+        app.focus == Focus::Outgoing,
+    );
+    let in_inner = in_block.inner(incoming_area);
+    let out_inner = out_block.inner(outgoing_area);
+    let mut in_state = ListState::default().with_selected(Some(app.incoming_sel));
+    let mut out_state = ListState::default().with_selected(Some(app.outgoing_sel));
+    frame.render_stateful_widget(
+        List::new(incoming)
+            .block(in_block)
+            .highlight_style(highlight(app.focus == Focus::Incoming)),
+        incoming_area,
+        &mut in_state,
+    );
+    frame.render_stateful_widget(
+        List::new(outgoing)
+            .block(out_block)
+            .highlight_style(highlight(app.focus == Focus::Outgoing)),
+        outgoing_area,
+        &mut out_state,
+    );
+
+    // help / status line
+    let help = if app.status.is_empty() {
+        format!(
+            "/ search  ↑↓ move  Tab pane  Enter follow  b/f back/forward ({}/{})  e edit  r reload  q quit",
+            app.back.len(),
+            app.forward.len()
+        )
+    } else {
+        app.status.clone()
+    };
+    frame.render_widget(Paragraph::new(help), help_area);
+
+    app.areas = Areas {
+        list: list_inner,
+        list_offset: state.offset(),
+        incoming: in_inner,
+        outgoing: out_inner,
+    };
+}
+
+/// Run the browser until the user quits
+pub fn run(mm: Mindmap, config: Config) -> Result<()> {
+    let mut app = App::new(mm, config)?;
+    let mut terminal = ratatui::init();
+    execute!(std::io::stdout(), EnableMouseCapture)?;
+
+    let result = (|| -> Result<()> {
+        loop {
+            terminal.draw(|f| draw(f, &mut app))?;
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => match app.on_key(key) {
+                    Action::Quit => return Ok(()),
+                    Action::Edit(id) => {
+                        // hand the terminal to the editor, then take it back
+                        execute!(std::io::stdout(), DisableMouseCapture)?;
+                        ratatui::restore();
+                        let r = app.edit(id);
+                        terminal = ratatui::init();
+                        execute!(std::io::stdout(), EnableMouseCapture)?;
+                        terminal.clear()?;
+                        app.status = match r {
+                            Ok(()) => format!("Edited node [{}]", id),
+                            Err(e) => format!("Edit failed: {}", e),
+                        };
+                    }
+                    Action::None => {}
+                },
+                Event::Mouse(m) => app.on_mouse(m),
+                _ => {}
+            }
+        }
+    })();
+
+    let _ = execute!(std::io::stdout(), DisableMouseCapture);
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{Terminal, backend::TestBackend};
+
+    fn key(c: KeyCode) -> KeyEvent {
+        KeyEvent::new(c, KeyModifiers::NONE)
+    }
+
+    fn workspace() -> Result<(tempfile::TempDir, App)> {
+        let temp = tempfile::TempDir::new()?;
+        fs::write(
+            temp.path().join("MINDMAP.md"),
+            "[1] **AE: Auth** - login, see [2] and [5](./team.md)\n\n[2] **WF: Flow** - uses [1]\n\n[3] **DR: Other** - x\n",
+        )?;
+        fs::write(temp.path().join("team.md"), "[5] **AE: Team** - shared\n")?;
+        let mm = Mindmap::load(temp.path().join("MINDMAP.md"))?;
+        let config = Config::for_mindmap(&mm.path)?;
+        Ok((temp, App::new(mm, config)?))
+    }
+
+    #[test]
+    fn test_search_filter() -> Result<()> {
+        let (_temp, mut app) = workspace()?;
+        assert_eq!(app.items, vec![1, 2, 3]);
+        app.on_key(key(KeyCode::Char('/')));
+        for c in "type:AE".chars() {
+            app.on_key(key(KeyCode::Char(c)));
+        }
+        assert_eq!(app.items, vec![1]);
+        app.on_key(key(KeyCode::Esc));
+        assert_eq!(app.items, vec![1, 2, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_follow_history_and_external() -> Result<()> {
+        let (_temp, mut app) = workspace()?;
+        assert_eq!(app.outgoing().len(), 2);
+
+        // Enter moves into the outgoing pane, Enter again follows [2]
+        app.on_key(key(KeyCode::Enter));
+        assert_eq!(app.focus, Focus::Outgoing);
+        app.on_key(key(KeyCode::Enter));
+        assert_eq!(app.current_id(), Some(2));
+        assert_eq!(app.incoming(), vec![1]);
+
+        app.on_key(key(KeyCode::Char('b')));
+        assert_eq!(app.current_id(), Some(1));
+        app.on_key(key(KeyCode::Char('f')));
+        assert_eq!(app.current_id(), Some(2));
+
+        // external reference switches file
+        app.go_back()?;
+        app.follow(&Reference::External(5, "./team.md".to_string()))?;
+        assert_eq!(app.current_id(), Some(5));
+        assert!(app.mm().path.ends_with("team.md"));
+        app.go_back()?;
+        assert!(app.mm().path.ends_with("MINDMAP.md"));
+        assert_eq!(app.current_id(), Some(1));
+
+        // missing targets are reported, not fatal
+        assert!(app.follow(&Reference::Internal(42)).is_err());
+        assert_eq!(app.current_id(), Some(1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_draw_renders_panes() -> Result<()> {
+        let (_temp, mut app) = workspace()?;
+        let mut terminal = Terminal::new(TestBackend::new(100, 30))?;
+        terminal.draw(|f| draw(f, &mut app))?;
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(text.contains("[1] AE: Auth"));
+        assert!(text.contains("Outgoing (2)"));
+        assert!(text.contains("Incoming (1)"));
+        Ok(())
+    }
+}
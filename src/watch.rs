@@ -0,0 +1,370 @@
+//! Watch: re-lint and re-export when the mindmap changes on disk
+//!
+//! This module provides:
+//! - Polling of the MINDMAP file (and, with `--follow`, every file reachable through
+//!   external references) by blake3 content hash
+//! - A lint diff per file on each change: issues that appeared and issues resolved
+//! - Regeneration of `--export FORMAT:PATH` targets (DOT, Mermaid, HTML, ...) so a
+//!   live preview stays current while the map is edited
+
+use anyhow::{Result, anyhow};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{ExportFormat, Mindmap, Reference, blake3_hash, cmd_lint, config::Config, export};
+
+/// One `--export FORMAT:PATH` target
+#[derive(Clone)]
+pub struct ExportTarget {
+    pub format: ExportFormat,
+    pub out: PathBuf,
+}
+
+/// clap value parser for `FORMAT:PATH` (e.g. `dot:map.dot`, `html:preview.html`)
+pub fn parse_export_target(s: &str) -> Result<ExportTarget, String> {
+    let (format, out) = s
+        .split_once(':')
+        .filter(|(_, out)| !out.is_empty())
+        .ok_or_else(|| format!("expected FORMAT:PATH (e.g. dot:map.dot), got '{}'", s))?;
+    let format = ExportFormat::from_str(format, true)?;
+    Ok(ExportTarget {
+        format,
+        out: PathBuf::from(out),
+    })
+}
+
+/// Lint changes for one file
+#[derive(Debug, Serialize)]
+pub struct FileChange {
+    pub file: PathBuf,
+    /// Set when the file could not be read or parsed (its issues are kept as they were)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub new: Vec<String>,
+    pub resolved: Vec<String>,
+    /// Issues now open in this file
+    pub open: usize,
+}
+
+/// What one scan found
+#[derive(Debug, Serialize)]
+pub struct WatchEvent {
+    pub files: Vec<FileChange>,
+    pub exported: Vec<PathBuf>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub export_errors: Vec<String>,
+}
+
+pub struct Watcher {
+    path: PathBuf,
+    follow: bool,
+    exports: Vec<ExportTarget>,
+    /// Watched files and their last seen content hash ("" when unreadable)
+    hashes: BTreeMap<PathBuf, String>,
+    /// Current lint issues per file, in lint order
+    issues: BTreeMap<PathBuf, Vec<String>>,
+}
+
+fn file_hash(path: &Path) -> String {
+    fs::read(path).map(|b| blake3_hash(&b)).unwrap_or_default()
+}
+
+fn lint_file(path: &Path) -> Result<Vec<String>> {
+    let mm = Mindmap::load(path.to_path_buf())?;
+    // re-read on every change: the config may be edited while watching
+    let config = Config::for_mindmap(path)?;
+    Ok(cmd_lint(&mm, &config)?
+        .iter()
+        .map(|f| f.to_string())
+        .collect())
+}
+
+impl Watcher {
+    pub fn new(path: PathBuf, follow: bool, exports: Vec<ExportTarget>) -> Self {
+        Watcher {
+            path,
+            follow,
+            exports,
+            hashes: BTreeMap::new(),
+            issues: BTreeMap::new(),
+        }
+    }
+
+    /// The MINDMAP file plus (with follow) every file reachable through external
+    /// references, resolved with the same path safety rules as `--follow`
+    fn members(&self) -> Vec<PathBuf> {
+        let mut members = vec![self.path.clone()];
+        if !self.follow {
+            return members;
+        }
+        let workspace = self.path.parent().unwrap_or_else(|| Path::new("."));
+        let cache = Config::for_mindmap(&self.path)
+            .unwrap_or_default()
+            .cache
+            .cache(workspace);
+        let main = fs::canonicalize(&self.path).unwrap_or_else(|_| self.path.clone());
+        let mut seen: HashSet<PathBuf> = HashSet::from([main.clone()]);
+        let mut queue = VecDeque::from([main]);
+        while let Some(file) = queue.pop_front() {
+            let Ok(mm) = Mindmap::load(file.clone()) else {
+                continue;
+            };
+            for n in &mm.nodes {
+                for r in &n.references {
+                    if let Reference::External(_, rel) = r
+                        && let Ok(target) = cache.resolve_path(&file, rel)
+                        && seen.insert(target.clone())
+                    {
+                        members.push(target.clone());
+                        queue.push_back(target);
+                    }
+                }
+            }
+        }
+        members
+    }
+
+    /// Number of files currently watched
+    pub fn file_count(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Check every watched file; on a change (or when `force` is set) re-lint the
+    /// changed files and regenerate exports. Returns `None` when nothing changed.
+    pub fn scan(&mut self, force: bool) -> Result<Option<WatchEvent>> {
+        let unchanged = !self.hashes.is_empty()
+            && self
+                .hashes
+                .iter()
+                .all(|(path, hash)| file_hash(path) == *hash);
+        if unchanged && !force {
+            return Ok(None);
+        }
+
+        let members = self.members();
+        let mut hashes = BTreeMap::new();
+        let mut files = Vec::new();
+        for path in members {
+            let hash = file_hash(&path);
+            let previous = self.hashes.get(&path);
+            if !force && previous == Some(&hash) {
+                hashes.insert(path, hash);
+                continue;
+            }
+
+            let old = self.issues.get(&path).cloned().unwrap_or_default();
+            let change = match lint_file(&path) {
+                Ok(current) => {
+                    let change = FileChange {
+                        file: path.clone(),
+                        error: None,
+                        new: current
+                            .iter()
+                            .filter(|w| !old.contains(w))
+                            .cloned()
+                            .collect(),
+                        resolved: old
+                            .iter()
+                            .filter(|w| !current.contains(w))
+                            .cloned()
+                            .collect(),
+                        open: current.len(),
+                    };
+                    self.issues.insert(path.clone(), current);
+                    change
+                }
+                Err(e) => FileChange {
+                    file: path.clone(),
+                    error: Some(e.to_string()),
+                    new: Vec::new(),
+                    resolved: Vec::new(),
+                    open: old.len(),
+                },
+            };
+            files.push(change);
+            hashes.insert(path, hash);
+        }
+
+        // files no longer referenced: their issues are gone from view
+        for path in self.hashes.keys().filter(|p| !hashes.contains_key(*p)) {
+            files.push(FileChange {
+                file: path.clone(),
+                error: Some("no longer referenced; stopped watching".to_string()),
+                new: Vec::new(),
+                resolved: self.issues.remove(path).unwrap_or_default(),
+                open: 0,
+            });
+        }
+        self.hashes = hashes;
+
+        let mut exported = Vec::new();
+        let mut export_errors = Vec::new();
+        if !self.exports.is_empty() {
+            match Mindmap::load(self.path.clone()) {
+                Ok(mm) => {
+                    for target in &self.exports {
+                        match export::write_export(&mm, &target.format, &target.out) {
+                            Ok(()) => exported.push(target.out.clone()),
+                            Err(e) => {
+                                export_errors.push(format!("{}: {}", target.out.display(), e))
+                            }
+                        }
+                    }
+                }
+                Err(e) => export_errors.push(format!("{}: {}", self.path.display(), e)),
+            }
+        }
+
+        Ok(Some(WatchEvent {
+            files,
+            exported,
+            export_errors,
+        }))
+    }
+}
+
+fn print_event(event: &WatchEvent, json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string(event)?);
+        return Ok(());
+    }
+    let time = chrono::Local::now().format("%H:%M:%S");
+    for f in &event.files {
+        let issues = f.new.len() + f.resolved.len();
+        match &f.error {
+            Some(e) => println!("[{}] {}: {}", time, f.file.display(), e),
+            None if f.open == 0 && issues == 0 => {
+                println!("[{}] {}: Lint OK", time, f.file.display())
+            }
+            None if issues == 0 => println!(
+                "[{}] {}: no lint changes ({} open)",
+                time,
+                f.file.display(),
+                f.open
+            ),
+            None => println!("[{}] {}:", time, f.file.display()),
+        }
+        for w in &f.new {
+            println!("  + {}", w);
+        }
+        for w in &f.resolved {
+            println!("  - {}", w);
+        }
+    }
+    for out in &event.exported {
+        println!("[{}] exported {}", time, out.display());
+    }
+    for e in &event.export_errors {
+        eprintln!("Warning: export failed: {}", e);
+    }
+    Ok(())
+}
+
+/// Watch until the process is stopped; `interval` is the polling period
+pub fn run(
+    path: PathBuf,
+    follow: bool,
+    exports: Vec<ExportTarget>,
+    interval: Duration,
+    json: bool,
+) -> Result<()> {
+    if path.as_os_str() == "-" {
+        return Err(anyhow!("Cannot watch stdin ('-'); use --file <path>"));
+    }
+    let mut watcher = Watcher::new(path.clone(), follow, exports);
+    if let Some(event) = watcher.scan(true)? {
+        print_event(&event, json)?;
+    }
+    eprintln!(
+        "Watching {} file{} (every {} ms); Ctrl-C to stop",
+        watcher.file_count(),
+        if watcher.file_count() == 1 { "" } else { "s" },
+        interval.as_millis()
+    );
+    loop {
+        std::thread::sleep(interval);
+        if let Some(event) = watcher.scan(false)? {
+            print_event(&event, json)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_export_target() {
+        let t = parse_export_target("dot:out/map.dot").unwrap();
+        assert!(matches!(t.format, ExportFormat::Dot));
+        assert_eq!(t.out, PathBuf::from("out/map.dot"));
+        assert!(parse_export_target("dot").is_err());
+        assert!(parse_export_target("pdf:x.pdf").is_err());
+    }
+
+    #[test]
+    fn test_scan_reports_new_and_resolved_issues() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let path = temp.path().join("MINDMAP.md");
+        let preview = temp.path().join("map.mmd");
+        fs::write(&path, "[1] **AE: One** - see [2]\n")?;
+        let mut w = Watcher::new(
+            path.clone(),
+            false,
+            vec![ExportTarget {
+                format: ExportFormat::Mermaid,
+                out: preview.clone(),
+            }],
+        );
+
+        let first = w.scan(true)?.unwrap();
+        assert_eq!(
+            first.files[0].new,
+            vec!["Missing ref: node 1 references missing node 2".to_string()]
+        );
+        assert_eq!(first.exported, vec![preview.clone()]);
+        assert!(w.scan(false)?.is_none());
+
+        fs::write(
+            &path,
+            "[1] **AE: One** - see [2]\n\n[2] **AE: Two** - x [3]\n",
+        )?;
+        let second = w.scan(false)?.unwrap();
+        assert_eq!(
+            second.files[0].new,
+            vec!["Missing ref: node 2 references missing node 3".to_string()]
+        );
+        assert_eq!(
+            second.files[0].resolved,
+            vec!["Missing ref: node 1 references missing node 2".to_string()]
+        );
+        assert!(fs::read_to_string(&preview)?.contains("n1 --> n2"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_follow_watches_referenced_files() -> Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let path = temp.path().join("MINDMAP.md");
+        let other = temp.path().join("other.md");
+        fs::write(&path, "[1] **AE: One** - see [10](./other.md)\n")?;
+        fs::write(&other, "[10] **AE: Ten** - ok\n")?;
+
+        let mut w = Watcher::new(path, true, Vec::new());
+        w.scan(true)?;
+        assert_eq!(w.file_count(), 2);
+
+        fs::write(&other, "[10] **AE: Ten** - see [11]\n")?;
+        let event = w.scan(false)?.unwrap();
+        assert_eq!(event.files.len(), 1);
+        assert!(event.files[0].file.ends_with("other.md"));
+        assert_eq!(event.files[0].new.len(), 1);
+        Ok(())
+    }
+}
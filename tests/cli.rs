@@ -238,6 +238,48 @@ fn integration_cli_errors_and_edge_cases() -> Result<(), Box<dyn std::error::Err
         .success()
         .stderr(predicate::str::contains("Syntax").and(predicate::str::contains("Duplicate ID")));
 
+    // CI formats: findings on stdout with file and line; warnings alone do not fail
+    let mut cmd = mindmap_cmd();
+    cmd.arg("lint")
+        .arg("--format")
+        .arg("github")
+        .arg("--file")
+        .arg(bad_file.path());
+    cmd.assert().success().stdout(
+        predicate::str::contains("::warning file=")
+            .and(predicate::str::contains(",line=3,col=1,"))
+            .and(predicate::str::contains("title=mindmap-cli duplicate-id::")),
+    );
+
+    // rule levels from .mindmap.toml: errors fail, in every format
+    temp.child(".mindmap.toml")
+        .write_str("[lint.rules]\nsyntax = \"error\"\nduplicate-id = \"info\"\n")?;
+    let mut cmd = mindmap_cmd();
+    cmd.arg("lint").arg("--file").arg(bad_file.path());
+    cmd.assert().failure().stderr(
+        predicate::str::contains("error[syntax]")
+            .and(predicate::str::contains("info[duplicate-id]")),
+    );
+    let mut cmd = mindmap_cmd();
+    cmd.arg("lint")
+        .arg("--format")
+        .arg("sarif")
+        .arg("--file")
+        .arg(bad_file.path());
+    let out = cmd.assert().failure().get_output().stdout.clone();
+    let log: serde_json::Value = serde_json::from_slice(&out)?;
+    assert_eq!(log["version"], "2.1.0");
+    assert_eq!(log["runs"][0]["results"][0]["ruleId"], "syntax");
+    assert_eq!(log["runs"][0]["results"][0]["level"], "error");
+    temp.child(".mindmap.toml")
+        .write_str("[lint.rules]\nno-such-rule = \"off\"\n")?;
+    let mut cmd = mindmap_cmd();
+    cmd.arg("lint").arg("--file").arg(bad_file.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown lint rule 'no-such-rule'"));
+    std::fs::remove_file(temp.child(".mindmap.toml").path())?;
+
     // orphans with some
     let orphan_file = temp.child("ORPHANS.md");
     orphan_file.write_str("[1] **AE: One** - first\n[2] **AE: Orphan** - lonely\n")?;
@@ -569,6 +611,35 @@ fn integration_cli_follow_flag() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn integration_cli_follow_uses_map_config() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    temp.child("docs/MAIN.md")
+        .write_str("[1] **AE: Main** - see [10](./ext.md)\n")?;
+    temp.child("docs/ext.md")
+        .write_str("[10] **AE: External concept** - far away\n")?;
+
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(temp.path())
+        .args(["--file", "docs/MAIN.md", "links", "1", "--follow"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("External concept"));
+
+    // cache limits come from the mindmap's own .mindmap.toml, not the cwd's
+    temp.child("docs/.mindmap.toml")
+        .write_str("[cache]\nmax_file_size = 10\n")?;
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(temp.path())
+        .args(["--file", "docs/MAIN.md", "links", "1", "--follow"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("External concept").not());
+
+    temp.close()?;
+    Ok(())
+}
+
 #[test]
 fn integration_cli_recursive_search() -> Result<(), Box<dyn std::error::Error>> {
     let temp = assert_fs::TempDir::new()?;
@@ -607,3 +678,718 @@ fn integration_cli_recursive_search() -> Result<(), Box<dyn std::error::Error>>
     temp.close()?;
     Ok(())
 }
+
+#[test]
+fn integration_cli_csv_export_import() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let file = temp.child("MINDMAP.md");
+    file.write_str("[1] **AE: One** - first\n\n[2] **AE: Two** - refers [1]\n")?;
+
+    // export to stdout
+    let mut cmd = mindmap_cmd();
+    cmd.arg("export").arg("--file").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "id,type,title,description,outgoing,incoming,line",
+        ))
+        .stdout(predicate::str::contains("1,AE,One,first,,[2],1"));
+
+    let csv = temp.child("nodes.csv");
+    csv.write_str("id,type,title,description\n2,AE,Two,now refers [1] again\n,WF,Three,new\n")?;
+
+    // dry-run leaves the file untouched
+    let mut cmd = mindmap_cmd();
+    cmd.arg("import")
+        .arg(csv.path())
+        .arg("--dry-run")
+        .arg("--file")
+        .arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("[3] **WF: Three** - new"));
+    let content = std::fs::read_to_string(file.path())?;
+    assert!(!content.contains("WF: Three"));
+
+    let mut cmd = mindmap_cmd();
+    cmd.arg("import")
+        .arg(csv.path())
+        .arg("--file")
+        .arg(file.path());
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Import applied successfully"));
+    let content = std::fs::read_to_string(file.path())?;
+    assert!(content.contains("[2] **AE: Two** - now refers [1] again"));
+    assert!(content.contains("[3] **WF: Three** - new"));
+
+    temp.close()?;
+    Ok(())
+}
+
+#[test]
+fn integration_cli_import_mindmap_dry_run() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let file = temp.child("MINDMAP.md");
+    file.write_str("[1] **AE: Auth** - auth\n")?;
+    let other = temp.child("OTHER.md");
+    other.write_str("[1] **WF: Deploy** - see [9]\n")?;
+
+    // stdout is only the preview; the mapping goes to stderr
+    let mut cmd = mindmap_cmd();
+    cmd.arg("import")
+        .arg(other.path())
+        .args(["--format", "mindmap", "--dry-run", "--file"])
+        .arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(
+            predicate::str::contains("[2] **WF: Deploy** - see <!-- missing ref 9 -->")
+                .and(predicate::str::contains("→").not()),
+        )
+        .stderr(predicate::str::contains("[1] → [2]"));
+
+    let mut cmd = mindmap_cmd();
+    cmd.arg("import")
+        .arg(other.path())
+        .args([
+            "--format",
+            "mindmap",
+            "--dry-run",
+            "--output",
+            "json",
+            "--file",
+        ])
+        .arg(file.path());
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let json: serde_json::Value = serde_json::from_slice(&out)?;
+    assert_eq!(json["result"]["id_map"][0]["new"], 2);
+
+    temp.close()?;
+    Ok(())
+}
+
+#[test]
+fn integration_cli_json_export_import_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let file = temp.child("MINDMAP.md");
+    let original = "# Project\n\n[1] **AE: One** - first\n\n[2] **AE: Two** - refers [1]\n";
+    file.write_str(original)?;
+
+    let json = temp.child("map.json");
+    let mut cmd = mindmap_cmd();
+    cmd.arg("export")
+        .arg("--format")
+        .arg("json")
+        .arg("--out")
+        .arg(json.path())
+        .arg("--file")
+        .arg(file.path());
+    cmd.assert().success();
+
+    // edit structurally and import back
+    let mut doc: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(json.path())?)?;
+    doc["nodes"][1]["description"] = serde_json::json!("refers [1] and more");
+    json.write_str(&serde_json::to_string(&doc)?)?;
+
+    let mut cmd = mindmap_cmd();
+    cmd.arg("import")
+        .arg(json.path())
+        .arg("--format")
+        .arg("json")
+        .arg("--file")
+        .arg(file.path());
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Import applied successfully"));
+    let content = std::fs::read_to_string(file.path())?;
+    assert_eq!(
+        content,
+        original.replace("refers [1]", "refers [1] and more")
+    );
+
+    temp.close()?;
+    Ok(())
+}
+
+#[test]
+fn integration_cli_lsp_initialize_shutdown() -> Result<(), Box<dyn std::error::Error>> {
+    fn frame(body: &str) -> String {
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+    let input = [
+        frame(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#),
+        frame(r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#),
+        frame(r#"{"jsonrpc":"2.0","method":"exit"}"#),
+    ]
+    .concat();
+
+    // no MINDMAP.md needed in the working directory
+    let temp = assert_fs::TempDir::new()?;
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(temp.path()).arg("lsp").write_stdin(input);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"definitionProvider\":true"))
+        .stdout(predicate::str::contains(
+            "\"id\":2,\"jsonrpc\":\"2.0\",\"result\":null",
+        ));
+
+    temp.close()?;
+    Ok(())
+}
+
+#[test]
+fn integration_cli_serve_stdio_tools() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let file = temp.child("MINDMAP.md");
+    file.write_str("[1] **AE: One** - first\n")?;
+
+    let input = [
+        r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-06-18"}}"#,
+        r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"add","arguments":{"type":"WF","title":"Two","desc":"uses [1]"}}}"#,
+    ]
+    .join("\n");
+
+    let mut cmd = mindmap_cmd();
+    cmd.arg("serve")
+        .arg("--stdio")
+        .arg("--file")
+        .arg(file.path())
+        .write_stdin(input);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\"protocolVersion\":\"2025-06-18\"",
+        ))
+        .stdout(predicate::str::contains("\"isError\":false"));
+
+    let content = std::fs::read_to_string(file.path())?;
+    assert!(content.contains("[2] **WF: Two** - uses [1]"));
+
+    temp.close()?;
+    Ok(())
+}
+
+#[test]
+fn integration_cli_shell_stages_until_commit() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let file = temp.child("MINDMAP.md");
+    file.write_str("[1] **AE: One** - first\n")?;
+
+    let input = "add --type WF --title Two --desc \"uses [1]\"\nrollback\npatch 1 --desc changed\ncommit\nexit\n";
+
+    let mut cmd = mindmap_cmd();
+    cmd.arg("shell")
+        .arg("--file")
+        .arg(file.path())
+        .env("HOME", temp.path())
+        .write_stdin(input);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Rolled back 1 staged change(s)"))
+        .stdout(predicate::str::contains("Committed 1 change(s)"));
+
+    let content = std::fs::read_to_string(file.path())?;
+    assert_eq!(content, "[1] **AE: One** - changed\n");
+
+    temp.close()?;
+    Ok(())
+}
+
+#[test]
+fn integration_cli_merge_driver() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let base = temp.child("base.md");
+    let ours = temp.child("ours.md");
+    let theirs = temp.child("theirs.md");
+    base.write_str("[1] **AE: One** - first\n")?;
+    ours.write_str("[1] **AE: One** - first\n\n[2] **WF: Ours** - uses [1]\n")?;
+    theirs.write_str("[1] **AE: Uno** - first\n\n[2] **WF: Theirs** - uses [1]\n")?;
+
+    let mut cmd = mindmap_cmd();
+    cmd.arg("merge-driver")
+        .arg(base.path())
+        .arg(ours.path())
+        .arg(theirs.path());
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("renumbered their node [2] to [3]"));
+    assert_eq!(
+        std::fs::read_to_string(ours.path())?,
+        "[1] **AE: Uno** - first\n\n[2] **WF: Ours** - uses [1]\n\n[3] **WF: Theirs** - uses [1]\n"
+    );
+
+    // the same field changed on both sides: markers and a non-zero exit
+    theirs.write_str("[1] **AE: Eins** - first\n")?;
+    let mut cmd = mindmap_cmd();
+    cmd.arg("merge-driver")
+        .arg(base.path())
+        .arg(ours.path())
+        .arg(theirs.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("merge conflict in [1]"));
+    assert!(std::fs::read_to_string(ours.path())?.contains("<<<<<<< ours"));
+
+    temp.close()?;
+    Ok(())
+}
+
+#[test]
+fn integration_cli_diff_files() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let old = temp.child("old.md");
+    let new = temp.child("new.md");
+    old.write_str("[1] **AE: One** - first\n[2] **WF: Two** - uses [1]\n")?;
+    new.write_str("[1] **AE: Uno** - first\n\n\n[2] **WF: Two** - uses [1]\n")?;
+
+    let mut cmd = mindmap_cmd();
+    cmd.arg("diff").arg(old.path()).arg(new.path());
+    cmd.assert()
+        .success()
+        .stdout("~ [1] title: \"One\" -> \"Uno\"\n")
+        .stderr(predicate::str::contains("0 added, 0 removed, 1 changed"));
+
+    let mut cmd = mindmap_cmd();
+    cmd.arg("diff")
+        .arg(old.path())
+        .arg(new.path())
+        .arg("--output")
+        .arg("json");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"field\": \"title\""));
+
+    temp.close()?;
+    Ok(())
+}
+
+#[test]
+fn integration_cli_project_config() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    temp.child(".mindmap.toml").write_str(
+        "file = \"docs/MINDMAP.md\"\n\n[output]\nformat = \"json\"\n\n[types]\nallowed = [\"AE\", \"META\", \"NOTE\"]\n\n[orphans]\nexempt = [\"META\", \"NOTE\"]\n",
+    )?;
+    temp.child("docs/MINDMAP.md").write_str(
+        "[1] **AE: One** - first [2]\n[2] **AE: Two** - second\n[3] **NOTE: Aside** - alone\n",
+    )?;
+    let nested = temp.child("src/deep");
+    nested.create_dir_all()?;
+
+    // default file and output format come from the config found above the cwd
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(nested.path()).arg("list");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"command\": \"list\""));
+
+    // --output still wins
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(nested.path())
+        .arg("--output")
+        .arg("default")
+        .arg("show")
+        .arg("1");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"command\"").not());
+
+    // NOTE is exempt from orphans
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(nested.path()).arg("orphans");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"count\": 0"));
+
+    // types outside [types] allowed are rejected by add and reported by lint
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(nested.path())
+        .args(["add", "--type", "WF", "--title", "Flow", "--desc", "x"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Type 'WF' is not allowed"));
+    temp.child("docs/MINDMAP.md")
+        .write_str("[1] **AE: One** - first [2]\n[2] **WF: Two** - second\n")?;
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(nested.path()).arg("lint");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"unknown-type\""));
+
+    // a type registry: aliases and case resolve to the declared prefix, conventions hold
+    temp.child(".mindmap.toml").write_str(
+        "file = \"docs/MINDMAP.md\"\n\n[types.AE]\naliases = [\"arch\"]\n\n[types.DR]\nmust_reference = [\"AE\"]\n",
+    )?;
+    temp.child("docs/MINDMAP.md")
+        .write_str("[1] **AE: One** - first\n")?;
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(nested.path()).args([
+        "add", "--type", "arch", "--title", "Two", "--desc", "second",
+    ]);
+    cmd.assert().success();
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(nested.path()).args([
+        "add", "--type", "dr", "--title", "Pick", "--desc", "no refs",
+    ]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "DR must reference at least one AE node",
+    ));
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(nested.path()).args([
+        "add", "--type", "dr", "--title", "Pick", "--desc", "see [2]",
+    ]);
+    cmd.assert().success();
+    let content = std::fs::read_to_string(temp.child("docs/MINDMAP.md").path())?;
+    assert!(content.contains("[2] **AE: Two** - second"));
+    assert!(content.contains("[3] **DR: Pick** - see [2]"));
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(nested.path())
+        .args(["list", "--type", "ARCH"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("AE: Two").and(predicate::str::contains("DR").not()));
+
+    temp.close()?;
+    Ok(())
+}
+
+#[test]
+fn integration_cli_lint_fix_preview() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let file = temp.child("MINDMAP.md");
+    let content = "[1]**AE: One** - see [9]\n\n[2] **AE: Two** — two [1]\n";
+    file.write_str(content)?;
+
+    // --dry-run previews fixes and leaves the file alone
+    let mut cmd = mindmap_cmd();
+    cmd.args([
+        "lint",
+        "--fix",
+        "--dry-run",
+        "--missing-refs",
+        "comment",
+        "--file",
+    ])
+    .arg(file.path());
+    cmd.assert().success().stderr(
+        predicate::str::contains("Would fix line 1 (malformed-node: repaired node syntax)")
+            .and(predicate::str::contains(
+                "Would fix line 3 (malformed-node: repaired node syntax)",
+            ))
+            .and(predicate::str::contains("missing-ref: commented out [9]")),
+    );
+    assert_eq!(std::fs::read_to_string(file.path())?, content);
+
+    let mut cmd = mindmap_cmd();
+    cmd.args(["lint", "--fix", "--missing-refs", "remove", "--file"])
+        .arg(file.path());
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Fixed line 1"));
+    assert_eq!(
+        std::fs::read_to_string(file.path())?,
+        "[1] **AE: One** - see\n\n[2] **AE: Two** - two [1]\n"
+    );
+
+    // --missing-refs needs --fix
+    let mut cmd = mindmap_cmd();
+    cmd.args(["lint", "--missing-refs", "remove", "--file"])
+        .arg(file.path());
+    cmd.assert().failure();
+
+    temp.close()?;
+    Ok(())
+}
+
+#[test]
+fn integration_cli_stale() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let file = temp.child("MINDMAP.md");
+    file.write_str(
+        "[1] **DR: Old** - x (verify 2000-01-01)\n[2] **DR: Never** - y\n[3] **AE: Fresh** - z\n",
+    )?;
+
+    let mut cmd = mindmap_cmd();
+    cmd.arg("verify").arg("3").arg("--file").arg(file.path());
+    cmd.assert().success();
+
+    // never-verified nodes lead, fresh ones are omitted
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(temp.path())
+        .args(["stale", "--older-than", "90d", "--no-git"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("[2] **DR: Never**"))
+        .stdout(predicate::str::contains("[3]").not());
+
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(temp.path())
+        .args(["--output", "json", "stale", "--type", "dr", "--no-git"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"count\": 2"))
+        .stdout(predicate::str::contains("\"verified\": \"2000-01-01\""));
+
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(temp.path())
+        .args(["stale", "--older-than", "soon"]);
+    cmd.assert().failure();
+
+    // critical types that go unverified fail lint
+    temp.child(".mindmap.toml")
+        .write_str("[stale.types]\nDR = \"1y\"\n")?;
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(temp.path()).arg("lint");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("stale-verification"));
+
+    temp.close()?;
+    Ok(())
+}
+
+#[test]
+fn integration_cli_lint_anchors() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    temp.child("src/cache.rs")
+        .write_str("pub fn resolve_path() {}\n")?;
+    temp.child("DESIGN.md").write_str("# Design\n")?;
+    let file = temp.child("MINDMAP.md");
+    file.write_str(
+        "[1] **AE: Cache** - Location: src/cache.rs:1, see [DESIGN](./DESIGN.md)\n[2] **AE: Gone** - moved from src/old.rs and src/cache.rs#load\n",
+    )?;
+
+    let mut cmd = mindmap_cmd();
+    cmd.arg("lint").arg("--file").arg(file.path());
+    cmd.assert().success().stderr(
+        predicate::str::contains("2:31: warn[broken-anchor]")
+            .and(predicate::str::contains("references src/old.rs"))
+            .and(predicate::str::contains("warn[missing-symbol]"))
+            .and(predicate::str::contains("node 1").not()),
+    );
+
+    // projects can make broken anchors fail lint
+    temp.child(".mindmap.toml")
+        .write_str("[lint.rules]\nbroken-anchor = \"error\"\n")?;
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(temp.path()).arg("lint");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("error[broken-anchor]"));
+
+    // the symbol check is optional
+    temp.child(".mindmap.toml")
+        .write_str("[lint.rules]\nmissing-symbol = \"off\"\n")?;
+    file.write_str("[1] **AE: Gone** - see src/cache.rs#load\n")?;
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(temp.path()).arg("lint");
+    cmd.assert().success();
+
+    temp.close()?;
+    Ok(())
+}
+
+#[test]
+fn integration_cli_scan_code() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let file = temp.child("MINDMAP.md");
+    file.write_str(
+        "[1] **AE: Cache** - a\n[2] **[DEPRECATED → 1] AE: Old cache** - b\n[3] **AE: Parser** - c\n",
+    )?;
+    temp.child("src/cache.rs")
+        .write_str("// MINDMAP[1]\nfn load() {}\n// see MINDMAP[2]\n")?;
+
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(temp.path())
+        .args(["scan-code", "src", "--uncited"]);
+    cmd.assert()
+        .failure()
+        .stdout(
+            predicate::str::contains(
+                "src/cache.rs:3:8: MINDMAP[2] cites deprecated node (use MINDMAP[1])",
+            )
+            .and(predicate::str::contains("[3] **AE: Parser**"))
+            .and(predicate::str::contains("[1] **AE: Cache**").not()),
+        )
+        .stderr(predicate::str::contains(
+            "Scanned 1 file: 2 citations of 2 nodes",
+        ));
+
+    // code locations citing a node
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(temp.path()).args(["show", "1", "--code"]);
+    cmd.assert().success().stderr(
+        predicate::str::contains("Cited in code (1):")
+            .and(predicate::str::contains("src/cache.rs:1:4  // MINDMAP[1]")),
+    );
+
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(temp.path())
+        .args(["--output", "json", "show", "1", "--code"]);
+    cmd.assert().success().stdout(
+        predicate::str::contains("\"code\": [").and(predicate::str::contains("\"line\": 1")),
+    );
+
+    // nothing broken once the citation is updated
+    temp.child("src/cache.rs")
+        .write_str("// MINDMAP[1]\nfn load() {}\n// see MINDMAP[1]\n")?;
+    let mut cmd = mindmap_cmd();
+    cmd.current_dir(temp.path()).args(["scan-code", "src"]);
+    cmd.assert().success();
+
+    temp.close()?;
+    Ok(())
+}
+
+#[test]
+fn integration_cli_dupes() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let file = temp.child("MINDMAP.md");
+    file.write_str(
+        "[1] **AE: Mindmap cache** - Lazy loading of mindmap files\n[2] **WF: Release** - Tag and publish\n[3] **AE: Mindmap caches** - Lazily loads mindmap files [1]\n",
+    )?;
+
+    let mut cmd = mindmap_cmd();
+    cmd.arg("dupes").arg("--file").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "0.73  [1] **AE: Mindmap cache** ≈ [3] **AE: Mindmap caches**",
+        ))
+        .stderr(predicate::str::contains("Merge candidates (1 pair"));
+
+    let mut cmd = mindmap_cmd();
+    cmd.args(["--output", "json", "dupes", "--threshold", "0.9", "--file"])
+        .arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"count\": 0"));
+
+    // add refuses near-duplicates by default, or only warns
+    let mut cmd = mindmap_cmd();
+    cmd.args([
+        "add",
+        "--type",
+        "AE",
+        "--title",
+        "Cache for mindmaps",
+        "--desc",
+        "loads mindmap files lazily",
+        "--check-dupes",
+        "--file",
+    ])
+    .arg(file.path());
+    cmd.assert().failure().stderr(
+        predicate::str::contains("Refusing to add")
+            .and(predicate::str::contains("[1] **AE: Mindmap cache**")),
+    );
+    assert!(!std::fs::read_to_string(file.path())?.contains("[4]"));
+
+    let mut cmd = mindmap_cmd();
+    cmd.args([
+        "add",
+        "--type",
+        "AE",
+        "--title",
+        "Cache for mindmaps",
+        "--desc",
+        "loads mindmap files lazily",
+        "--check-dupes=warn",
+        "--file",
+    ])
+    .arg(file.path());
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("near-duplicate of:"));
+    assert!(std::fs::read_to_string(file.path())?.contains("[4] **AE: Cache for mindmaps**"));
+
+    let mut cmd = mindmap_cmd();
+    cmd.args([
+        "add",
+        "--type",
+        "WF",
+        "--title",
+        "Onboarding",
+        "--desc",
+        "first steps",
+        "--check-dupes",
+        "--file",
+    ])
+    .arg(file.path());
+    cmd.assert().success();
+
+    temp.close()?;
+    Ok(())
+}
+
+#[test]
+fn integration_cli_ranked_search() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let file = temp.child("MINDMAP.md");
+    file.write_str(
+        "[1] **AE: Parser** - reads lines; see the cache [2]\n[2] **AE: MindmapCache** - lazy loading of files, cache size limits\n[3] **WF: Release** - tag and publish the crate\n",
+    )?;
+
+    // best match first, with highlights; partial words and typos match
+    let mut cmd = mindmap_cmd();
+    cmd.args(["search", "cache", "--file"]).arg(file.path());
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let out = String::from_utf8(out)?;
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "[2] **AE: Mindmap«Cache»** - lazy loading of files, «cache» size limits",
+            "[1] **AE: Parser** - reads lines; see the «cache» [2]",
+        ]
+    );
+
+    let mut cmd = mindmap_cmd();
+    cmd.args(["search", "relase", "--file"]).arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("**WF: «Release»**"));
+
+    let mut cmd = mindmap_cmd();
+    cmd.args(["search", "relase", "--exact-match", "--file"])
+        .arg(file.path());
+    cmd.assert().success().stdout(predicate::str::is_empty());
+
+    // qualifiers, phrases, --limit and JSON scores
+    let mut cmd = mindmap_cmd();
+    cmd.args(["search", "title:cache", "--file"])
+        .arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("[1]").not());
+
+    let mut cmd = mindmap_cmd();
+    cmd.args([
+        "--output",
+        "json",
+        "search",
+        "\"lazy loading\" cache",
+        "--limit",
+        "1",
+        "--file",
+    ])
+    .arg(file.path());
+    cmd.assert().success().stdout(
+        predicate::str::contains("\"count\": 1")
+            .and(predicate::str::contains("\"score\": "))
+            .and(predicate::str::contains("«lazy loading»")),
+    );
+
+    // regex mode stays an unranked filter
+    let mut cmd = mindmap_cmd();
+    cmd.args(["search", "[", "--regex-mode", "--file"])
+        .arg(file.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid regex pattern"));
+
+    temp.close()?;
+    Ok(())
+}